@@ -0,0 +1,250 @@
+//! aria2 后端抽象
+//! core 通过 `Aria2Backend` 访问 aria2，不直接依赖具体传输方式与全局端点状态。
+//! `BackendRegistry` 作为 Tauri 托管状态按后端 ID 持有各实现，
+//! 默认实现 `HttpBackend` 持有各自的端点与 `aria2::client::RpcClient`（HTTP / WebSocket JSON-RPC）。
+
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use std::sync::{Arc, RwLock};
+
+use super::client::{
+    self, Aria2FileServers, Aria2GlobalStat, Aria2Peer, Aria2Task, RpcClient, RpcEndpoint,
+    DEFAULT_BACKEND_ID,
+};
+use crate::core::config::RemoteBackendConfig;
+use crate::core::error::{AppError, AppResult};
+
+/// 单个 aria2 实例提供的操作
+#[async_trait]
+pub trait Aria2Backend: Send + Sync {
+    /// 是否由本应用独占（本地 Sidecar）。仅独占的后端会清理 Store 之外的孤儿任务。
+    fn is_exclusive(&self) -> bool;
+
+    /// 推送通知所用的 WebSocket 地址，不提供通知的实现返回 None
+    fn ws_url(&self) -> Option<String> {
+        None
+    }
+
+    async fn add_uri(&self, urls: Vec<String>, options: Option<Value>) -> AppResult<String>;
+    async fn add_torrent(&self, torrent: String, options: Option<Value>) -> AppResult<String>;
+    async fn add_metalink(&self, metalink: String, options: Option<Value>) -> AppResult<Vec<String>>;
+
+    /// 一次性获取活跃、等待（按队列顺序）与已停止的任务
+    async fn tell_all(&self) -> AppResult<Vec<Aria2Task>>;
+    async fn tell_status(&self, gid: String, keys: Vec<&'static str>) -> AppResult<Aria2Task>;
+    /// 按需读取部分字段，不要求满足 Aria2Task 的完整结构
+    async fn tell_status_fields(
+        &self,
+        gid: String,
+        keys: Vec<&'static str>,
+    ) -> AppResult<Map<String, Value>>;
+
+    async fn pause(&self, gid: String) -> AppResult<String>;
+    async fn resume(&self, gid: String) -> AppResult<String>;
+    async fn remove(&self, gid: String) -> AppResult<String>;
+    async fn purge(&self, gid: String) -> AppResult<String>;
+    async fn pause_all(&self) -> AppResult<String>;
+    async fn unpause_all(&self) -> AppResult<String>;
+
+    async fn change_option(&self, gid: String, options: Value) -> AppResult<String>;
+    async fn get_option(&self, gid: String) -> AppResult<Map<String, Value>>;
+    async fn change_global_option(&self, options: Value) -> AppResult<String>;
+    async fn change_position(&self, gid: String, pos: i64, how: &'static str) -> AppResult<i64>;
+
+    async fn get_peers(&self, gid: String) -> AppResult<Vec<Aria2Peer>>;
+    async fn get_servers(&self, gid: String) -> AppResult<Vec<Aria2FileServers>>;
+    async fn get_global_stat(&self) -> AppResult<Aria2GlobalStat>;
+}
+
+/// 基于 JSON-RPC 客户端的实现，端点与 Secret 随实例创建时确定
+pub struct HttpBackend {
+    rpc: RpcClient,
+    exclusive: bool,
+}
+
+impl HttpBackend {
+    /// 本地 Sidecar，始终作为默认后端
+    pub fn local(port: u16, secret: Option<String>) -> Self {
+        Self {
+            rpc: RpcClient::new(DEFAULT_BACKEND_ID, RpcEndpoint::local(port, secret)),
+            exclusive: true,
+        }
+    }
+
+    /// 远程 aria2 守护进程；`id` 为默认后端 ID 时取代本地 Sidecar
+    pub fn remote(id: &str, remote: &RemoteBackendConfig) -> Self {
+        Self {
+            rpc: RpcClient::new(id, RpcEndpoint::from_config(remote)),
+            exclusive: false,
+        }
+    }
+}
+
+#[async_trait]
+impl Aria2Backend for HttpBackend {
+    fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    fn ws_url(&self) -> Option<String> {
+        (self.rpc.backend_id() == DEFAULT_BACKEND_ID).then(|| self.rpc.endpoint().ws_url())
+    }
+
+    async fn add_uri(&self, urls: Vec<String>, options: Option<Value>) -> AppResult<String> {
+        client::add_uri(&self.rpc, urls, options).await
+    }
+
+    async fn add_torrent(&self, torrent: String, options: Option<Value>) -> AppResult<String> {
+        client::add_torrent(&self.rpc, torrent, options).await
+    }
+
+    async fn add_metalink(&self, metalink: String, options: Option<Value>) -> AppResult<Vec<String>> {
+        client::add_metalink(&self.rpc, metalink, options).await
+    }
+
+    async fn tell_all(&self) -> AppResult<Vec<Aria2Task>> {
+        client::get_all_tasks(&self.rpc).await
+    }
+
+    async fn tell_status(&self, gid: String, keys: Vec<&'static str>) -> AppResult<Aria2Task> {
+        client::tell_status(&self.rpc, gid, keys).await
+    }
+
+    async fn tell_status_fields(
+        &self,
+        gid: String,
+        keys: Vec<&'static str>,
+    ) -> AppResult<Map<String, Value>> {
+        client::tell_status_fields(&self.rpc, gid, keys).await
+    }
+
+    async fn pause(&self, gid: String) -> AppResult<String> {
+        client::pause(&self.rpc, gid).await
+    }
+
+    async fn resume(&self, gid: String) -> AppResult<String> {
+        client::resume(&self.rpc, gid).await
+    }
+
+    async fn remove(&self, gid: String) -> AppResult<String> {
+        client::remove(&self.rpc, gid).await
+    }
+
+    async fn purge(&self, gid: String) -> AppResult<String> {
+        client::purge(&self.rpc, gid).await
+    }
+
+    async fn pause_all(&self) -> AppResult<String> {
+        client::pause_all(&self.rpc).await
+    }
+
+    async fn unpause_all(&self) -> AppResult<String> {
+        client::unpause_all(&self.rpc).await
+    }
+
+    async fn change_option(&self, gid: String, options: Value) -> AppResult<String> {
+        client::change_option(&self.rpc, gid, options).await
+    }
+
+    async fn get_option(&self, gid: String) -> AppResult<Map<String, Value>> {
+        client::get_option(&self.rpc, gid).await
+    }
+
+    async fn change_global_option(&self, options: Value) -> AppResult<String> {
+        client::change_global_option(&self.rpc, options).await
+    }
+
+    async fn change_position(&self, gid: String, pos: i64, how: &'static str) -> AppResult<i64> {
+        client::change_position(&self.rpc, gid, pos, how).await
+    }
+
+    async fn get_peers(&self, gid: String) -> AppResult<Vec<Aria2Peer>> {
+        client::get_peers(&self.rpc, gid).await
+    }
+
+    async fn get_servers(&self, gid: String) -> AppResult<Vec<Aria2FileServers>> {
+        client::get_servers(&self.rpc, gid).await
+    }
+
+    async fn get_global_stat(&self) -> AppResult<Aria2GlobalStat> {
+        client::get_global_stat(&self.rpc).await
+    }
+}
+
+/// 按后端 ID 持有的 aria2 实现集合（默认后端始终排在首位）
+#[derive(Default)]
+pub struct BackendRegistry {
+    backends: RwLock<Vec<(String, Arc<dyn Aria2Backend>)>>,
+}
+
+impl BackendRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按配置替换默认后端之外的远程后端。
+    /// 未启用、缺少 ID 或与默认后端 ID 冲突的条目会被忽略。
+    pub fn set_remotes(&self, remotes: &[RemoteBackendConfig]) {
+        let mut registered: Vec<(String, Arc<dyn Aria2Backend>)> = Vec::new();
+        for remote in remotes {
+            let id = remote.id.trim();
+            if !remote.enabled
+                || id.is_empty()
+                || id == DEFAULT_BACKEND_ID
+                || registered.iter().any(|(existing, _)| existing == id)
+            {
+                continue;
+            }
+            registered.push((id.to_string(), Arc::new(HttpBackend::remote(id, remote))));
+        }
+
+        crate::app_info!(
+            "Aria2::Backend",
+            "extra_backends_configured",
+            serde_json::json!({ "ids": registered.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>() })
+        );
+
+        if let Ok(mut guard) = self.backends.write() {
+            guard.retain(|(id, _)| id == DEFAULT_BACKEND_ID);
+            guard.extend(registered);
+        }
+    }
+
+    /// 注册或替换指定 ID 的实现
+    pub fn register(&self, id: &str, backend: Arc<dyn Aria2Backend>) {
+        if let Ok(mut guard) = self.backends.write() {
+            match guard.iter_mut().find(|(existing, _)| existing == id) {
+                Some(entry) => entry.1 = backend,
+                None if id == DEFAULT_BACKEND_ID => guard.insert(0, (id.to_string(), backend)),
+                None => guard.push((id.to_string(), backend)),
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> AppResult<Arc<dyn Aria2Backend>> {
+        self.backends
+            .read()
+            .ok()
+            .and_then(|guard| {
+                guard
+                    .iter()
+                    .find(|(existing, _)| existing == id)
+                    .map(|(_, backend)| backend.clone())
+            })
+            .ok_or_else(|| AppError::aria2(format!("未知的 aria2 后端: {}", id)))
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.backends
+            .read()
+            .map(|guard| guard.iter().map(|(id, _)| id.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.backends
+            .read()
+            .map(|guard| guard.iter().any(|(existing, _)| existing == id))
+            .unwrap_or(false)
+    }
+}
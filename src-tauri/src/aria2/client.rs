@@ -1,35 +1,111 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use std::sync::OnceLock;
-
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::aria2::ws::CallError;
+use crate::core::config::RemoteBackendConfig;
 use crate::core::error::{AppError, AppResult};
 
-static ARIA2_PORT: AtomicU16 = AtomicU16::new(6800);
-static ARIA2_SECRET: tokio::sync::Mutex<Option<String>> = tokio::sync::Mutex::const_new(None);
+/// 默认后端 ID：本地 Sidecar，或远程模式下取代 Sidecar 的远程守护进程
+pub const DEFAULT_BACKEND_ID: &str = "default";
+
+/// 单个 aria2 实例的连接端点（主机、端口、TLS、路径前缀与 Secret）
+#[derive(Debug, Clone)]
+pub struct RpcEndpoint {
+    host: String,
+    port: u16,
+    secure: bool,
+    path_prefix: String,
+    secret: Option<String>,
+}
 
-static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+impl RpcEndpoint {
+    /// 本机 Sidecar
+    pub fn local(port: u16, secret: Option<String>) -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port,
+            secure: false,
+            path_prefix: String::new(),
+            secret: secret.filter(|secret| !secret.is_empty()),
+        }
+    }
 
-pub fn set_aria2_port(port: u16) {
-    ARIA2_PORT.store(port, Ordering::SeqCst);
-    crate::app_info!("Aria2::Client", "port_configured", json!({ "port": port }));
-}
+    pub fn from_config(remote: &RemoteBackendConfig) -> Self {
+        let prefix = remote.path_prefix.trim().trim_end_matches('/');
+        let path_prefix = if prefix.is_empty() || prefix.starts_with('/') {
+            prefix.to_string()
+        } else {
+            format!("/{}", prefix)
+        };
+
+        Self {
+            host: remote.host.trim().to_string(),
+            port: remote.port,
+            secure: remote.use_tls,
+            path_prefix,
+            secret: remote.secret.clone().filter(|secret| !secret.is_empty()),
+        }
+    }
 
-pub async fn set_aria2_secret(secret: String) {
-    let mut s = ARIA2_SECRET.lock().await;
-    *s = Some(secret);
+    fn url(&self, plain_scheme: &str, secure_scheme: &str) -> String {
+        format!(
+            "{}://{}:{}{}/jsonrpc",
+            if self.secure { secure_scheme } else { plain_scheme },
+            self.host,
+            self.port,
+            self.path_prefix
+        )
+    }
+
+    pub fn http_url(&self) -> String {
+        self.url("http", "https")
+    }
+
+    pub fn ws_url(&self) -> String {
+        self.url("ws", "wss")
+    }
+
+    /// aria2 要求作为第一个参数传入的 `token:<secret>`
+    fn token(&self) -> Option<String> {
+        self.secret.as_ref().map(|s| format!("token:{}", s))
+    }
 }
 
-pub fn get_aria2_port() -> u16 {
-    ARIA2_PORT.load(Ordering::SeqCst)
+/// 绑定到单个 aria2 实例的 JSON-RPC 客户端，端点、Secret 与 HTTP 连接池由实例自身持有
+#[derive(Debug, Clone)]
+pub struct RpcClient {
+    backend_id: String,
+    endpoint: RpcEndpoint,
+    http: reqwest::Client,
 }
 
-fn get_client() -> &'static reqwest::Client {
-    CLIENT.get_or_init(reqwest::Client::new)
+impl RpcClient {
+    pub fn new(backend_id: impl Into<String>, endpoint: RpcEndpoint) -> Self {
+        Self {
+            backend_id: backend_id.into(),
+            endpoint,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn backend_id(&self) -> &str {
+        &self.backend_id
+    }
+
+    pub fn endpoint(&self) -> &RpcEndpoint {
+        &self.endpoint
+    }
+
+    /// 仅默认后端维持 WebSocket 长连接
+    fn uses_websocket(&self) -> bool {
+        self.backend_id == DEFAULT_BACKEND_ID
+    }
 }
 
+static REQUEST_SEQ: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Aria2Task {
     pub gid: String,
@@ -50,6 +126,82 @@ pub struct Aria2Task {
     pub error_message: Option<String>,
     pub dir: String,
     pub files: Vec<Aria2File>,
+    #[serde(rename = "numSeeders", default)]
+    pub num_seeders: Option<String>,
+    #[serde(default)]
+    pub connections: Option<String>,
+    #[serde(rename = "infoHash", default)]
+    pub info_hash: Option<String>,
+    #[serde(default)]
+    pub bittorrent: Option<Aria2BitTorrent>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Aria2BitTorrent {
+    #[serde(rename = "announceList", default)]
+    pub announce_list: Vec<Vec<String>>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(rename = "creationDate", default)]
+    pub creation_date: Option<u64>,
+    #[serde(default)]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub info: Option<Aria2BitTorrentInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Aria2BitTorrentInfo {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Aria2Peer {
+    #[serde(rename = "peerId")]
+    pub peer_id: String,
+    pub ip: String,
+    pub port: String,
+    pub bitfield: String,
+    #[serde(rename = "amChoking")]
+    pub am_choking: String,
+    #[serde(rename = "peerChoking")]
+    pub peer_choking: String,
+    #[serde(rename = "downloadSpeed")]
+    pub download_speed: String,
+    #[serde(rename = "uploadSpeed")]
+    pub upload_speed: String,
+    pub seeder: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Aria2FileServers {
+    pub index: String,
+    pub servers: Vec<Aria2Server>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Aria2Server {
+    pub uri: String,
+    #[serde(rename = "currentUri")]
+    pub current_uri: String,
+    #[serde(rename = "downloadSpeed")]
+    pub download_speed: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Aria2GlobalStat {
+    #[serde(rename = "downloadSpeed")]
+    pub download_speed: String,
+    #[serde(rename = "uploadSpeed")]
+    pub upload_speed: String,
+    #[serde(rename = "numActive")]
+    pub num_active: String,
+    #[serde(rename = "numWaiting")]
+    pub num_waiting: String,
+    #[serde(rename = "numStopped")]
+    pub num_stopped: String,
+    #[serde(rename = "numStoppedTotal")]
+    pub num_stopped_total: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -69,36 +221,31 @@ pub struct Aria2Uri {
     pub status: String,
 }
 
-async fn send_rpc_request<T>(method: &str, params: Vec<Value>) -> AppResult<T>
-where
-    T: serde::de::DeserializeOwned,
-{
-    let client = get_client();
+fn next_request_id() -> String {
+    format!("mua-app-{}", REQUEST_SEQ.fetch_add(1, Ordering::Relaxed))
+}
 
-    // Inject Token if exists
-    let mut final_params = params;
-    {
-        let secret_guard = ARIA2_SECRET.lock().await;
-        if let Some(secret) = secret_guard.as_ref() {
-            // Aria2 requires token:<secret> as the FIRST parameter
-            final_params.insert(0, json!(format!("token:{}", secret)));
+/// 发送原始 JSON-RPC 请求体，返回完整响应体。
+/// 默认后端的 WebSocket 已连接时优先走长连接，请求未能发出时回退到 HTTP POST。
+/// 请求已发出后超时或断开时直接返回错误：aria2 可能已经执行（如 addUri），重发会产生重复任务。
+async fn dispatch(rpc: &RpcClient, payload: Value) -> AppResult<Value> {
+    if rpc.uses_websocket() && crate::aria2::ws::is_connected() {
+        match crate::aria2::ws::call(&payload).await {
+            Ok(body) => return Ok(body),
+            Err(CallError::Sent(e)) => return Err(e),
+            Err(CallError::NotSent(e)) => {
+                crate::app_debug!(
+                    "Aria2::Client",
+                    "ws_call_failed_fallback_http",
+                    json!({ "error": e.to_string() })
+                );
+            }
         }
     }
 
-    let payload = json!({
-        "jsonrpc": "2.0",
-        "id": "mua-app",
-        "method": method,
-        "params": final_params
-    });
-
-    crate::app_debug!("Aria2::Client", "rpc_request", json!({ "method": method }));
-
-    let port = get_aria2_port();
-    let url = format!("http://localhost:{}/jsonrpc", port);
-
-    let response = client
-        .post(&url)
+    let response = rpc
+        .http
+        .post(rpc.endpoint.http_url())
         .json(&payload)
         .send()
         .await
@@ -111,13 +258,40 @@ where
         )));
     }
 
-    let body: Value = response
+    response
         .json()
         .await
-        .map_err(|e| AppError::aria2(e.to_string()))?;
+        .map_err(|e| AppError::aria2(e.to_string()))
+}
+
+async fn send_rpc_request<T>(rpc: &RpcClient, method: &str, params: Vec<Value>) -> AppResult<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    // Inject Token if exists
+    let mut final_params = params;
+    if let Some(token) = rpc.endpoint.token() {
+        // Aria2 requires token:<secret> as the FIRST parameter
+        final_params.insert(0, json!(token));
+    }
+
+    let payload = json!({
+        "jsonrpc": "2.0",
+        "id": next_request_id(),
+        "method": method,
+        "params": final_params
+    });
+
+    crate::app_debug!(
+        "Aria2::Client",
+        "rpc_request",
+        json!({ "method": method, "backend": rpc.backend_id })
+    );
+
+    let body = dispatch(rpc, payload).await?;
 
     if let Some(error) = body.get("error") {
-        return Err(AppError::aria2(error.to_string()));
+        return Err(AppError::aria2_rpc(error));
     }
 
     if let Some(result) = body.get("result") {
@@ -130,32 +304,45 @@ where
     }
 }
 
-pub async fn add_uri(urls: Vec<String>, options: Option<Value>) -> AppResult<String> {
+pub async fn add_uri(rpc: &RpcClient, urls: Vec<String>, options: Option<Value>) -> AppResult<String> {
     let mut params = vec![json!(urls)];
     if let Some(opts) = options {
         params.push(json!(opts));
     }
 
-    send_rpc_request::<String>("aria2.addUri", params).await
+    send_rpc_request::<String>(rpc, "aria2.addUri", params).await
 }
 
-pub async fn add_torrent(torrent: String, options: Option<Value>) -> AppResult<String> {
+pub async fn add_torrent(
+    rpc: &RpcClient,
+    torrent: String,
+    options: Option<Value>,
+) -> AppResult<String> {
     let mut params = vec![json!(torrent), json!([])];
     if let Some(opts) = options {
         params.push(json!(opts));
     }
 
-    send_rpc_request::<String>("aria2.addTorrent", params).await
+    send_rpc_request::<String>(rpc, "aria2.addTorrent", params).await
 }
 
-pub async fn get_all_tasks() -> AppResult<Vec<Aria2Task>> {
-    let client = get_client();
+/// 添加 Metalink（Base64 编码的文件内容），每个文件对应一个任务，返回全部 GID
+pub async fn add_metalink(
+    rpc: &RpcClient,
+    metalink: String,
+    options: Option<Value>,
+) -> AppResult<Vec<String>> {
+    let mut params = vec![json!(metalink)];
+    if let Some(opts) = options {
+        params.push(json!(opts));
+    }
 
-    // 获取 RPC Secret (如果配置了)
-    let token: Option<String> = {
-        let guard = ARIA2_SECRET.lock().await;
-        guard.as_ref().map(|s| format!("token:{}", s))
-    };
+    send_rpc_request::<Vec<String>>(rpc, "aria2.addMetalink", params).await
+}
+
+pub async fn get_all_tasks(rpc: &RpcClient) -> AppResult<Vec<Aria2Task>> {
+    // RPC Secret (如果配置了)
+    let token = rpc.endpoint.token();
 
     // 构建 multicall 参数，每个子方法都需要注入 token
     let params = vec![
@@ -183,110 +370,148 @@ pub async fn get_all_tasks() -> AppResult<Vec<Aria2Task>> {
 
     let payload = json!({
         "jsonrpc": "2.0",
-        "id": "mua-app-multicall",
+        "id": next_request_id(),
         "method": "system.multicall",
         "params": [params]
     });
 
     // Multicall 具有特定的返回结构，不进行转换很难清晰地使用通用辅助函数
-    let port = get_aria2_port();
-    let url = format!("http://localhost:{}/jsonrpc", port);
-
-    match client.post(&url).json(&payload).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                let body: Value = response
-                    .json()
-                    .await
-                    .map_err(|e| AppError::aria2(e.to_string()))?;
-
-                if let Some(results) = body.get("result") {
-                    if let Some(results_array) = results.as_array() {
-                        let mut all_tasks = Vec::new();
-
-                        for result_value in results_array {
-                            if let Some(tasks_val) = result_value.get(0) {
-                                // 每个调用的结果都包裹在一个数组中 [result]
-                                if let Ok(tasks) =
-                                    serde_json::from_value::<Vec<Aria2Task>>(tasks_val.clone())
-                                {
-                                    all_tasks.extend(tasks);
-                                } else if let Ok(tasks) =
-                                    serde_json::from_value::<Vec<Aria2Task>>(result_value.clone())
-                                {
-                                    all_tasks.extend(tasks);
-                                }
-                            }
-                        }
-
-                        Ok(all_tasks)
-                    } else {
-                        Err(AppError::aria2("Result is not an array"))
+    let body = dispatch(rpc, payload).await?;
+
+    if let Some(results) = body.get("result") {
+        if let Some(results_array) = results.as_array() {
+            let mut all_tasks = Vec::new();
+
+            for result_value in results_array {
+                if let Some(tasks_val) = result_value.get(0) {
+                    // 每个调用的结果都包裹在一个数组中 [result]
+                    if let Ok(tasks) = serde_json::from_value::<Vec<Aria2Task>>(tasks_val.clone())
+                    {
+                        all_tasks.extend(tasks);
+                    } else if let Ok(tasks) =
+                        serde_json::from_value::<Vec<Aria2Task>>(result_value.clone())
+                    {
+                        all_tasks.extend(tasks);
                     }
-                } else if let Some(error) = body.get("error") {
-                    Err(AppError::aria2(error.to_string()))
-                } else {
-                    Err(AppError::aria2("Unknown response format"))
                 }
-            } else {
-                Err(AppError::aria2(format!(
-                    "HTTP Error: {}",
-                    response.status()
-                )))
             }
+
+            Ok(all_tasks)
+        } else {
+            Err(AppError::aria2("Result is not an array"))
         }
-        Err(e) => Err(AppError::aria2(e.to_string())),
+    } else if let Some(error) = body.get("error") {
+        Err(AppError::aria2_rpc(error))
+    } else {
+        Err(AppError::aria2("Unknown response format"))
     }
 }
 
-pub async fn tell_active(keys: Vec<&str>) -> AppResult<Vec<Aria2Task>> {
-    send_rpc_request::<Vec<Aria2Task>>("aria2.tellActive", vec![json!(keys)]).await
+pub async fn tell_active(rpc: &RpcClient, keys: Vec<&str>) -> AppResult<Vec<Aria2Task>> {
+    send_rpc_request::<Vec<Aria2Task>>(rpc, "aria2.tellActive", vec![json!(keys)]).await
 }
 
-pub async fn tell_waiting(offset: usize, num: usize, keys: Vec<&str>) -> AppResult<Vec<Aria2Task>> {
+pub async fn tell_waiting(
+    rpc: &RpcClient,
+    offset: usize,
+    num: usize,
+    keys: Vec<&str>,
+) -> AppResult<Vec<Aria2Task>> {
     send_rpc_request::<Vec<Aria2Task>>(
+        rpc,
         "aria2.tellWaiting",
         vec![json!(offset), json!(num), json!(keys)],
     )
     .await
 }
 
-pub async fn tell_stopped(offset: usize, num: usize, keys: Vec<&str>) -> AppResult<Vec<Aria2Task>> {
+pub async fn tell_stopped(
+    rpc: &RpcClient,
+    offset: usize,
+    num: usize,
+    keys: Vec<&str>,
+) -> AppResult<Vec<Aria2Task>> {
     send_rpc_request::<Vec<Aria2Task>>(
+        rpc,
         "aria2.tellStopped",
         vec![json!(offset), json!(num), json!(keys)],
     )
     .await
 }
 
-pub async fn tell_status(gid: String, keys: Vec<&str>) -> AppResult<Aria2Task> {
-    send_rpc_request::<Aria2Task>("aria2.tellStatus", vec![json!(gid), json!(keys)]).await
+pub async fn tell_status(rpc: &RpcClient, gid: String, keys: Vec<&str>) -> AppResult<Aria2Task> {
+    send_rpc_request::<Aria2Task>(rpc, "aria2.tellStatus", vec![json!(gid), json!(keys)]).await
+}
+
+pub async fn pause(rpc: &RpcClient, gid: String) -> AppResult<String> {
+    send_rpc_request::<String>(rpc, "aria2.pause", vec![json!(gid)]).await
+}
+
+pub async fn resume(rpc: &RpcClient, gid: String) -> AppResult<String> {
+    send_rpc_request::<String>(rpc, "aria2.unpause", vec![json!(gid)]).await
+}
+
+pub async fn remove(rpc: &RpcClient, gid: String) -> AppResult<String> {
+    send_rpc_request::<String>(rpc, "aria2.remove", vec![json!(gid)]).await
+}
+
+pub async fn purge(rpc: &RpcClient, gid: String) -> AppResult<String> {
+    send_rpc_request::<String>(rpc, "aria2.removeDownloadResult", vec![json!(gid)]).await
+}
+
+pub async fn pause_all(rpc: &RpcClient) -> AppResult<String> {
+    send_rpc_request::<String>(rpc, "aria2.pauseAll", vec![]).await
+}
+
+pub async fn unpause_all(rpc: &RpcClient) -> AppResult<String> {
+    send_rpc_request::<String>(rpc, "aria2.unpauseAll", vec![]).await
 }
 
-pub async fn pause(gid: String) -> AppResult<String> {
-    send_rpc_request::<String>("aria2.pause", vec![json!(gid)]).await
+pub async fn change_global_option(rpc: &RpcClient, options: Value) -> AppResult<String> {
+    send_rpc_request::<String>(rpc, "aria2.changeGlobalOption", vec![options]).await
 }
 
-pub async fn resume(gid: String) -> AppResult<String> {
-    send_rpc_request::<String>("aria2.unpause", vec![json!(gid)]).await
+pub async fn change_option(rpc: &RpcClient, gid: String, options: Value) -> AppResult<String> {
+    send_rpc_request::<String>(rpc, "aria2.changeOption", vec![json!(gid), options]).await
 }
 
-pub async fn remove(gid: String) -> AppResult<String> {
-    send_rpc_request::<String>("aria2.remove", vec![json!(gid)]).await
+/// 调整等待队列中的位置，`how` 为 POS_SET / POS_CUR / POS_END，返回新位置
+pub async fn change_position(rpc: &RpcClient, gid: String, pos: i64, how: &str) -> AppResult<i64> {
+    send_rpc_request::<i64>(
+        rpc,
+        "aria2.changePosition",
+        vec![json!(gid), json!(pos), json!(how)],
+    )
+    .await
 }
 
-pub async fn purge(gid: String) -> AppResult<String> {
-    send_rpc_request::<String>("aria2.removeDownloadResult", vec![json!(gid)]).await
+pub async fn get_option(rpc: &RpcClient, gid: String) -> AppResult<serde_json::Map<String, Value>> {
+    send_rpc_request::<serde_json::Map<String, Value>>(rpc, "aria2.getOption", vec![json!(gid)])
+        .await
 }
 
-pub async fn pause_all() -> AppResult<String> {
-    send_rpc_request::<String>("aria2.pauseAll", vec![]).await
+pub async fn get_global_stat(rpc: &RpcClient) -> AppResult<Aria2GlobalStat> {
+    send_rpc_request::<Aria2GlobalStat>(rpc, "aria2.getGlobalStat", vec![]).await
 }
 
-pub async fn unpause_all() -> AppResult<String> {
-    send_rpc_request::<String>("aria2.unpauseAll", vec![]).await
+pub async fn get_peers(rpc: &RpcClient, gid: String) -> AppResult<Vec<Aria2Peer>> {
+    send_rpc_request::<Vec<Aria2Peer>>(rpc, "aria2.getPeers", vec![json!(gid)]).await
 }
 
-pub async fn change_global_option(options: Value) -> AppResult<String> {
-    send_rpc_request::<String>("aria2.changeGlobalOption", vec![options]).await
+pub async fn get_servers(rpc: &RpcClient, gid: String) -> AppResult<Vec<Aria2FileServers>> {
+    send_rpc_request::<Vec<Aria2FileServers>>(rpc, "aria2.getServers", vec![json!(gid)]).await
+}
+
+/// 按需读取 tellStatus 的部分字段（不要求满足 Aria2Task 的完整结构）
+pub async fn tell_status_fields(
+    rpc: &RpcClient,
+    gid: String,
+    keys: Vec<&str>,
+) -> AppResult<serde_json::Map<String, Value>> {
+    send_rpc_request::<serde_json::Map<String, Value>>(
+        rpc,
+        "aria2.tellStatus",
+        vec![json!(gid), json!(keys)],
+    )
+    .await
 }
@@ -0,0 +1,718 @@
+//! 测试用的进程内 aria2 JSON-RPC 模拟服务
+//! 实现客户端用到的 RPC 子集，任务保存在内存中，可通过脚本驱动状态迁移，
+//! 使 sync_tasks、智能恢复、批量删除等逻辑无需真实 aria2 即可测试。
+//! 每个模拟服务监听独立端口，`registry()` 返回指向该端口的后端，测试之间互不影响。
+
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::backend::{BackendRegistry, HttpBackend};
+use super::client::DEFAULT_BACKEND_ID;
+use crate::core::config::RemoteBackendConfig;
+use crate::core::error::ARIA2_ERR_NOT_FOUND;
+use crate::core::store::PersistedTask;
+use crate::core::types::TaskState;
+
+/// 模拟的单个下载任务
+#[derive(Debug, Clone)]
+pub(crate) struct MockTask {
+    pub gid: String,
+    pub status: String,
+    pub dir: String,
+    pub path: String,
+    pub uris: Vec<String>,
+    pub total_length: u64,
+    pub completed_length: u64,
+    pub download_speed: u64,
+    pub upload_speed: u64,
+    pub error_code: Option<u32>,
+    pub error_message: Option<String>,
+    pub options: Map<String, Value>,
+}
+
+impl MockTask {
+    pub fn new(gid: &str, status: &str) -> Self {
+        Self {
+            gid: gid.to_string(),
+            status: status.to_string(),
+            dir: String::new(),
+            path: String::new(),
+            uris: Vec::new(),
+            total_length: 0,
+            completed_length: 0,
+            download_speed: 0,
+            upload_speed: 0,
+            error_code: None,
+            error_message: None,
+            options: Map::new(),
+        }
+    }
+
+    pub fn with_file(mut self, dir: &str, filename: &str) -> Self {
+        self.dir = dir.to_string();
+        self.path = format!("{}/{}", dir.trim_end_matches('/'), filename);
+        self
+    }
+
+    pub fn with_progress(mut self, completed: u64, total: u64, speed: u64) -> Self {
+        self.completed_length = completed;
+        self.total_length = total;
+        self.download_speed = speed;
+        self
+    }
+
+    fn is_stopped(&self) -> bool {
+        matches!(self.status.as_str(), "complete" | "error" | "removed")
+    }
+
+    fn to_json(&self, keys: Option<&[String]>) -> Value {
+        let uris: Vec<Value> = self
+            .uris
+            .iter()
+            .map(|uri| json!({ "uri": uri, "status": "used" }))
+            .collect();
+
+        let mut value = json!({
+            "gid": self.gid,
+            "status": self.status,
+            "totalLength": self.total_length.to_string(),
+            "completedLength": self.completed_length.to_string(),
+            "downloadSpeed": self.download_speed.to_string(),
+            "uploadLength": "0",
+            "uploadSpeed": self.upload_speed.to_string(),
+            "connections": "0",
+            "dir": self.dir,
+            "files": [{
+                "index": "1",
+                "path": self.path,
+                "length": self.total_length.to_string(),
+                "completedLength": self.completed_length.to_string(),
+                "selected": "true",
+                "uris": uris,
+            }],
+        });
+
+        if let Some(code) = self.error_code {
+            value["errorCode"] = json!(code.to_string());
+        }
+        if let Some(message) = &self.error_message {
+            value["errorMessage"] = json!(message);
+        }
+
+        match keys {
+            Some(keys) if !keys.is_empty() => {
+                let map = value.as_object().cloned().unwrap_or_default();
+                Value::Object(
+                    map.into_iter()
+                        .filter(|(k, _)| keys.iter().any(|key| key == k))
+                        .collect(),
+                )
+            }
+            _ => value,
+        }
+    }
+}
+
+/// 脚本化的状态迁移：每次 `system.multicall`（即一轮 sync）消费一步
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ScriptStep {
+    pub status: Option<String>,
+    pub completed_length: Option<u64>,
+    pub download_speed: Option<u64>,
+    pub error: Option<(u32, String)>,
+}
+
+impl ScriptStep {
+    pub fn status(status: &str) -> Self {
+        Self {
+            status: Some(status.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn progress(mut self, completed: u64, speed: u64) -> Self {
+        self.completed_length = Some(completed);
+        self.download_speed = Some(speed);
+        self
+    }
+
+    /// 以 aria2 错误码及信息失败
+    pub fn error(mut self, code: u32, message: &str) -> Self {
+        self.error = Some((code, message.to_string()));
+        self
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    /// 按加入顺序保存，tellWaiting 依此返回队列顺序
+    tasks: Vec<MockTask>,
+    next_gid: u64,
+    scripts: HashMap<String, VecDeque<ScriptStep>>,
+    /// 注入的一次性失败（方法名 -> 错误信息）
+    failures: HashMap<String, String>,
+    calls: Vec<(String, Vec<Value>)>,
+    global_options: Map<String, Value>,
+}
+
+fn rpc_error(code: i64, message: impl Into<String>) -> Value {
+    json!({ "code": code, "message": message.into() })
+}
+
+fn not_found(gid: &str) -> Value {
+    rpc_error(ARIA2_ERR_NOT_FOUND, format!("GID {} is not found", gid))
+}
+
+fn param_str(params: &[Value], idx: usize) -> String {
+    params
+        .get(idx)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn param_keys(params: &[Value], idx: usize) -> Option<Vec<String>> {
+    params.get(idx).and_then(|v| {
+        v.as_array().map(|keys| {
+            keys.iter()
+                .filter_map(|k| k.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+    })
+}
+
+impl MockState {
+    fn find_mut(&mut self, gid: &str) -> Option<&mut MockTask> {
+        self.tasks.iter_mut().find(|t| t.gid == gid)
+    }
+
+    fn next_gid(&mut self) -> String {
+        self.next_gid += 1;
+        format!("{:016x}", 0xa000_0000_0000_0000u64 + self.next_gid)
+    }
+
+    fn add_task(&mut self, uris: Vec<String>, options: Option<&Value>, fallback_name: &str) -> String {
+        let options = options.and_then(|o| o.as_object()).cloned().unwrap_or_default();
+        let dir = options
+            .get("dir")
+            .and_then(|v| v.as_str())
+            .unwrap_or("/downloads")
+            .to_string();
+        let out = options
+            .get("out")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| {
+                uris.first()
+                    .and_then(|u| u.rsplit('/').next())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string())
+            })
+            .unwrap_or_else(|| fallback_name.to_string());
+
+        let status = if options.get("pause").and_then(|v| v.as_str()) == Some("true") {
+            "paused"
+        } else {
+            "active"
+        };
+
+        let gid = self.next_gid();
+        let mut task = MockTask::new(&gid, status).with_file(&dir, &out);
+        task.uris = uris;
+        task.options = options;
+        self.tasks.push(task);
+        gid
+    }
+
+    /// 每轮 sync 推进一步脚本
+    fn advance_scripts(&mut self) {
+        let gids: Vec<String> = self.scripts.keys().cloned().collect();
+        for gid in gids {
+            let step = self.scripts.get_mut(&gid).and_then(|steps| steps.pop_front());
+            let Some(step) = step else { continue };
+            if let Some(task) = self.find_mut(&gid) {
+                if let Some(status) = step.status {
+                    task.status = status;
+                }
+                if let Some(completed) = step.completed_length {
+                    task.completed_length = completed;
+                }
+                if let Some(speed) = step.download_speed {
+                    task.download_speed = speed;
+                }
+                if let Some((code, message)) = step.error {
+                    task.error_code = Some(code);
+                    task.error_message = Some(message);
+                }
+            }
+        }
+        self.scripts.retain(|_, steps| !steps.is_empty());
+    }
+
+    fn list(&self, filter: impl Fn(&MockTask) -> bool, params: &[Value], paged: bool) -> Value {
+        let (offset, num, keys_idx) = if paged {
+            (
+                params.first().and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+                params.get(1).and_then(|v| v.as_u64()).unwrap_or(1000) as usize,
+                2,
+            )
+        } else {
+            (0, usize::MAX, 0)
+        };
+        let keys = param_keys(params, keys_idx);
+
+        Value::Array(
+            self.tasks
+                .iter()
+                .filter(|t| filter(t))
+                .skip(offset)
+                .take(num)
+                .map(|t| t.to_json(keys.as_deref()))
+                .collect(),
+        )
+    }
+
+    fn call(&mut self, method: &str, params: Vec<Value>) -> Result<Value, Value> {
+        // 去掉 token:<secret> 参数
+        let params: Vec<Value> = match params.first().and_then(|v| v.as_str()) {
+            Some(first) if first.starts_with("token:") => params[1..].to_vec(),
+            _ => params,
+        };
+
+        self.calls.push((method.to_string(), params.clone()));
+
+        if let Some(message) = self.failures.remove(method) {
+            return Err(rpc_error(2, message));
+        }
+
+        match method {
+            "system.multicall" => {
+                self.advance_scripts();
+                let calls = params
+                    .first()
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                let results = calls
+                    .into_iter()
+                    .map(|c| {
+                        let name = c.get("methodName").and_then(|v| v.as_str()).unwrap_or("");
+                        let sub = c
+                            .get("params")
+                            .and_then(|v| v.as_array())
+                            .cloned()
+                            .unwrap_or_default();
+                        match self.call(name, sub) {
+                            Ok(result) => json!([result]),
+                            Err(error) => error,
+                        }
+                    })
+                    .collect();
+                Ok(Value::Array(results))
+            }
+            "aria2.addUri" => {
+                let uris: Vec<String> = params
+                    .first()
+                    .and_then(|v| v.as_array())
+                    .map(|list| {
+                        list.iter()
+                            .filter_map(|u| u.as_str().map(|s| s.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if uris.is_empty() {
+                    return Err(rpc_error(ARIA2_ERR_NOT_FOUND, "No URI to download."));
+                }
+                Ok(json!(self.add_task(uris, params.get(1), "index.html")))
+            }
+            "aria2.addTorrent" => {
+                if param_str(&params, 0).is_empty() {
+                    return Err(rpc_error(ARIA2_ERR_NOT_FOUND, "Torrent data is empty."));
+                }
+                Ok(json!(self.add_task(vec![], params.get(2), "torrent")))
+            }
+            "aria2.addMetalink" => {
+                if param_str(&params, 0).is_empty() {
+                    return Err(rpc_error(ARIA2_ERR_NOT_FOUND, "Metalink data is empty."));
+                }
+                Ok(json!([self.add_task(vec![], params.get(1), "metalink")]))
+            }
+            "aria2.tellStatus" => {
+                let gid = param_str(&params, 0);
+                let keys = param_keys(&params, 1);
+                self.tasks
+                    .iter()
+                    .find(|t| t.gid == gid)
+                    .map(|t| t.to_json(keys.as_deref()))
+                    .ok_or_else(|| not_found(&gid))
+            }
+            "aria2.tellActive" => Ok(self.list(|t| t.status == "active", &params, false)),
+            "aria2.tellWaiting" => Ok(self.list(
+                |t| t.status == "waiting" || t.status == "paused",
+                &params,
+                true,
+            )),
+            "aria2.tellStopped" => Ok(self.list(MockTask::is_stopped, &params, true)),
+            "aria2.pause" | "aria2.forcePause" => {
+                let gid = param_str(&params, 0);
+                match self.find_mut(&gid) {
+                    Some(t) if t.status == "active" || t.status == "waiting" => {
+                        t.status = "paused".to_string();
+                        t.download_speed = 0;
+                        Ok(json!(gid))
+                    }
+                    _ => Err(not_found(&gid)),
+                }
+            }
+            "aria2.unpause" => {
+                let gid = param_str(&params, 0);
+                match self.find_mut(&gid) {
+                    Some(t) if t.status == "paused" => {
+                        t.status = "waiting".to_string();
+                        Ok(json!(gid))
+                    }
+                    _ => Err(not_found(&gid)),
+                }
+            }
+            "aria2.remove" | "aria2.forceRemove" => {
+                let gid = param_str(&params, 0);
+                match self.find_mut(&gid) {
+                    Some(t) if !t.is_stopped() => {
+                        t.status = "removed".to_string();
+                        t.download_speed = 0;
+                        Ok(json!(gid))
+                    }
+                    _ => Err(not_found(&gid)),
+                }
+            }
+            "aria2.removeDownloadResult" => {
+                let gid = param_str(&params, 0);
+                match self.tasks.iter().position(|t| t.gid == gid && t.is_stopped()) {
+                    Some(idx) => {
+                        self.tasks.remove(idx);
+                        Ok(json!("OK"))
+                    }
+                    None => Err(not_found(&gid)),
+                }
+            }
+            "aria2.pauseAll" | "aria2.forcePauseAll" => {
+                for t in self.tasks.iter_mut() {
+                    if t.status == "active" || t.status == "waiting" {
+                        t.status = "paused".to_string();
+                        t.download_speed = 0;
+                    }
+                }
+                Ok(json!("OK"))
+            }
+            "aria2.unpauseAll" => {
+                for t in self.tasks.iter_mut().filter(|t| t.status == "paused") {
+                    t.status = "waiting".to_string();
+                }
+                Ok(json!("OK"))
+            }
+            "aria2.changePosition" => {
+                let gid = param_str(&params, 0);
+                let pos = params.get(1).and_then(|v| v.as_i64()).unwrap_or(0);
+                let how = param_str(&params, 2);
+                let mut queue: Vec<String> = self
+                    .tasks
+                    .iter()
+                    .filter(|t| t.status == "waiting" || t.status == "paused")
+                    .map(|t| t.gid.clone())
+                    .collect();
+                let Some(current) = queue.iter().position(|g| *g == gid) else {
+                    return Err(not_found(&gid));
+                };
+                let last = queue.len() as i64 - 1;
+                let target = match how.as_str() {
+                    "POS_SET" => pos,
+                    "POS_CUR" => current as i64 + pos,
+                    "POS_END" => last + pos,
+                    _ => {
+                        return Err(rpc_error(
+                            ARIA2_ERR_NOT_FOUND,
+                            format!("Invalid how: {}", how),
+                        ))
+                    }
+                }
+                .clamp(0, last) as usize;
+
+                // 在任务列表中移动，使 tellWaiting 按新顺序返回
+                queue.remove(current);
+                let Some(idx) = self.tasks.iter().position(|t| t.gid == gid) else {
+                    return Err(not_found(&gid));
+                };
+                let task = self.tasks.remove(idx);
+                let insert_at = queue
+                    .get(target)
+                    .and_then(|next| self.tasks.iter().position(|t| t.gid == *next))
+                    .unwrap_or(self.tasks.len());
+                self.tasks.insert(insert_at, task);
+                Ok(json!(target))
+            }
+            "aria2.changeGlobalOption" => {
+                if let Some(options) = params.first().and_then(|v| v.as_object()) {
+                    self.global_options
+                        .extend(options.iter().map(|(k, v)| (k.clone(), v.clone())));
+                }
+                Ok(json!("OK"))
+            }
+            "aria2.changeOption" => {
+                let gid = param_str(&params, 0);
+                let options = params.get(1).and_then(|v| v.as_object()).cloned();
+                match (self.find_mut(&gid), options) {
+                    (Some(t), Some(options)) if !t.is_stopped() => {
+                        t.options.extend(options);
+                        Ok(json!("OK"))
+                    }
+                    _ => Err(not_found(&gid)),
+                }
+            }
+            "aria2.getGlobalStat" => {
+                let count = |f: &dyn Fn(&MockTask) -> bool| {
+                    self.tasks.iter().filter(|t| f(t)).count().to_string()
+                };
+                let speed: u64 = self
+                    .tasks
+                    .iter()
+                    .filter(|t| t.status == "active")
+                    .map(|t| t.download_speed)
+                    .sum();
+                Ok(json!({
+                    "downloadSpeed": speed.to_string(),
+                    "uploadSpeed": "0",
+                    "numActive": count(&|t| t.status == "active"),
+                    "numWaiting": count(&|t| t.status == "waiting" || t.status == "paused"),
+                    "numStopped": count(&|t| t.is_stopped()),
+                    "numStoppedTotal": count(&|t| t.is_stopped()),
+                }))
+            }
+            _ => Err(rpc_error(-32601, format!("No such method: {}", method))),
+        }
+    }
+
+    fn handle(&mut self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(|v| v.as_str()).unwrap_or("");
+        let params = request
+            .get("params")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        match self.call(method, params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        }
+    }
+}
+
+fn serve_connection(stream: TcpStream, state: &Mutex<MockState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    let mut content_length = 0usize;
+
+    // 请求行 + 头部
+    reader.read_line(&mut line)?;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+    let request: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let response = match state.lock() {
+        Ok(mut guard) => guard.handle(&request),
+        Err(_) => json!({ "jsonrpc": "2.0", "error": rpc_error(-32603, "mock poisoned") }),
+    }
+    .to_string();
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.len(),
+        response
+    )?;
+    stream.flush()
+}
+
+/// 运行中的模拟服务，Drop 时停止监听
+pub(crate) struct MockAria2 {
+    port: u16,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl MockAria2 {
+    /// 在随机端口上启动模拟服务
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock aria2");
+        let port = listener.local_addr().expect("mock aria2 addr").port();
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread_state = state.clone();
+        let thread_shutdown = shutdown.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_shutdown.load(Ordering::Acquire) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = thread_state.clone();
+                std::thread::spawn(move || {
+                    let _ = serve_connection(stream, &state);
+                });
+            }
+        });
+
+        Self {
+            port,
+            state,
+            shutdown,
+        }
+    }
+
+    /// 指向本服务的默认后端注册表
+    pub fn registry(&self) -> BackendRegistry {
+        let registry = BackendRegistry::new();
+        registry.register(DEFAULT_BACKEND_ID, Arc::new(HttpBackend::local(self.port, None)));
+        registry
+    }
+
+    /// 将本模拟服务作为远程后端 `id` 注册到已有的注册表中
+    pub fn register_remote(&self, registry: &BackendRegistry, id: &str) {
+        let config = RemoteBackendConfig {
+            id: id.to_string(),
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port: self.port,
+            ..RemoteBackendConfig::default()
+        };
+        registry.register(id, Arc::new(HttpBackend::remote(id, &config)));
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut MockState) -> R) -> R {
+        let mut guard = self.state.lock().expect("mock aria2 state");
+        f(&mut guard)
+    }
+
+    pub fn insert(&self, task: MockTask) {
+        self.with_state(|s| s.tasks.push(task));
+    }
+
+    pub fn task(&self, gid: &str) -> Option<MockTask> {
+        self.with_state(|s| s.tasks.iter().find(|t| t.gid == gid).cloned())
+    }
+
+    pub fn tasks(&self) -> Vec<MockTask> {
+        self.with_state(|s| s.tasks.clone())
+    }
+
+    pub fn set_status(&self, gid: &str, status: &str) {
+        self.with_state(|s| {
+            if let Some(task) = s.find_mut(gid) {
+                task.status = status.to_string();
+            }
+        });
+    }
+
+    /// 模拟 aria2 丢失任务（如重启后会话未保存）
+    pub fn forget(&self, gid: &str) {
+        self.with_state(|s| s.tasks.retain(|t| t.gid != gid));
+    }
+
+    /// 为任务追加脚本化状态迁移
+    pub fn script(&self, gid: &str, steps: Vec<ScriptStep>) {
+        self.with_state(|s| s.scripts.entry(gid.to_string()).or_default().extend(steps));
+    }
+
+    /// 让指定方法的下一次调用返回错误
+    pub fn fail_next(&self, method: &str, message: &str) {
+        self.with_state(|s| {
+            s.failures.insert(method.to_string(), message.to_string());
+        });
+    }
+
+    /// 指定方法被调用的参数记录（已去除 token）
+    pub fn calls(&self, method: &str) -> Vec<Vec<Value>> {
+        self.with_state(|s| {
+            s.calls
+                .iter()
+                .filter(|(m, _)| m == method)
+                .map(|(_, p)| p.clone())
+                .collect()
+        })
+    }
+}
+
+impl Drop for MockAria2 {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        // 唤醒阻塞中的 accept
+        let _ = TcpStream::connect(("127.0.0.1", self.port));
+    }
+}
+
+/// 构造一条用于测试的持久化任务
+pub(crate) fn persisted_task(gid: &str, state: TaskState) -> PersistedTask {
+    PersistedTask {
+        gid: gid.to_string(),
+        filename: format!("{}.bin", gid),
+        url: format!("https://example.com/{}.bin", gid),
+        save_path: "/downloads".to_string(),
+        added_at: "2024-01-01T00:00:00+00:00".to_string(),
+        state,
+        total_length: 0,
+        completed_length: 0,
+        download_speed: 0,
+        completed_at: None,
+        error_message: String::new(),
+        error_kind: None,
+        retry_attempts: 0,
+        next_retry_at: None,
+        user_agent: String::new(),
+        referer: String::new(),
+        proxy: String::new(),
+        headers: Vec::new(),
+        max_download_limit: String::new(),
+        select_file: None,
+        trackers: None,
+        backend_id: DEFAULT_BACKEND_ID.to_string(),
+        queue_position: None,
+        category: None,
+        tags: Vec::new(),
+        start_at: None,
+        stop_at: None,
+        on_complete: None,
+        checksum: None,
+        checksum_status: None,
+        info_hash: None,
+    }
+}
+
+/// 创建测试专用的临时目录，名称带随机后缀，并行运行的测试之间互不干扰
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mua-{}-{}", name, uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    dir
+}
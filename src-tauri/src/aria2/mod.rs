@@ -1,2 +1,7 @@
+pub mod backend;
 pub mod client;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod options;
 pub mod sidecar;
+pub mod ws;
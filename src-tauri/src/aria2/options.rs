@@ -0,0 +1,432 @@
+//! Aria2 选项模型
+//! 同一组设置既可渲染为 RPC 选项对象，也可渲染为 `--flag=value` 命令行参数，
+//! 保证启动参数与运行时下发的选项不会出现偏差。
+
+use serde_json::Value;
+use std::fmt;
+
+use crate::core::checksum::Checksum;
+use crate::core::config::AppConfig;
+use crate::core::error::{AppError, AppResult};
+
+/// 启用做种时使用的做种时长（分钟），实际由 seed-ratio 控制停止
+pub const INFINITE_SEED_TIME: u64 = 999_999_999;
+
+/// 字节大小，如 `0`、`512K`、`2M`（1K = 1024，1M = 1024K）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteSize(String);
+
+impl ByteSize {
+    pub fn parse(raw: &str) -> AppResult<Self> {
+        let trimmed = raw.trim();
+        let (digits, unit) = match trimmed.char_indices().last() {
+            Some((idx, c)) if c.is_ascii_alphabetic() => (&trimmed[..idx], Some(c)),
+            _ => (trimmed, None),
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::validation(format!("无效的大小: {}", raw)));
+        }
+
+        let normalized = match unit {
+            None => digits.to_string(),
+            Some('k' | 'K') => format!("{}K", digits),
+            Some('m' | 'M') => format!("{}M", digits),
+            Some(_) => return Err(AppError::validation(format!("无效的大小单位: {}", raw))),
+        };
+
+        Ok(Self(normalized))
+    }
+
+    /// 空字符串视为未设置
+    pub fn parse_optional(raw: &str) -> AppResult<Option<Self>> {
+        if raw.trim().is_empty() {
+            Ok(None)
+        } else {
+            Self::parse(raw).map(Some)
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self("0".to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// 端口或端口范围，如 `6881` 或 `6881-6999`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortSpec(String);
+
+impl PortSpec {
+    pub fn parse(raw: &str) -> AppResult<Self> {
+        let trimmed = raw.trim();
+        let parse_port = |s: &str| -> AppResult<u16> {
+            match s.trim().parse::<u16>() {
+                Ok(port) if port > 0 => Ok(port),
+                _ => Err(AppError::validation(format!("无效的端口: {}", raw))),
+            }
+        };
+
+        match trimmed.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse_port(start)?, parse_port(end)?);
+                if start > end {
+                    return Err(AppError::validation(format!("无效的端口范围: {}", raw)));
+                }
+                Ok(Self(format!("{}-{}", start, end)))
+            }
+            None => parse_port(trimmed).map(|port| Self(port.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for PortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Notice => "notice",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// 渲染前的单个选项值
+enum OptionValue {
+    Single(String),
+    /// 可重复的选项（如 header），RPC 中为数组，命令行中重复出现
+    Repeated(Vec<String>),
+}
+
+/// aria2 选项集合。未设置（None / 空）的字段不会被渲染。
+#[derive(Debug, Clone, Default)]
+pub struct Aria2Options {
+    // --- 任务级 ---
+    pub dir: Option<String>,
+    pub out: Option<String>,
+    pub user_agent: Option<String>,
+    pub referer: Option<String>,
+    /// None 表示不设置；Some(空) 在 RPC 中渲染为空字符串，用于清除已有请求头
+    pub header: Option<Vec<String>>,
+    pub all_proxy: Option<String>,
+    pub max_download_limit: Option<ByteSize>,
+    pub select_file: Option<String>,
+    pub bt_tracker: Option<String>,
+    /// 添加后保持暂停（定时启动的任务）
+    pub pause: Option<bool>,
+    /// 下载完成后校验的摘要
+    pub checksum: Option<Checksum>,
+
+    // --- 全局 ---
+    pub max_concurrent_downloads: Option<u32>,
+    pub max_overall_download_limit: Option<ByteSize>,
+    pub max_overall_upload_limit: Option<ByteSize>,
+    pub enable_dht: Option<bool>,
+    pub enable_peer_exchange: Option<bool>,
+    pub seed_ratio: Option<f64>,
+    pub seed_time: Option<u64>,
+    pub dht_listen_port: Option<PortSpec>,
+    pub listen_port: Option<PortSpec>,
+
+    // --- 仅启动参数 ---
+    pub enable_rpc: Option<bool>,
+    pub rpc_listen_all: Option<bool>,
+    pub rpc_listen_port: Option<u16>,
+    pub rpc_secret: Option<String>,
+    pub disable_ipv6: Option<bool>,
+    pub log_level: Option<LogLevel>,
+    pub stop_with_process: Option<u32>,
+    pub conf_path: Option<String>,
+    pub input_file: Option<String>,
+    pub save_session: Option<String>,
+    pub save_session_interval: Option<u64>,
+}
+
+/// 按分号或换行拆分请求头
+pub fn split_headers(raw: &str) -> Vec<String> {
+    raw.split([';', '\n'])
+        .map(|h| h.trim())
+        .filter(|h| !h.is_empty())
+        .map(|h| h.to_string())
+        .collect()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.is_empty())
+}
+
+impl Aria2Options {
+    /// 构建单个下载任务的选项
+    pub fn for_task(
+        save_path: Option<String>,
+        filename: Option<String>,
+        user_agent: Option<String>,
+        referer: Option<String>,
+        headers: Option<String>,
+        proxy: Option<String>,
+        max_download_limit: Option<String>,
+    ) -> AppResult<Self> {
+        let max_download_limit = match max_download_limit {
+            Some(limit) => ByteSize::parse_optional(&limit)?,
+            None => None,
+        };
+
+        Ok(Self {
+            dir: save_path.map(|dir| crate::utils::resolve_path(&dir)),
+            out: non_empty(filename),
+            user_agent: non_empty(user_agent),
+            referer: non_empty(referer),
+            header: headers
+                .as_deref()
+                .map(split_headers)
+                .filter(|h| !h.is_empty()),
+            all_proxy: non_empty(proxy),
+            max_download_limit,
+            ..Self::default()
+        })
+    }
+
+    /// 从应用配置构建可在运行时修改的全局选项。
+    /// 启动参数与 `changeGlobalOption` 共用此结果。
+    pub fn global_from_config(config: &AppConfig) -> AppResult<Self> {
+        let normalized_trackers = crate::utils::normalize_bt_trackers(&config.bt_trackers);
+
+        Ok(Self {
+            max_concurrent_downloads: Some(config.max_concurrent_downloads),
+            bt_tracker: Some(normalized_trackers).filter(|t| !t.is_empty()),
+            enable_dht: Some(config.enable_dht),
+            enable_peer_exchange: Some(config.enable_peer_exchange),
+            seed_ratio: Some(config.seed_ratio),
+            seed_time: Some(if config.enable_seeding {
+                INFINITE_SEED_TIME
+            } else {
+                0
+            }),
+            dht_listen_port: Some(PortSpec::parse(&config.dht_listen_port)?),
+            listen_port: Some(PortSpec::parse(&config.listen_port)?),
+            ..Self::speed_limits(
+                &config.global_max_download_limit,
+                &config.global_max_upload_limit,
+            )?
+        })
+    }
+
+    /// 仅包含全局下载/上传限速的选项，空字符串视为不限速。
+    /// 使用 overall 选项，运行时修改对正在进行的下载同样生效
+    pub fn speed_limits(download: &str, upload: &str) -> AppResult<Self> {
+        Ok(Self {
+            max_overall_download_limit: Some(
+                ByteSize::parse_optional(download)?.unwrap_or_else(ByteSize::unlimited),
+            ),
+            max_overall_upload_limit: Some(
+                ByteSize::parse_optional(upload)?.unwrap_or_else(ByteSize::unlimited),
+            ),
+            ..Self::default()
+        })
+    }
+
+    /// 最终保存目录（未指定时为空字符串）
+    pub fn save_dir(&self) -> String {
+        self.dir.clone().unwrap_or_default()
+    }
+
+    fn entries(&self) -> Vec<(&'static str, OptionValue)> {
+        use OptionValue::{Repeated, Single};
+
+        let mut entries = Vec::new();
+        let mut push = |key: &'static str, value: Option<String>| {
+            if let Some(v) = value {
+                entries.push((key, Single(v)));
+            }
+        };
+
+        push("enable-rpc", self.enable_rpc.map(|v| v.to_string()));
+        push("rpc-listen-all", self.rpc_listen_all.map(|v| v.to_string()));
+        push("rpc-listen-port", self.rpc_listen_port.map(|v| v.to_string()));
+        push("disable-ipv6", self.disable_ipv6.map(|v| v.to_string()));
+        push("log-level", self.log_level.map(|v| v.as_str().to_string()));
+        push("stop-with-process", self.stop_with_process.map(|v| v.to_string()));
+
+        push("dir", self.dir.clone());
+        push("out", self.out.clone());
+        push("user-agent", self.user_agent.clone());
+        push("referer", self.referer.clone());
+        push("all-proxy", self.all_proxy.clone());
+        push(
+            "max-download-limit",
+            self.max_download_limit.as_ref().map(|v| v.to_string()),
+        );
+        push("select-file", self.select_file.clone());
+        push("pause", self.pause.map(|v| v.to_string()));
+        push("checksum", self.checksum.as_ref().map(|v| v.to_string()));
+
+        push(
+            "max-concurrent-downloads",
+            self.max_concurrent_downloads.map(|v| v.to_string()),
+        );
+        push(
+            "max-overall-download-limit",
+            self.max_overall_download_limit.as_ref().map(|v| v.to_string()),
+        );
+        push(
+            "max-overall-upload-limit",
+            self.max_overall_upload_limit.as_ref().map(|v| v.to_string()),
+        );
+        push("bt-tracker", self.bt_tracker.clone());
+        push("enable-dht", self.enable_dht.map(|v| v.to_string()));
+        push(
+            "enable-peer-exchange",
+            self.enable_peer_exchange.map(|v| v.to_string()),
+        );
+        push("seed-ratio", self.seed_ratio.map(|v| v.to_string()));
+        push("seed-time", self.seed_time.map(|v| v.to_string()));
+        push(
+            "dht-listen-port",
+            self.dht_listen_port.as_ref().map(|v| v.to_string()),
+        );
+        push("listen-port", self.listen_port.as_ref().map(|v| v.to_string()));
+
+        push("rpc-secret", self.rpc_secret.clone());
+        push("conf-path", self.conf_path.clone());
+        push("input-file", self.input_file.clone());
+        push("save-session", self.save_session.clone());
+        push(
+            "save-session-interval",
+            self.save_session_interval.map(|v| v.to_string()),
+        );
+
+        match &self.header {
+            Some(list) if list.is_empty() => entries.push(("header", Single(String::new()))),
+            Some(list) => entries.push(("header", Repeated(list.clone()))),
+            None => {}
+        }
+
+        entries
+    }
+
+    /// 渲染为 RPC 选项对象（aria2 要求所有值为字符串）
+    pub fn to_rpc_value(&self) -> Value {
+        let mut map = serde_json::Map::new();
+        for (key, value) in self.entries() {
+            let json_value = match value {
+                OptionValue::Single(v) => Value::String(v),
+                OptionValue::Repeated(list) => {
+                    Value::Array(list.into_iter().map(Value::String).collect())
+                }
+            };
+            map.insert(key.to_string(), json_value);
+        }
+        Value::Object(map)
+    }
+
+    /// 渲染为 `--flag=value` 命令行参数
+    pub fn to_cli_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for (key, value) in self.entries() {
+            match value {
+                OptionValue::Single(v) => args.push(format!("--{}={}", key, v)),
+                OptionValue::Repeated(list) => {
+                    args.extend(list.into_iter().map(|v| format!("--{}={}", key, v)))
+                }
+            }
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn byte_size_normalizes_units() {
+        assert_eq!(ByteSize::parse(" 512k ").unwrap().as_str(), "512K");
+        assert_eq!(ByteSize::parse("2M").unwrap().as_str(), "2M");
+        assert_eq!(ByteSize::parse("0").unwrap().as_str(), "0");
+        assert_eq!(ByteSize::parse_optional("  ").unwrap(), None);
+
+        for raw in ["", "K", "1.5M", "-1", "2G", "10 M"] {
+            assert!(ByteSize::parse(raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn port_spec_accepts_ports_and_ranges() {
+        assert_eq!(PortSpec::parse("6881").unwrap().to_string(), "6881");
+        assert_eq!(PortSpec::parse(" 6881 - 6999 ").unwrap().to_string(), "6881-6999");
+
+        for raw in ["", "0", "65536", "abc", "6999-6881", "6881-", "1-2-3"] {
+            assert!(PortSpec::parse(raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn cli_and_rpc_renderings_match() {
+        let options = Aria2Options {
+            dir: Some("/downloads".to_string()),
+            header: Some(split_headers("A: 1; B: 2\nC: 3")),
+            max_download_limit: Some(ByteSize::parse("1M").unwrap()),
+            listen_port: Some(PortSpec::parse("6881-6999").unwrap()),
+            enable_dht: Some(false),
+            ..Aria2Options::default()
+        };
+
+        let rpc = options.to_rpc_value();
+        assert_eq!(
+            rpc,
+            json!({
+                "dir": "/downloads",
+                "max-download-limit": "1M",
+                "enable-dht": "false",
+                "listen-port": "6881-6999",
+                "header": ["A: 1", "B: 2", "C: 3"],
+            })
+        );
+
+        // 命令行中每个值对应一个参数，可重复的选项逐个展开
+        let mut expected: Vec<String> = Vec::new();
+        for (key, value) in rpc.as_object().unwrap() {
+            match value {
+                Value::Array(list) => expected.extend(
+                    list.iter()
+                        .map(|v| format!("--{}={}", key, v.as_str().unwrap())),
+                ),
+                v => expected.push(format!("--{}={}", key, v.as_str().unwrap())),
+            }
+        }
+        let mut cli = options.to_cli_args();
+        cli.sort();
+        expected.sort();
+        assert_eq!(cli, expected);
+        assert_eq!(
+            cli.iter().filter(|a| a.starts_with("--header=")).count(),
+            3
+        );
+    }
+}
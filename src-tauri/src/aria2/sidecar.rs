@@ -1,16 +1,17 @@
+use crate::aria2::backend::{BackendRegistry, HttpBackend};
+use crate::aria2::client::DEFAULT_BACKEND_ID;
+use crate::aria2::options::{Aria2Options, LogLevel};
 use crate::core::events::{EVENT_ARIA2_SIDECAR_ERROR, EVENT_ARIA2_STDOUT};
 use chrono::Local;
 use serde_json::json;
 use std::collections::VecDeque;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_shell::process::CommandChild;
 use tauri_plugin_shell::process::CommandEvent;
 use tauri_plugin_shell::ShellExt;
 
-const INFINITE_SEED_TIME: &str = "999999999";
-
 fn find_available_port(start: u16) -> Result<u16, String> {
     let mut port = start;
     loop {
@@ -67,58 +68,14 @@ pub fn init_aria2_sidecar(app: AppHandle) {
             };
 
             // 1. 检查现有配置
-            let (
-                preferred_port,
-                save_session_interval,
-                existing_secret,
-                max_concurrent,
-                bt_trackers,
-                enable_dht,
-                enable_peer_exchange,
-                enable_seeding,
-                seed_ratio,
-                dht_listen_port,
-                listen_port,
-                global_max_upload_limit,
-            ) = {
-                if let Some(state) = app
-                    .state::<crate::core::config::ConfigState>()
-                    .config
-                    .lock()
-                    .ok()
-                {
-                    (
-                        state.rpc_port,
-                        state.save_session_interval,
-                        state.rpc_secret.clone(),
-                        state.max_concurrent_downloads,
-                        state.bt_trackers.clone(),
-                        state.enable_dht,
-                        state.enable_peer_exchange,
-                        state.enable_seeding,
-                        state.seed_ratio,
-                        state.dht_listen_port.clone(),
-                        state.listen_port.clone(),
-                        state.global_max_upload_limit.clone(),
-                    )
-                } else {
-                    let default = crate::core::config::AppConfig::default();
-                    (
-                        default.rpc_port,
-                        default.save_session_interval,
-                        default.rpc_secret.clone(),
-                        default.max_concurrent_downloads,
-                        default.bt_trackers.clone(),
-                        default.enable_dht,
-                        default.enable_peer_exchange,
-                        default.enable_seeding,
-                        default.seed_ratio,
-                        default.dht_listen_port.clone(),
-                        default.listen_port.clone(),
-                        default.global_max_upload_limit.clone(),
-                    )
-                }
-            };
+            let config = app
+                .state::<crate::core::config::ConfigState>()
+                .config
+                .lock()
+                .map(|c| c.clone())
+                .unwrap_or_default();
+            let preferred_port = config.rpc_port;
+            let existing_secret = config.rpc_secret.clone();
             crate::app_info!(
                 "Aria2::Sidecar",
                 "port_preferred",
@@ -140,54 +97,29 @@ pub fn init_aria2_sidecar(app: AppHandle) {
             };
             crate::app_info!("Aria2::Sidecar", "port_selected", json!({ "port": port }));
 
-            // 3. 更新客户端配置（端口 + Secret）
-            crate::aria2::client::set_aria2_port(port);
-
-            // 如果已有配置的 Secret，也要同步给 Client
-            if let Some(ref secret) = existing_secret {
-                crate::aria2::client::set_aria2_secret(secret.clone()).await;
-            }
-
-            let mut args = vec![
-                "--enable-rpc".to_string(),
-                "--rpc-listen-all=false".to_string(),
-                format!("--rpc-listen-port={}", port),
-                "--disable-ipv6".to_string(),
-                "--log-level=warn".to_string(),
-                format!("--max-concurrent-downloads={}", max_concurrent),
-                format!("--stop-with-process={}", std::process::id()), // 父进程退出时自动关闭
-                format!("--enable-dht={}", enable_dht),
-                format!("--enable-peer-exchange={}", enable_peer_exchange),
-                format!("--seed-ratio={}", seed_ratio),
-                // 如果启用做种，不传 seed-time (默认无限/受 ratio 控制)
-                // 如果禁用做种，传 seed-time=0
-                format!(
-                    "--seed-time={}",
-                    if enable_seeding {
-                        INFINITE_SEED_TIME.to_string()
-                    } else {
-                        "0".to_string()
-                    }
-                ),
-                format!("--dht-listen-port={}", dht_listen_port),
-                format!("--listen-port={}", listen_port),
-            ];
-
-            if !global_max_upload_limit.is_empty() {
-                args.push(format!(
-                    "--max-overall-upload-limit={}",
-                    global_max_upload_limit
-                ));
-            }
-
-            let normalized_bt_trackers = crate::utils::normalize_bt_trackers(&bt_trackers);
-            if !normalized_bt_trackers.is_empty() {
-                args.push(format!("--bt-tracker={}", normalized_bt_trackers));
-            }
+            // 3. 将默认后端指向本次启动的端口与 Secret
+            app.state::<BackendRegistry>().register(
+                DEFAULT_BACKEND_ID,
+                Arc::new(HttpBackend::local(port, existing_secret.clone())),
+            );
 
-            if let Some(ref secret) = existing_secret {
-                args.push(format!("--rpc-secret={}", secret));
-            }
+            // 全局选项与运行时 changeGlobalOption 共用同一模型
+            let mut options = Aria2Options::global_from_config(&config).unwrap_or_else(|e| {
+                crate::app_warn!(
+                    "Aria2::Sidecar",
+                    "invalid_config_options_fallback_default",
+                    json!({ "error": e.to_string() })
+                );
+                Aria2Options::global_from_config(&crate::core::config::AppConfig::default())
+                    .unwrap_or_default()
+            });
+            options.enable_rpc = Some(true);
+            options.rpc_listen_all = Some(false);
+            options.rpc_listen_port = Some(port);
+            options.disable_ipv6 = Some(true);
+            options.log_level = Some(LogLevel::Warn);
+            options.stop_with_process = Some(std::process::id()); // 父进程退出时自动关闭
+            options.rpc_secret = existing_secret.clone();
 
             // 检查自定义配置文件
             if let Ok(config_dir) = app.path().app_config_dir() {
@@ -204,7 +136,7 @@ pub fn init_aria2_sidecar(app: AppHandle) {
                         "custom_config_detected",
                         json!({ "path": conf_path.to_string_lossy() })
                     );
-                    args.push(format!("--conf-path={}", conf_path.to_string_lossy()));
+                    options.conf_path = Some(conf_path.to_string_lossy().to_string());
                 }
 
                 // 2. 会话文件 (持久化)
@@ -219,12 +151,14 @@ pub fn init_aria2_sidecar(app: AppHandle) {
                     }
                 }
 
-                let session_path_str = session_path.to_string_lossy();
-                args.push(format!("--input-file={}", session_path_str));
-                args.push(format!("--save-session={}", session_path_str));
-                args.push(format!("--save-session-interval={}", save_session_interval));
+                let session_path_str = session_path.to_string_lossy().to_string();
+                options.input_file = Some(session_path_str.clone());
+                options.save_session = Some(session_path_str);
+                options.save_session_interval = Some(config.save_session_interval);
             }
 
+            let args = options.to_cli_args();
+
             crate::app_info!("Aria2::Sidecar", "start_requested");
 
             // --- 自定义二进制逻辑 ---
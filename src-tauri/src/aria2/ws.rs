@@ -0,0 +1,263 @@
+//! Aria2 WebSocket 传输层
+//! 维持到 `/jsonrpc` 的长连接：订阅 aria2 推送通知，并在连接可用时承载 RPC 调用。
+//! 连接断开期间 `client` 自动回退到 HTTP POST；请求已发出后的失败不回退，以免重复执行。
+
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::aria2::backend::BackendRegistry;
+use crate::aria2::client::DEFAULT_BACKEND_ID;
+use crate::core::error::AppError;
+
+/// 单次 WebSocket RPC 调用的超时时间
+const WS_CALL_TIMEOUT: Duration = Duration::from_secs(10);
+const INITIAL_RECONNECT_DELAY_SECS: u64 = 1;
+const MAX_RECONNECT_DELAY_SECS: u64 = 30;
+
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+static OUTBOUND: Mutex<Option<mpsc::UnboundedSender<String>>> = Mutex::new(None);
+static PENDING: Mutex<Option<HashMap<String, oneshot::Sender<Value>>>> = Mutex::new(None);
+
+/// aria2 推送通知类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aria2NotificationKind {
+    DownloadStart,
+    DownloadPause,
+    DownloadStop,
+    DownloadComplete,
+    DownloadError,
+    BtDownloadComplete,
+}
+
+impl Aria2NotificationKind {
+    fn from_method(method: &str) -> Option<Self> {
+        match method {
+            "aria2.onDownloadStart" => Some(Self::DownloadStart),
+            "aria2.onDownloadPause" => Some(Self::DownloadPause),
+            "aria2.onDownloadStop" => Some(Self::DownloadStop),
+            "aria2.onDownloadComplete" => Some(Self::DownloadComplete),
+            "aria2.onDownloadError" => Some(Self::DownloadError),
+            "aria2.onBtDownloadComplete" => Some(Self::BtDownloadComplete),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Aria2Notification {
+    pub kind: Aria2NotificationKind,
+    pub gid: String,
+}
+
+/// WebSocket 是否处于已连接状态
+pub fn is_connected() -> bool {
+    CONNECTED.load(Ordering::Acquire)
+}
+
+/// WebSocket 调用失败的阶段，决定调用方能否改走 HTTP 重发
+#[derive(Debug)]
+pub(crate) enum CallError {
+    /// 请求未发出（未连接或写入通道失败），可以安全地回退到 HTTP
+    NotSent(AppError),
+    /// 请求已发出但未收到响应，aria2 可能已经执行，不能重发
+    Sent(AppError),
+}
+
+/// 通过 WebSocket 发送一个完整的 JSON-RPC 请求体，返回原始响应体。
+/// 请求体必须带有唯一的 `id`，用于匹配响应。
+pub(crate) async fn call(payload: &Value) -> Result<Value, CallError> {
+    let id = payload
+        .get("id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| CallError::NotSent(AppError::aria2("WebSocket 请求缺少 id")))?
+        .to_string();
+
+    let sender = OUTBOUND
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone())
+        .ok_or_else(|| CallError::NotSent(AppError::aria2("WebSocket 未连接")))?;
+
+    let (tx, rx) = oneshot::channel();
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.get_or_insert_with(HashMap::new).insert(id.clone(), tx);
+    }
+
+    if sender.send(payload.to_string()).is_err() {
+        remove_pending(&id);
+        return Err(CallError::NotSent(AppError::aria2("WebSocket 发送失败")));
+    }
+
+    match tokio::time::timeout(WS_CALL_TIMEOUT, rx).await {
+        Ok(Ok(body)) => Ok(body),
+        Ok(Err(_)) => Err(CallError::Sent(AppError::aria2("WebSocket 连接已断开"))),
+        Err(_) => {
+            remove_pending(&id);
+            Err(CallError::Sent(AppError::aria2("WebSocket 请求超时")))
+        }
+    }
+}
+
+fn remove_pending(id: &str) {
+    if let Ok(mut pending) = PENDING.lock() {
+        if let Some(map) = pending.as_mut() {
+            map.remove(id);
+        }
+    }
+}
+
+/// 断开时清理连接状态；丢弃所有等待中的调用使其立即失败并回退到 HTTP
+fn mark_disconnected() {
+    CONNECTED.store(false, Ordering::Release);
+    if let Ok(mut outbound) = OUTBOUND.lock() {
+        *outbound = None;
+    }
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = None;
+    }
+}
+
+fn handle_incoming(text: &str, events: &mpsc::UnboundedSender<Aria2Notification>) {
+    let Ok(body) = serde_json::from_str::<Value>(text) else {
+        return;
+    };
+
+    // 带 id 的是 RPC 响应，交还给等待中的调用方
+    if let Some(id) = body.get("id").and_then(|v| v.as_str()) {
+        let waiter = PENDING
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.as_mut().and_then(|map| map.remove(id)));
+        if let Some(waiter) = waiter {
+            let _ = waiter.send(body);
+        }
+        return;
+    }
+
+    // 否则为推送通知: {"method": "aria2.onDownloadStart", "params": [{"gid": "..."}]}
+    let Some(kind) = body
+        .get("method")
+        .and_then(|v| v.as_str())
+        .and_then(Aria2NotificationKind::from_method)
+    else {
+        return;
+    };
+
+    let gids = body
+        .get("params")
+        .and_then(|v| v.as_array())
+        .map(|params| {
+            params
+                .iter()
+                .filter_map(|p| p.get("gid").and_then(|g| g.as_str()))
+                .map(|g| g.to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    for gid in gids {
+        crate::app_debug!(
+            "Aria2::Ws",
+            "notification_received",
+            json!({ "kind": format!("{:?}", kind), "gid": gid })
+        );
+        let _ = events.send(Aria2Notification { kind, gid });
+    }
+}
+
+fn is_shutting_down(app: &AppHandle) -> bool {
+    app.try_state::<crate::aria2::sidecar::ShutdownState>()
+        .is_some_and(|state| state.0.load(Ordering::SeqCst))
+}
+
+/// 启动 WebSocket 连接守护任务，返回 aria2 推送通知的接收端。
+/// 连接断开后按指数退避自动重连（端口在每次重连时重新读取，以适配 Sidecar 重启换端口）。
+pub fn start_notification_listener(app: AppHandle) -> mpsc::UnboundedReceiver<Aria2Notification> {
+    let (events_tx, events_rx) = mpsc::unbounded_channel();
+
+    tauri::async_runtime::spawn(async move {
+        // 启动宽限期：等待 Sidecar 绑定端口
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let mut retry_delay_secs = INITIAL_RECONNECT_DELAY_SECS;
+
+        loop {
+            if is_shutting_down(&app) {
+                break;
+            }
+
+            let Some(url) = app
+                .try_state::<BackendRegistry>()
+                .and_then(|backends| backends.get(DEFAULT_BACKEND_ID).ok())
+                .and_then(|backend| backend.ws_url())
+            else {
+                // 默认后端尚未就绪（Sidecar 仍在分配端口）
+                tokio::time::sleep(Duration::from_secs(retry_delay_secs)).await;
+                continue;
+            };
+            match tokio_tungstenite::connect_async(url.as_str()).await {
+                Ok((stream, _)) => {
+                    crate::app_info!("Aria2::Ws", "connected", json!({ "url": url }));
+                    retry_delay_secs = INITIAL_RECONNECT_DELAY_SECS;
+
+                    let (mut write, mut read) = stream.split();
+                    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<String>();
+                    if let Ok(mut outbound) = OUTBOUND.lock() {
+                        *outbound = Some(out_tx);
+                    }
+                    CONNECTED.store(true, Ordering::Release);
+
+                    loop {
+                        tokio::select! {
+                            Some(text) = out_rx.recv() => {
+                                if let Err(e) = write.send(Message::Text(text)).await {
+                                    crate::app_warn!(
+                                        "Aria2::Ws",
+                                        "send_failed",
+                                        json!({ "error": e.to_string() })
+                                    );
+                                    break;
+                                }
+                            }
+                            msg = read.next() => match msg {
+                                Some(Ok(Message::Text(text))) => handle_incoming(&text, &events_tx),
+                                Some(Ok(Message::Close(_))) | None => break,
+                                Some(Ok(_)) => {}
+                                Some(Err(e)) => {
+                                    crate::app_warn!(
+                                        "Aria2::Ws",
+                                        "read_failed",
+                                        json!({ "error": e.to_string() })
+                                    );
+                                    break;
+                                }
+                            }
+                        }
+                    }
+
+                    mark_disconnected();
+                    crate::app_warn!("Aria2::Ws", "disconnected_fallback_http");
+                }
+                Err(e) => {
+                    crate::app_debug!(
+                        "Aria2::Ws",
+                        "connect_failed",
+                        json!({ "error": e.to_string(), "retry_in_secs": retry_delay_secs })
+                    );
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(retry_delay_secs)).await;
+            retry_delay_secs = (retry_delay_secs * 2).min(MAX_RECONNECT_DELAY_SECS);
+        }
+    });
+
+    events_rx
+}
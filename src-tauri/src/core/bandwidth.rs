@@ -0,0 +1,557 @@
+//! 按时段切换的全局限速
+//! 后台每隔一段时间按 `AppConfig.bandwidth_schedule` 计算当前应生效的限速方案，
+//! 方案变化（即跨过时段边界）时通过 `aria2.changeGlobalOption` 下发；
+//! 手动指定的方案优先于计划，直到用户取消；乌龟模式（备用限速）优先于两者。
+//! 限速只作用于默认后端：远程后端运行在其他机器上，不占用本机带宽。
+
+use chrono::{Datelike, Duration as ChronoDuration, Local, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::aria2::backend::{Aria2Backend, BackendRegistry};
+use crate::aria2::client::DEFAULT_BACKEND_ID;
+use crate::aria2::options::Aria2Options;
+use crate::core::config::{AppConfig, ConfigState, ScheduleEntry};
+use crate::core::error::{AppError, AppResult};
+use crate::core::events::EVENT_BANDWIDTH_PROFILE_CHANGED;
+use crate::core::store::TaskStore;
+use crate::core::types::TaskState;
+
+/// 代表“全局限速设置”的方案 ID，可用于手动指定以忽略计划
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+/// 乌龟模式的方案 ID
+pub const TURTLE_PROFILE_ID: &str = "turtle";
+
+/// 计划检查间隔
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 当前方案的来源
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileSource {
+    /// 全局限速设置（计划未启用或未覆盖当前时间）
+    Default,
+    /// 计划中的时段
+    Schedule,
+    /// 用户手动指定
+    Manual,
+    /// 乌龟模式
+    Turtle,
+}
+
+/// 当前生效的限速方案
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveProfile {
+    pub id: String,
+    pub name: String,
+    pub max_download_limit: String,
+    pub max_upload_limit: String,
+    pub paused: bool,
+    pub source: ProfileSource,
+}
+
+impl ActiveProfile {
+    fn global(config: &AppConfig, source: ProfileSource) -> Self {
+        Self {
+            id: DEFAULT_PROFILE_ID.to_string(),
+            name: "全局设置".to_string(),
+            max_download_limit: config.global_max_download_limit.clone(),
+            max_upload_limit: config.global_max_upload_limit.clone(),
+            paused: false,
+            source,
+        }
+    }
+
+    fn from_config(config: &AppConfig, id: &str, source: ProfileSource) -> Option<Self> {
+        if id == DEFAULT_PROFILE_ID {
+            return Some(Self::global(config, source));
+        }
+        config
+            .bandwidth_schedule
+            .profiles
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| Self {
+                id: p.id.clone(),
+                name: p.name.clone(),
+                max_download_limit: p.max_download_limit.clone(),
+                max_upload_limit: p.max_upload_limit.clone(),
+                paused: p.paused,
+                source,
+            })
+    }
+}
+
+/// 手动指定的方案与最近一次成功下发的方案
+#[derive(Default)]
+pub struct BandwidthState {
+    manual: Mutex<Option<String>>,
+    applied: Mutex<Option<ActiveProfile>>,
+    /// 由“暂停下载”方案暂停的任务，方案结束时只恢复这些任务
+    paused_gids: Mutex<Vec<String>>,
+    /// 乌龟模式开关，仅在本次运行期间有效
+    turtle: Mutex<bool>,
+}
+
+impl BandwidthState {
+    pub fn manual(&self) -> Option<String> {
+        self.manual.lock().ok().and_then(|m| m.clone())
+    }
+
+    pub fn applied(&self) -> Option<ActiveProfile> {
+        self.applied.lock().ok().and_then(|a| a.clone())
+    }
+
+    pub fn turtle(&self) -> bool {
+        self.turtle.lock().map(|t| *t).unwrap_or(false)
+    }
+}
+
+/// 计算指定时间应生效的方案。手动指定的方案已被删除时回退到计划。
+pub fn resolve(
+    config: &AppConfig,
+    now: NaiveDateTime,
+    manual: Option<&str>,
+    turtle: bool,
+) -> ActiveProfile {
+    if turtle {
+        return ActiveProfile {
+            id: TURTLE_PROFILE_ID.to_string(),
+            name: "乌龟模式".to_string(),
+            max_download_limit: config.alt_speed.max_download_limit.clone(),
+            max_upload_limit: config.alt_speed.max_upload_limit.clone(),
+            paused: false,
+            source: ProfileSource::Turtle,
+        };
+    }
+
+    if let Some(profile) =
+        manual.and_then(|id| ActiveProfile::from_config(config, id, ProfileSource::Manual))
+    {
+        return profile;
+    }
+
+    let schedule = &config.bandwidth_schedule;
+    if schedule.enabled {
+        let scheduled = schedule
+            .entries
+            .iter()
+            .filter(|entry| entry_covers(entry, now))
+            .find_map(|entry| {
+                ActiveProfile::from_config(config, &entry.profile_id, ProfileSource::Schedule)
+            });
+        if let Some(profile) = scheduled {
+            return profile;
+        }
+    }
+
+    ActiveProfile::global(config, ProfileSource::Default)
+}
+
+fn parse_time(raw: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(raw.trim(), "%H:%M").ok()
+}
+
+/// 时段是否覆盖 `now`；跨午夜的时段后半段属于开始日期的次日。
+/// 开始与结束时间相同表示从开始时间起的整 24 小时（`00:00-00:00` 即全天）
+fn entry_covers(entry: &ScheduleEntry, now: NaiveDateTime) -> bool {
+    let (Some(start), Some(end)) = (parse_time(&entry.start), parse_time(&entry.end)) else {
+        return false;
+    };
+    let on_day = |date: chrono::NaiveDate| {
+        entry.days.is_empty() || entry.days.contains(&date.weekday().number_from_monday())
+    };
+
+    let time = now.time();
+    if start < end {
+        return on_day(now.date()) && time >= start && time < end;
+    }
+    // 跨午夜（end <= start），如 22:00-06:00；start == end 时覆盖整 24 小时
+    (time >= start && on_day(now.date()))
+        || (time < end && on_day(now.date() - ChronoDuration::days(1)))
+}
+
+/// 保存配置前校验计划：方案 ID 唯一、限速格式有效、时段时间与引用的方案有效
+pub fn validate(config: &AppConfig) -> AppResult<()> {
+    Aria2Options::speed_limits(
+        &config.alt_speed.max_download_limit,
+        &config.alt_speed.max_upload_limit,
+    )?;
+
+    let schedule = &config.bandwidth_schedule;
+    for (idx, profile) in schedule.profiles.iter().enumerate() {
+        if profile.id.trim().is_empty()
+            || profile.id == DEFAULT_PROFILE_ID
+            || profile.id == TURTLE_PROFILE_ID
+        {
+            return Err(AppError::validation(format!(
+                "无效的限速方案 ID: {}",
+                profile.id
+            )));
+        }
+        if schedule.profiles[..idx].iter().any(|p| p.id == profile.id) {
+            return Err(AppError::validation(format!(
+                "限速方案 ID 重复: {}",
+                profile.id
+            )));
+        }
+        Aria2Options::speed_limits(&profile.max_download_limit, &profile.max_upload_limit)?;
+    }
+
+    for entry in &schedule.entries {
+        if parse_time(&entry.start).is_none() || parse_time(&entry.end).is_none() {
+            return Err(AppError::validation(format!(
+                "无效的时段: {}-{}",
+                entry.start, entry.end
+            )));
+        }
+        if let Some(day) = entry.days.iter().find(|d| !(1..=7).contains(*d)) {
+            return Err(AppError::validation(format!("无效的星期: {}", day)));
+        }
+        if entry.profile_id != DEFAULT_PROFILE_ID
+            && !schedule.profiles.iter().any(|p| p.id == entry.profile_id)
+        {
+            return Err(AppError::validation(format!(
+                "时段引用了未知的限速方案: {}",
+                entry.profile_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 当前应生效的方案
+pub fn current<R: Runtime>(app: &AppHandle<R>) -> ActiveProfile {
+    let config = app.state::<ConfigState>().snapshot();
+    let state = app.state::<BandwidthState>();
+    resolve(
+        &config,
+        Local::now().naive_local(),
+        state.manual().as_deref(),
+        state.turtle(),
+    )
+}
+
+/// 方案与已下发的不同时（或 `force` 为 true 时）下发到默认后端。
+/// 下发失败时不记录为已应用，下次检查时重试。
+pub async fn apply<R: Runtime>(app: &AppHandle<R>, force: bool) -> AppResult<ActiveProfile> {
+    let profile = current(app);
+    let state = app.state::<BandwidthState>();
+    let previous = state.applied();
+    if !force && previous.as_ref() == Some(&profile) {
+        return Ok(profile);
+    }
+
+    let backend = app.state::<BackendRegistry>().get(DEFAULT_BACKEND_ID)?;
+    let options =
+        Aria2Options::speed_limits(&profile.max_download_limit, &profile.max_upload_limit)?;
+    backend.change_global_option(options.to_rpc_value()).await?;
+
+    let was_paused = previous.as_ref().is_some_and(|p| p.paused);
+    if profile.paused && !was_paused {
+        let gids = pause_profile_tasks(&app.state::<TaskStore>(), backend.as_ref()).await;
+        if let Ok(mut paused) = state.paused_gids.lock() {
+            paused.extend(gids);
+        }
+    } else if !profile.paused && was_paused {
+        let gids = state
+            .paused_gids
+            .lock()
+            .map(|mut paused| std::mem::take(&mut *paused))
+            .unwrap_or_default();
+        resume_profile_tasks(&app.state::<TaskStore>(), backend.as_ref(), gids).await;
+    }
+
+    crate::app_info!(
+        "Core::Bandwidth",
+        "profile_applied",
+        json!({
+            "profile_id": profile.id,
+            "source": profile.source,
+            "download_limit": profile.max_download_limit,
+            "upload_limit": profile.max_upload_limit,
+            "paused": profile.paused,
+        })
+    );
+
+    if let Ok(mut applied) = state.applied.lock() {
+        *applied = Some(profile.clone());
+    }
+    if previous.as_ref() != Some(&profile) {
+        let _ = app.emit(EVENT_BANDWIDTH_PROFILE_CHANGED, &profile);
+        crate::ui::tray::refresh_menu(app);
+    }
+    Ok(profile)
+}
+
+/// 暂停默认后端上正在下载或排队的任务，返回实际暂停的 GID。
+/// 不使用 `pauseAll`：已暂停的任务（包括等待定时开始的任务）不应被记录，以免方案结束时被一并恢复。
+async fn pause_profile_tasks(store: &TaskStore, backend: &dyn Aria2Backend) -> Vec<String> {
+    let candidates = store.get_all().into_iter().filter(|t| {
+        t.backend_id == DEFAULT_BACKEND_ID
+            && matches!(t.state, TaskState::Active | TaskState::Waiting)
+    });
+
+    let mut paused = Vec::new();
+    for task in candidates {
+        match backend.pause(task.gid.clone()).await {
+            Ok(_) => {
+                store.update_task_state(&task.gid, TaskState::Paused);
+                paused.push(task.gid);
+            }
+            Err(e) => crate::app_warn!(
+                "Core::Bandwidth",
+                "profile_pause_failed",
+                json!({ "gid": task.gid, "error": e.to_string() })
+            ),
+        }
+    }
+    paused
+}
+
+/// 恢复由方案暂停的任务；期间被用户手动恢复、删除或已结束的任务跳过
+async fn resume_profile_tasks(store: &TaskStore, backend: &dyn Aria2Backend, gids: Vec<String>) {
+    for gid in gids {
+        if store.get_task(&gid).map(|t| t.state) != Some(TaskState::Paused) {
+            continue;
+        }
+        match backend.resume(gid.clone()).await {
+            Ok(_) => store.update_task_state(&gid, TaskState::Waiting),
+            Err(e) if e.is_aria2_not_found() => {}
+            Err(e) => crate::app_warn!(
+                "Core::Bandwidth",
+                "profile_resume_failed",
+                json!({ "gid": gid, "error": e.to_string() })
+            ),
+        }
+    }
+}
+
+/// 手动指定方案（None 表示恢复按计划切换）并立即下发
+pub async fn set_manual<R: Runtime>(
+    app: &AppHandle<R>,
+    profile_id: Option<String>,
+) -> AppResult<ActiveProfile> {
+    if let Some(ref id) = profile_id {
+        let config = app.state::<ConfigState>().snapshot();
+        if ActiveProfile::from_config(&config, id, ProfileSource::Manual).is_none() {
+            return Err(AppError::validation(format!("未知的限速方案: {}", id)));
+        }
+    }
+    if let Ok(mut manual) = app.state::<BandwidthState>().manual.lock() {
+        *manual = profile_id;
+    }
+    crate::ui::tray::refresh_menu(app);
+    apply(app, false).await
+}
+
+pub fn turtle_enabled<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.try_state::<BandwidthState>()
+        .is_some_and(|state| state.turtle())
+}
+
+/// 开启或关闭乌龟模式：只修改运行时状态（不写入配置），立即下发并刷新托盘菜单与图标
+pub async fn set_turtle<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> AppResult<ActiveProfile> {
+    if let Ok(mut turtle) = app.state::<BandwidthState>().turtle.lock() {
+        *turtle = enabled;
+    }
+
+    crate::app_info!(
+        "Core::Bandwidth",
+        "turtle_mode_toggled",
+        json!({ "enabled": enabled })
+    );
+    crate::ui::tray::refresh_menu(app);
+    crate::ui::tray::invalidate_icon(app);
+    apply(app, false).await
+}
+
+/// 启动后台计划检查
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = apply(&app, false).await {
+                crate::app_debug!(
+                    "Core::Bandwidth",
+                    "profile_apply_failed",
+                    json!({ "error": e.to_string() })
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::{BandwidthSchedule, SpeedProfile};
+    use chrono::NaiveDate;
+
+    fn profile(id: &str, limit: &str, paused: bool) -> SpeedProfile {
+        SpeedProfile {
+            id: id.to_string(),
+            name: id.to_string(),
+            max_download_limit: limit.to_string(),
+            max_upload_limit: String::new(),
+            paused,
+        }
+    }
+
+    fn entry(days: &[u32], start: &str, end: &str, profile_id: &str) -> ScheduleEntry {
+        ScheduleEntry {
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+            profile_id: profile_id.to_string(),
+        }
+    }
+
+    fn config() -> AppConfig {
+        AppConfig {
+            global_max_download_limit: "0".to_string(),
+            bandwidth_schedule: BandwidthSchedule {
+                enabled: true,
+                profiles: vec![
+                    profile("work", "2M", false),
+                    profile("night", "", false),
+                    profile("weekend", "", true),
+                ],
+                entries: vec![
+                    entry(&[1, 2, 3, 4, 5], "09:00", "18:00", "work"),
+                    entry(&[1, 2, 3, 4, 5], "22:00", "06:00", "night"),
+                    entry(&[6, 7], "00:00", "00:00", "weekend"),
+                ],
+            },
+            ..AppConfig::default()
+        }
+    }
+
+    // 2026-10-19 为周一
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn resolves_weekly_entries() {
+        let config = config();
+        assert_eq!(resolve(&config, at(19, 9, 0), None, false).id, "work");
+        assert_eq!(
+            resolve(&config, at(19, 18, 0), None, false).id,
+            DEFAULT_PROFILE_ID
+        );
+        assert_eq!(resolve(&config, at(19, 23, 30), None, false).id, "night");
+        // 周五 22:00 开始的时段延续到周六凌晨
+        assert_eq!(resolve(&config, at(24, 3, 0), None, false).id, "night");
+        assert_eq!(resolve(&config, at(24, 12, 0), None, false).id, "weekend");
+        // 周日夜间的跨午夜时段不属于计划，周一凌晨使用全局设置
+        assert_eq!(
+            resolve(&config, at(26, 3, 0), None, false).id,
+            DEFAULT_PROFILE_ID
+        );
+        assert!(resolve(&config, at(25, 12, 0), None, false).paused);
+        // 全天时段覆盖到午夜前的最后一分钟
+        assert_eq!(resolve(&config, at(25, 23, 59), None, false).id, "weekend");
+    }
+
+    #[test]
+    fn manual_override_wins_until_profile_removed() {
+        let mut config = config();
+        let active = resolve(&config, at(19, 10, 0), Some("night"), false);
+        assert_eq!(active.id, "night");
+        assert_eq!(active.source, ProfileSource::Manual);
+
+        let active = resolve(&config, at(19, 10, 0), Some(DEFAULT_PROFILE_ID), false);
+        assert_eq!(active.source, ProfileSource::Manual);
+        assert_eq!(active.max_download_limit, "0");
+
+        config
+            .bandwidth_schedule
+            .profiles
+            .retain(|p| p.id != "night");
+        assert_eq!(
+            resolve(&config, at(19, 10, 0), Some("night"), false).id,
+            "work"
+        );
+    }
+
+    #[test]
+    fn turtle_mode_takes_precedence() {
+        let config = config();
+        let active = resolve(&config, at(19, 10, 0), Some("night"), true);
+        assert_eq!(active.id, TURTLE_PROFILE_ID);
+        assert_eq!(active.source, ProfileSource::Turtle);
+        assert_eq!(active.max_download_limit, "512K");
+        // 计划中的暂停时段也被乌龟模式取代
+        assert!(!resolve(&config, at(25, 12, 0), None, true).paused);
+    }
+
+    #[test]
+    fn disabled_schedule_uses_global_limits() {
+        let mut config = config();
+        config.bandwidth_schedule.enabled = false;
+        let active = resolve(&config, at(19, 10, 0), None, false);
+        assert_eq!(active.source, ProfileSource::Default);
+    }
+
+    #[test]
+    fn validation_rejects_bad_schedule() {
+        let mut config = config();
+        assert!(validate(&config).is_ok());
+
+        config
+            .bandwidth_schedule
+            .entries
+            .push(entry(&[8], "01:00", "02:00", "work"));
+        assert!(validate(&config).is_err());
+
+        config.bandwidth_schedule.entries.pop();
+        config
+            .bandwidth_schedule
+            .entries
+            .push(entry(&[], "25:00", "02:00", "work"));
+        assert!(validate(&config).is_err());
+
+        config.bandwidth_schedule.entries.pop();
+        config
+            .bandwidth_schedule
+            .profiles
+            .push(profile("bad", "2X", false));
+        assert!(validate(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn paused_profile_only_resumes_its_own_tasks() {
+        use crate::aria2::mock::{persisted_task, MockAria2, MockTask};
+
+        let aria2 = MockAria2::start().await;
+        let backend = aria2.registry().get(DEFAULT_BACKEND_ID).unwrap();
+        aria2.insert(MockTask::new("a1", "active"));
+        aria2.insert(MockTask::new("w1", "waiting"));
+        aria2.insert(MockTask::new("p1", "paused"));
+        let store = TaskStore::new();
+        store.add_task(persisted_task("a1", TaskState::Active));
+        store.add_task(persisted_task("w1", TaskState::Waiting));
+        store.add_task(persisted_task("p1", TaskState::Paused));
+
+        let mut gids = pause_profile_tasks(&store, backend.as_ref()).await;
+        gids.sort();
+        assert_eq!(gids, vec!["a1", "w1"]);
+        assert_eq!(aria2.task("a1").unwrap().status, "paused");
+
+        resume_profile_tasks(&store, backend.as_ref(), gids).await;
+        assert_eq!(aria2.task("a1").unwrap().status, "waiting");
+        assert_eq!(aria2.task("w1").unwrap().status, "waiting");
+        assert_eq!(aria2.task("p1").unwrap().status, "paused");
+        assert_eq!(store.get_task("p1").unwrap().state, TaskState::Paused);
+    }
+}
@@ -1,4 +1,8 @@
+use crate::aria2::backend::{BackendRegistry, HttpBackend};
+use crate::aria2::client::DEFAULT_BACKEND_ID;
 use crate::aria2::sidecar;
+use crate::aria2::ws;
+use crate::core::bandwidth;
 use crate::core::commands;
 use crate::core::config::{self, ConfigState};
 use crate::core::store::TaskStore;
@@ -6,7 +10,7 @@ use crate::core::sync;
 use crate::core::types::TaskState;
 use crate::ui::tray;
 use serde_json::json;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::{App, Manager};
 
 /// 运行应用启动链路
@@ -27,10 +31,25 @@ pub fn run(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
     // 3. 初始化任务存储引擎
     let store = app.state::<TaskStore>();
     store.init(&handle);
+    // 历史保留策略在首轮后台同步中执行，清理结果随任务快照一并下发
+    store.request_retention();
 
-    // 4. 激活 Aria2 Sidecar 进程
-    #[cfg(desktop)]
-    sidecar::init_aria2_sidecar(handle.clone());
+    // 4. 激活 Aria2 后端：远程模式直接指向远程守护进程，否则启动本地 Sidecar
+    if config.remote_backend.enabled {
+        crate::app_info!(
+            "Core::Boot",
+            "remote_backend_enabled_skip_sidecar",
+            json!({ "host": config.remote_backend.host })
+        );
+        app.state::<BackendRegistry>().register(
+            DEFAULT_BACKEND_ID,
+            Arc::new(HttpBackend::remote(DEFAULT_BACKEND_ID, &config.remote_backend)),
+        );
+    } else {
+        #[cfg(desktop)]
+        sidecar::init_aria2_sidecar(handle.clone());
+    }
+    app.state::<BackendRegistry>().set_remotes(&config.remote_backends);
 
     // 5. 调试环境下注入日志增强插件
     if cfg!(debug_assertions) {
@@ -48,9 +67,14 @@ pub fn run(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
         let app_handle_resume = handle.clone();
         tauri::async_runtime::spawn(async move {
             let state = app_handle_resume.state::<TaskStore>();
+            let backends = app_handle_resume.state::<BackendRegistry>();
             let store_tasks = state.get_all();
 
             for task in store_tasks {
+                // 等待定时启动的任务由定时器负责恢复
+                if task.start_at.is_some() {
+                    continue;
+                }
                 if task.state == TaskState::Paused
                     || task.state == TaskState::Waiting
                     || task.state == TaskState::Active
@@ -60,17 +84,22 @@ pub fn run(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
                         "auto_resume_task",
                         json!({ "gid": task.gid })
                     );
-                    let _ = commands::resume_task(state.clone(), task.gid).await;
+                    let _ = commands::resume_task(state.clone(), backends.clone(), task.gid).await;
                 }
             }
         });
     }
 
-    // 7. 启动后台同步循环
-    sync::start_background_sync(handle.clone());
+    // 7. 建立 Aria2 WebSocket 通知通道，并启动后台同步循环
+    let notifications = ws::start_notification_listener(handle.clone());
+    sync::start_background_sync(handle.clone(), notifications);
+
+    // 8. 启动限速计划，并按已加载的配置刷新托盘菜单中的限速方案
+    bandwidth::start_scheduler(handle.clone());
+    tray::refresh_menu(&handle);
 
     // --- L4: 视图管理层 ---
-    // 8. 应用窗口启动显隐策略
+    // 9. 应用窗口启动显隐策略
     if config.start_minimized {
         if let Some(window) = app.get_webview_window("main") {
             crate::app_info!("Core::Boot", "window_start_minimized");
@@ -0,0 +1,149 @@
+//! 下载文件校验
+//! 添加任务时的期望校验值通过 aria2 的 `checksum` 选项下发（HTTP/FTP 下载完成后由 aria2 校验），
+//! 已完成的任务也可以随时在本地重新计算摘要并与期望值比对。
+
+use std::fmt;
+use std::path::Path;
+
+use crate::core::error::{AppError, AppResult};
+use crate::core::types::ChecksumStatus;
+use crate::utils;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Md5,
+}
+
+impl HashAlgorithm {
+    /// aria2 使用的算法名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "sha-1",
+            Self::Sha256 => "sha-256",
+            Self::Md5 => "md5",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "sha-1" | "sha1" => Some(Self::Sha1),
+            "sha-256" | "sha256" => Some(Self::Sha256),
+            "md5" => Some(Self::Md5),
+            _ => None,
+        }
+    }
+
+    /// 十六进制摘要的长度
+    fn hex_len(&self) -> usize {
+        match self {
+            Self::Sha1 => 40,
+            Self::Sha256 => 64,
+            Self::Md5 => 32,
+        }
+    }
+
+    pub fn hex_of_file(&self, path: &Path) -> std::io::Result<String> {
+        match self {
+            Self::Sha1 => utils::sha1_hex_of_file(path),
+            Self::Sha256 => utils::sha256_hex_of_file(path),
+            Self::Md5 => utils::md5_hex_of_file(path),
+        }
+    }
+}
+
+/// 期望的校验值，如 `sha-256=<hex>`，摘要统一为小写
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: HashAlgorithm,
+    pub digest: String,
+}
+
+impl Checksum {
+    pub fn parse(raw: &str) -> AppResult<Self> {
+        let invalid = || AppError::validation(format!("无效的校验值: {}", raw));
+
+        let (algorithm, digest) = raw.trim().split_once('=').ok_or_else(invalid)?;
+        let algorithm = HashAlgorithm::parse(algorithm).ok_or_else(|| {
+            AppError::validation(format!("不支持的校验算法: {}（可选 sha-1、sha-256、md5）", algorithm.trim()))
+        })?;
+
+        let digest = digest.trim().to_ascii_lowercase();
+        if digest.len() != algorithm.hex_len() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        Ok(Self { algorithm, digest })
+    }
+
+    /// 空字符串视为未设置
+    pub fn parse_optional(raw: Option<&str>) -> AppResult<Option<Self>> {
+        match raw.map(str::trim) {
+            Some(raw) if !raw.is_empty() => Self::parse(raw).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// 计算文件摘要并与期望值比对，返回校验结果与实际摘要
+    pub fn verify_file(&self, path: &Path) -> std::io::Result<(ChecksumStatus, String)> {
+        let actual = self.algorithm.hex_of_file(path)?;
+        let status = if actual == self.digest {
+            ChecksumStatus::Verified
+        } else {
+            ChecksumStatus::Mismatch
+        };
+        Ok((status, actual))
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.algorithm.as_str(), self.digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_normalizes() {
+        let checksum = Checksum::parse(" SHA256=D41D8CD98F00B204E9800998ECF8427ED41D8CD98F00B204E9800998ECF8427E ")
+            .unwrap();
+        assert_eq!(checksum.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(
+            checksum.to_string(),
+            "sha-256=d41d8cd98f00b204e9800998ecf8427ed41d8cd98f00b204e9800998ecf8427e"
+        );
+
+        assert!(Checksum::parse("md5=d41d8cd98f00b204e9800998ecf8427e").is_ok());
+        assert!(Checksum::parse("md5=d41d8cd9").is_err());
+        assert!(Checksum::parse("sha-1=zz41d8cd98f00b204e9800998ecf8427e0000000").is_err());
+        assert!(Checksum::parse("crc32=00000000").is_err());
+        assert!(Checksum::parse("d41d8cd98f00b204e9800998ecf8427e").is_err());
+        assert_eq!(Checksum::parse_optional(Some("  ")).unwrap(), None);
+    }
+
+    #[test]
+    fn verifies_file_contents() {
+        let path = crate::aria2::mock::temp_dir("checksum").join("abc.txt");
+        std::fs::write(&path, b"abc").unwrap();
+
+        let cases = [
+            ("md5=900150983cd24fb0d6963f7d28e17f72", ChecksumStatus::Verified),
+            ("sha-1=a9993e364706816aba3e25717850c26c9cd0d89d", ChecksumStatus::Verified),
+            (
+                "sha-256=ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                ChecksumStatus::Verified,
+            ),
+            ("md5=00000000000000000000000000000000", ChecksumStatus::Mismatch),
+        ];
+        for (raw, expected) in cases {
+            let (status, _) = Checksum::parse(raw).unwrap().verify_file(&path).unwrap();
+            assert_eq!(status, expected, "{}", raw);
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
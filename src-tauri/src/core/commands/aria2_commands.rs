@@ -1,7 +1,9 @@
 //! Aria2 相关命令
 //! 包含 Aria2 配置与版本信息操作
 
+use crate::aria2::backend::BackendRegistry;
 use crate::core::error::{AppError, AppResult};
+use crate::core::stats::GlobalStats;
 use tauri::{AppHandle, Manager};
 
 #[tauri::command]
@@ -260,3 +262,11 @@ pub async fn trust_custom_aria2_binary(app: AppHandle) -> AppResult<()> {
 
     Ok(())
 }
+
+/// 读取所有后端汇总后的全局速度与任务数量
+#[tauri::command]
+pub async fn get_global_stats(
+    backends: tauri::State<'_, BackendRegistry>,
+) -> AppResult<GlobalStats> {
+    crate::core::stats::fetch_global_stats(&backends).await
+}
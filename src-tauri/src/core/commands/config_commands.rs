@@ -1,12 +1,17 @@
 //! 配置相关命令
 //! 包含应用配置的读写操作
 
+use crate::aria2::backend::{BackendRegistry, HttpBackend};
+use crate::aria2::client::DEFAULT_BACKEND_ID;
+use crate::aria2::options::Aria2Options;
+use crate::core::bandwidth::{self, ActiveProfile};
+use crate::core::completion;
 use crate::core::error::AppResult;
+use crate::core::recovery::{RecoveryLog, RecoveryReport};
 use crate::core::events::EVENT_ARIA2_STDOUT;
+use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
-const INFINITE_SEED_TIME: &str = "999999999";
-
 #[tauri::command]
 pub async fn get_app_config(app: AppHandle) -> AppResult<crate::core::config::AppConfig> {
     Ok(crate::core::config::load_config(&app))
@@ -31,6 +36,12 @@ pub async fn save_app_config(
         );
     }
 
+    // 先校验 aria2 选项（大小、端口等），避免非法值写入磁盘
+    let options = Aria2Options::global_from_config(&config)?;
+    crate::core::rules::validate(&config)?;
+    crate::core::bandwidth::validate(&config)?;
+    crate::core::completion::validate(&config)?;
+
     // 1. 保存到磁盘
     crate::core::config::save_config(&app, &config)?;
 
@@ -41,94 +52,72 @@ pub async fn save_app_config(
         }
     }
 
-    // 3. 实时同步到正在运行的 Aria2 内核
-    let mut options = serde_json::Map::new();
-    options.insert(
-        "max-concurrent-downloads".to_string(),
-        serde_json::Value::String(config.max_concurrent_downloads.to_string()),
-    );
-
-    if !config.global_max_download_limit.is_empty() {
-        options.insert(
-            "max-download-limit".to_string(),
-            serde_json::Value::String(config.global_max_download_limit.clone()),
-        );
-    } else {
-        options.insert(
-            "max-download-limit".to_string(),
-            serde_json::Value::String("0".to_string()),
-        );
-    }
-
-    if !config.global_max_upload_limit.is_empty() {
-        options.insert(
-            "max-overall-upload-limit".to_string(),
-            serde_json::Value::String(config.global_max_upload_limit.clone()),
-        );
-    } else {
-        options.insert(
-            "max-overall-upload-limit".to_string(),
-            serde_json::Value::String("0".to_string()),
-        );
-    }
-
-    // BT 设置
-    if !config.bt_trackers.is_empty() {
-        let normalized_trackers = crate::utils::normalize_bt_trackers(&config.bt_trackers);
-        options.insert(
-            "bt-tracker".to_string(),
-            serde_json::Value::String(normalized_trackers),
+    // 按新的保留策略清理历史任务：交由后台同步执行，避免阻塞保存并由其通知前端
+    app.state::<crate::core::store::TaskStore>().request_retention();
+
+    // 3. 远程后端模式下刷新连接端点（本地/远程模式切换需重启生效）
+    let backends = app.state::<BackendRegistry>();
+    let default_is_remote = backends
+        .get(DEFAULT_BACKEND_ID)
+        .is_ok_and(|backend| !backend.is_exclusive());
+    if config.remote_backend.enabled && default_is_remote {
+        backends.register(
+            DEFAULT_BACKEND_ID,
+            Arc::new(HttpBackend::remote(DEFAULT_BACKEND_ID, &config.remote_backend)),
         );
     }
-
-    options.insert(
-        "enable-dht".to_string(),
-        serde_json::Value::String(config.enable_dht.to_string()),
-    );
-
-    options.insert(
-        "enable-peer-exchange".to_string(),
-        serde_json::Value::String(config.enable_peer_exchange.to_string()),
-    );
-
-    options.insert(
-        "seed-ratio".to_string(),
-        serde_json::Value::String(config.seed_ratio.to_string()),
-    );
-
-    if config.enable_seeding {
-        options.insert(
-            "seed-time".to_string(),
-            serde_json::Value::String(INFINITE_SEED_TIME.to_string()),
-        );
-    } else {
-        options.insert(
-            "seed-time".to_string(),
-            serde_json::Value::String("0".to_string()),
+    backends.set_remotes(&config.remote_backends);
+
+    // 4. 实时同步到正在运行的 Aria2 内核
+    let applied = match backends.get(DEFAULT_BACKEND_ID) {
+        Ok(backend) => backend.change_global_option(options.to_rpc_value()).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = applied {
+        crate::app_warn!(
+            "Core::Config",
+            "runtime_apply_failed",
+            serde_json::json!({ "error": e.to_string() })
         );
     }
 
-    options.insert(
-        "dht-listen-port".to_string(),
-        serde_json::Value::String(config.dht_listen_port.clone()),
-    );
-
-    options.insert(
-        "listen-port".to_string(),
-        serde_json::Value::String(config.listen_port.clone()),
-    );
-
-    if let Err(e) = crate::aria2::client::change_global_option(serde_json::Value::Object(options)).await {
+    // 5. 全局限速可能被当前时段的限速方案覆盖，重新下发；方案列表可能变化，刷新托盘菜单
+    if let Err(e) = bandwidth::apply(&app, true).await {
         crate::app_warn!(
             "Core::Config",
-            "runtime_apply_failed",
+            "bandwidth_apply_failed",
             serde_json::json!({ "error": e.to_string() })
         );
     }
+    crate::ui::tray::refresh_menu(&app);
 
     Ok(())
 }
 
+/// 当前生效的限速方案
+#[tauri::command]
+pub async fn get_bandwidth_profile(app: AppHandle) -> AppResult<ActiveProfile> {
+    Ok(bandwidth::current(&app))
+}
+
+/// 手动指定限速方案，`profile_id` 为空时恢复按计划切换
+#[tauri::command]
+pub async fn set_bandwidth_override(
+    app: AppHandle,
+    profile_id: Option<String>,
+) -> AppResult<ActiveProfile> {
+    let profile_id = profile_id.filter(|id| !id.trim().is_empty());
+    bandwidth::set_manual(&app, profile_id).await
+}
+
+/// 启动时发生的文件损坏恢复记录（前端挂载晚于事件发送时使用）
+#[tauri::command]
+pub async fn get_recovery_reports(
+    log: tauri::State<'_, RecoveryLog>,
+) -> AppResult<Vec<RecoveryReport>> {
+    Ok(log.all())
+}
+
 #[tauri::command]
 pub async fn show_main_window(app: AppHandle) -> AppResult<()> {
     if let Some(window) = app.get_webview_window("main") {
@@ -204,3 +193,15 @@ pub fn get_platform_info() -> PlatformInfo {
         arch: std::env::consts::ARCH.to_string(),
     }
 }
+
+/// 开启或关闭乌龟模式（备用限速）
+#[tauri::command]
+pub async fn set_turtle_mode(app: AppHandle, enabled: bool) -> AppResult<ActiveProfile> {
+    bandwidth::set_turtle(&app, enabled).await
+}
+
+/// 取消等待中的完成后睡眠 / 关机，返回是否有动作被取消
+#[tauri::command]
+pub async fn cancel_power_action(app: AppHandle) -> AppResult<bool> {
+    Ok(completion::cancel_power_action(&app))
+}
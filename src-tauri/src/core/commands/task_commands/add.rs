@@ -1,21 +1,48 @@
+use crate::aria2::backend::BackendRegistry;
 use crate::aria2::client as aria2_client;
+use crate::aria2::options::{split_headers, Aria2Options};
+use crate::core::checksum::Checksum;
+use crate::core::completion;
+use crate::core::config::{AppConfig, ConfigState, DownloadRule};
 use crate::core::error::{AppError, AppResult};
+use crate::core::magnet::{self, MagnetInfo};
+use crate::core::metalink::{self, MetalinkInfo};
+use crate::core::rules::{self, RuleSubject};
 use crate::core::store::{PersistedTask, TaskStore};
-use crate::core::types::{DownloadConfig, TaskState};
+use crate::core::timer;
+use crate::core::types::{CompletionAction, DownloadConfig, TaskState, TorrentDownloadConfig};
 use crate::utils;
 use base64::Engine as _;
 use chrono::Local;
 use futures::future::join_all;
+use serde::Serialize;
 use serde_json::json;
+use std::collections::HashSet;
 
 #[tauri::command]
 pub async fn add_download_tasks(
     state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    config_state: tauri::State<'_, ConfigState>,
     configs: Vec<DownloadConfig>,
 ) -> AppResult<Vec<Option<String>>> {
-    let futures = configs
-        .into_iter()
-        .map(|cfg| add_download_task_inner(&state, cfg));
+    let app_config = config_state.snapshot();
+    // 同一批次中的重复磁力链接只添加第一个（并发添加时无法通过 Store 查重）
+    let mut seen_hashes = HashSet::new();
+    let futures = configs.into_iter().map(|cfg| {
+        let duplicate = magnet_of(&cfg)
+            .and_then(|magnet| magnet.key().map(str::to_string))
+            .is_some_and(|hash| !seen_hashes.insert(hash));
+        let state = &state;
+        let backends = &backends;
+        let app_config = &app_config;
+        async move {
+            if duplicate {
+                return Err(AppError::validation("同一批次中存在重复的磁力链接"));
+            }
+            add_download_task_inner(state, backends, app_config, cfg).await
+        }
+    });
 
     let results = join_all(futures).await;
 
@@ -57,16 +84,119 @@ pub async fn parse_torrent(path: String) -> AppResult<crate::core::torrent::Torr
     crate::core::torrent::parse_torrent_file(&path)
 }
 
-async fn add_download_task_inner(state: &TaskStore, cfg: DownloadConfig) -> AppResult<String> {
+#[tauri::command]
+pub async fn parse_metalink(path: String) -> AppResult<MetalinkInfo> {
+    metalink::parse_metalink_file(&path)
+}
+
+#[tauri::command]
+pub async fn parse_magnet(uri: String) -> AppResult<MagnetInfo> {
+    magnet::parse_magnet(&uri)
+}
+
+/// 下载配置中的磁力链接（仅看第一个 URI），无法解析时返回 None
+fn magnet_of(cfg: &DownloadConfig) -> Option<MagnetInfo> {
+    cfg.urls
+        .first()
+        .filter(|url| magnet::is_magnet(url))
+        .and_then(|url| magnet::parse_magnet(url).ok())
+}
+
+/// 查找 infohash 相同、且未失败或移除的已有任务
+fn find_duplicate_torrent(state: &TaskStore, magnet: &MagnetInfo) -> Option<PersistedTask> {
+    state
+        .get_all()
+        .into_iter()
+        .filter(|t| !matches!(t.state, TaskState::Error | TaskState::Removed))
+        .find(|t| {
+            t.info_hash.as_deref().is_some_and(|hash| magnet.has_hash(hash))
+                || (magnet::is_magnet(&t.url)
+                    && magnet::parse_magnet(&t.url).is_ok_and(|m| m.same_torrent(magnet)))
+        })
+}
+
+/// 规则试运行结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RulePreview {
+    /// 参与匹配的任务特征
+    pub subject: RuleSubject,
+    pub rule_id: Option<String>,
+    pub rule_name: Option<String>,
+    /// 应用规则与分类默认值后的下载配置
+    pub config: DownloadConfig,
+}
+
+/// 试运行下载规则：显示给定链接（或种子）会命中哪条规则及最终参数，不会添加任务。
+/// 传入 `rules` 时使用尚未保存的规则列表，便于在设置页编辑时测试。
+#[tauri::command]
+pub async fn preview_download_rules(
+    config_state: tauri::State<'_, ConfigState>,
+    url: String,
+    torrent_path: Option<String>,
+    rules: Option<Vec<DownloadRule>>,
+) -> AppResult<RulePreview> {
+    let mut config = config_state.snapshot();
+    if let Some(rules) = rules {
+        config.rules = rules;
+        rules::validate(&config)?;
+    }
+
+    let cfg = DownloadConfig {
+        urls: vec![url],
+        torrent_config: torrent_path.map(|path| TorrentDownloadConfig {
+            path,
+            select_file: None,
+            trackers: None,
+        }),
+        ..DownloadConfig::default()
+    };
+
+    let evaluation = rules::evaluate(&config.rules, &cfg).await;
+    let rule = evaluation.rule;
+    let resolved = match rule {
+        Some(rule) => rules::apply_rule(cfg, rule),
+        None => cfg,
+    };
+
+    Ok(RulePreview {
+        subject: evaluation.subject,
+        rule_id: rule.map(|r| r.id.clone()),
+        rule_name: rule.map(|r| r.name.clone()),
+        config: apply_category_defaults(resolved, &config)?,
+    })
+}
+
+async fn add_download_task_inner(
+    state: &TaskStore,
+    registry: &BackendRegistry,
+    config: &AppConfig,
+    cfg: DownloadConfig,
+) -> AppResult<String> {
     crate::app_info!(
         "Core::TaskAdd",
         "add_task_requested",
-        json!({ "url_count": cfg.urls.len(), "is_torrent": cfg.torrent_config.is_some() })
+        json!({
+            "url_count": cfg.urls.len(),
+            "is_torrent": cfg.torrent_config.is_some(),
+            "is_metalink": cfg.metalink_config.is_some()
+        })
     );
 
+    let mut cfg = resolve_download_config(cfg, config).await?;
+    timer::normalize(&mut cfg, Local::now())?;
+    if let Some(ref action) = cfg.on_complete {
+        completion::validate_action(action)?;
+    }
+    let checksum = Checksum::parse_optional(cfg.checksum.as_deref())?;
+    cfg.checksum = checksum.as_ref().map(Checksum::to_string);
+
+    let backend = resolve_target_backend(registry, &cfg)?;
+
     if let Some(ref torrent_cfg) = cfg.torrent_config {
         return add_torrent_task_inner(
             state,
+            registry,
             torrent_cfg.path.clone(),
             torrent_cfg.select_file.clone(),
             torrent_cfg.trackers.clone(),
@@ -75,12 +205,37 @@ async fn add_download_task_inner(state: &TaskStore, cfg: DownloadConfig) -> AppR
         .await;
     }
 
+    if let Some(ref metalink_cfg) = cfg.metalink_config {
+        return add_metalink_task_inner(
+            state,
+            registry,
+            metalink_cfg.path.clone(),
+            metalink_cfg.select_file.clone(),
+            &cfg,
+        )
+        .await;
+    }
+
     for url in &cfg.urls {
         if !utils::is_valid_url(url) {
             return Err(AppError::validation(format!("无效的 URL: {}", url)));
         }
     }
 
+    let magnet = match cfg.urls.first() {
+        Some(url) if magnet::is_magnet(url) => Some(magnet::parse_magnet(url)?),
+        _ => None,
+    };
+    if let Some(ref magnet) = magnet {
+        reject_checksum(&cfg, "磁力链接任务由 BitTorrent 分块校验保证完整性")?;
+        if let Some(existing) = find_duplicate_torrent(state, magnet) {
+            return Err(AppError::validation(format!(
+                "该资源已在任务列表中: {}",
+                existing.filename
+            )));
+        }
+    }
+
     let deduced_name = utils::deduce_filename(cfg.filename.clone(), &cfg.urls);
 
     let resolved_save_path = if let Some(ref path) = cfg.save_path {
@@ -93,7 +248,7 @@ async fn add_download_task_inner(state: &TaskStore, cfg: DownloadConfig) -> AppR
     let unique_filename =
         utils::get_unique_filename(&resolved_save_path, &deduced_name, &active_names);
 
-    let (options, final_save_path) = utils::build_aria2_options(
+    let mut options = Aria2Options::for_task(
         cfg.save_path.clone(),
         Some(unique_filename.clone()),
         cfg.user_agent.clone(),
@@ -101,17 +256,35 @@ async fn add_download_task_inner(state: &TaskStore, cfg: DownloadConfig) -> AppR
         cfg.headers.clone(),
         cfg.proxy.clone(),
         cfg.max_download_limit.clone(),
-    );
+    )?;
+    // 定时启动的任务先以暂停状态加入
+    options.pause = cfg.start_at.is_some().then_some(true);
+    options.checksum = checksum;
+    // 任务级 bt-tracker 会覆盖全局设置，因此需与全局 Tracker 合并
+    if let Some(magnet) = magnet.as_ref().filter(|m| !m.trackers.is_empty()) {
+        options.bt_tracker = Some(utils::normalize_bt_trackers(&format!(
+            "{},{}",
+            magnet.trackers.join(","),
+            config.bt_trackers
+        )));
+    }
+    let final_save_path = options.save_dir();
 
-    match aria2_client::add_uri(cfg.urls.clone(), Some(options)).await {
+    match registry
+        .get(&backend)?
+        .add_uri(cfg.urls.clone(), Some(options.to_rpc_value()))
+        .await
+    {
         Ok(gid) => {
-            let task = create_persisted_task(
+            let mut task = create_persisted_task(
                 gid.clone(),
                 unique_filename,
                 cfg.urls.get(0).cloned().unwrap_or_default(),
                 final_save_path,
+                backend,
                 &cfg,
             );
+            task.info_hash = magnet.as_ref().and_then(|m| m.key().map(str::to_string));
             state.add_task(task);
             Ok(gid)
         }
@@ -119,11 +292,85 @@ async fn add_download_task_inner(state: &TaskStore, cfg: DownloadConfig) -> AppR
     }
 }
 
+/// 依次应用第一条命中的下载规则与分类默认值，得到最终的下载配置
+async fn resolve_download_config(cfg: DownloadConfig, config: &AppConfig) -> AppResult<DownloadConfig> {
+    let evaluation = rules::evaluate(&config.rules, &cfg).await;
+    let cfg = match evaluation.rule {
+        Some(rule) => {
+            crate::app_info!(
+                "Core::TaskAdd",
+                "download_rule_matched",
+                json!({ "rule_id": rule.id, "rule_name": rule.name, "host": evaluation.subject.host })
+            );
+            rules::apply_rule(cfg, rule)
+        }
+        None => cfg,
+    };
+    apply_category_defaults(cfg, config)
+}
+
+/// 按任务所属分类补全未指定的保存目录、限速与 UA，并规范化标签
+fn apply_category_defaults(mut cfg: DownloadConfig, config: &AppConfig) -> AppResult<DownloadConfig> {
+    cfg.category = cfg
+        .category
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    cfg.tags = normalize_tags(&cfg.tags);
+
+    let Some(id) = cfg.category.as_deref() else {
+        return Ok(cfg);
+    };
+    let category = config
+        .category(id)
+        .ok_or_else(|| AppError::validation(format!("未知的分类: {}", id)))?;
+
+    fn fill(target: &mut Option<String>, default: &str) {
+        let missing = target.as_deref().map(str::trim).unwrap_or_default().is_empty();
+        if missing && !default.is_empty() {
+            *target = Some(default.to_string());
+        }
+    }
+    fill(&mut cfg.save_path, &category.default_dir);
+    fill(&mut cfg.max_download_limit, &category.max_download_limit);
+    fill(&mut cfg.user_agent, &category.user_agent);
+
+    Ok(cfg)
+}
+
+/// 去除首尾空白、空标签与重复标签，保留原有顺序
+pub(crate) fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !result.iter().any(|existing| existing == tag) {
+            result.push(tag.to_string());
+        }
+    }
+    result
+}
+
+/// 解析任务目标后端，并校验其已注册
+fn resolve_target_backend(registry: &BackendRegistry, cfg: &DownloadConfig) -> AppResult<String> {
+    let backend = cfg
+        .backend_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .unwrap_or(aria2_client::DEFAULT_BACKEND_ID)
+        .to_string();
+
+    if !registry.contains(&backend) {
+        return Err(AppError::validation(format!("未知的 aria2 后端: {}", backend)));
+    }
+
+    Ok(backend)
+}
+
 fn create_persisted_task(
     gid: String,
     filename: String,
     url: String,
     save_path: String,
+    backend_id: String,
     cfg: &DownloadConfig,
 ) -> PersistedTask {
     PersistedTask {
@@ -132,37 +379,66 @@ fn create_persisted_task(
         url,
         save_path,
         added_at: Local::now().to_rfc3339(),
-        state: TaskState::Waiting,
-        total_length: "0".to_string(),
-        completed_length: "0".to_string(),
-        download_speed: "0 B/s".to_string(),
+        state: if cfg.start_at.is_some() {
+            TaskState::Paused
+        } else {
+            TaskState::Waiting
+        },
+        total_length: 0,
+        completed_length: 0,
+        download_speed: 0,
         completed_at: None,
         error_message: "".to_string(),
+        error_kind: None,
+        retry_attempts: 0,
+        next_retry_at: None,
         user_agent: cfg.user_agent.clone().unwrap_or_default(),
         referer: cfg.referer.clone().unwrap_or_default(),
         proxy: cfg.proxy.clone().unwrap_or_default(),
         headers: cfg
             .headers
-            .clone()
-            .unwrap_or_default()
-            .split(|c| c == ';' || c == '\n')
-            .filter(|s| !s.trim().is_empty())
-            .map(|s| s.trim().to_string())
-            .collect(),
+            .as_deref()
+            .map(split_headers)
+            .unwrap_or_default(),
         max_download_limit: cfg.max_download_limit.clone().unwrap_or_default(),
         // Torrent 扩展参数持久化
-        select_file: cfg.torrent_config.as_ref().and_then(|t| t.select_file.clone()),
+        select_file: cfg
+            .torrent_config
+            .as_ref()
+            .and_then(|t| t.select_file.clone())
+            .or_else(|| cfg.metalink_config.as_ref().and_then(|m| m.select_file.clone())),
         trackers: cfg.torrent_config.as_ref().and_then(|t| t.trackers.clone()),
+        backend_id,
+        queue_position: None,
+        category: cfg.category.clone(),
+        tags: cfg.tags.clone(),
+        start_at: cfg.start_at.clone(),
+        stop_at: cfg.stop_at.clone(),
+        on_complete: cfg.on_complete.clone().filter(|a| *a != CompletionAction::None),
+        checksum: cfg.checksum.clone(),
+        checksum_status: None,
+        info_hash: None,
+    }
+}
+
+/// aria2 的 `checksum` 选项只对 HTTP/FTP 下载生效，其他类型的任务设置时直接拒绝，避免误以为已校验
+fn reject_checksum(cfg: &DownloadConfig, reason: &str) -> AppResult<()> {
+    if cfg.checksum.as_deref().is_some_and(|c| !c.trim().is_empty()) {
+        return Err(AppError::validation(format!("不支持为此任务设置校验值：{}", reason)));
     }
+    Ok(())
 }
 
 pub(crate) async fn add_torrent_task_inner(
     state: &TaskStore,
+    registry: &BackendRegistry,
     path: String,
     select_file: Option<String>,
     trackers: Option<String>,
     base_cfg: &DownloadConfig,
 ) -> AppResult<String> {
+    reject_checksum(base_cfg, "种子任务由 BitTorrent 分块校验保证完整性")?;
+
     // 尝试解析种子以获取显示名称
     let display_name = crate::core::torrent::parse_torrent_file(&path)
         .map(|info| info.name)
@@ -177,7 +453,7 @@ pub(crate) async fn add_torrent_task_inner(
     let content = std::fs::read(&path).map_err(|e| AppError::Fs(e.to_string()))?;
     let torrent_b64 = base64::engine::general_purpose::STANDARD.encode(&content);
 
-    let (mut options_val, final_save_path) = utils::build_aria2_options(
+    let mut options = Aria2Options::for_task(
         base_cfg.save_path.clone(),
         None,
         base_cfg.user_agent.clone(),
@@ -185,38 +461,31 @@ pub(crate) async fn add_torrent_task_inner(
         base_cfg.headers.clone(),
         base_cfg.proxy.clone(),
         base_cfg.max_download_limit.clone(),
-    );
+    )?;
+    let final_save_path = options.save_dir();
 
-    if let Some(opts) = options_val.as_object_mut() {
-        if let Some(sf) = select_file.and_then(|v| {
-            let t = v.trim().to_string();
-            if t.is_empty() { None } else { Some(t) }
-        }) {
-            opts.insert("select-file".to_string(), serde_json::Value::String(sf));
-        }
+    options.select_file = select_file
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    options.bt_tracker = trackers
+        .map(|v| utils::normalize_bt_trackers(&v))
+        .filter(|v| !v.is_empty());
+    options.pause = base_cfg.start_at.is_some().then_some(true);
 
-        if let Some(bt_trackers) = trackers.and_then(|v| {
-            let normalized = utils::normalize_bt_trackers(&v);
-            if normalized.is_empty() {
-                None
-            } else {
-                Some(normalized)
-            }
-        }) {
-            opts.insert(
-                "bt-tracker".to_string(),
-                serde_json::Value::String(bt_trackers),
-            );
-        }
-    }
+    let backend = resolve_target_backend(registry, base_cfg)?;
 
-    match aria2_client::add_torrent(torrent_b64, Some(options_val)).await {
+    match registry
+        .get(&backend)?
+        .add_torrent(torrent_b64, Some(options.to_rpc_value()))
+        .await
+    {
         Ok(gid) => {
             let task = create_persisted_task(
                 gid.clone(),
                 display_name,
                 format!("file://{}", path),
                 final_save_path,
+                backend,
                 base_cfg,
             );
             state.add_task(task);
@@ -225,3 +494,247 @@ pub(crate) async fn add_torrent_task_inner(
         Err(e) => Err(e),
     }
 }
+
+/// 添加 Metalink 任务。aria2 为每个选中的文件各创建一个任务，全部写入 Store，返回第一个 GID。
+/// 能与文件一一对应时，每个任务记录自己的文件序号，智能恢复时只重新下载该文件。
+pub(crate) async fn add_metalink_task_inner(
+    state: &TaskStore,
+    registry: &BackendRegistry,
+    path: String,
+    select_file: Option<String>,
+    base_cfg: &DownloadConfig,
+) -> AppResult<String> {
+    reject_checksum(base_cfg, "Metalink 任务使用文件中声明的校验值")?;
+
+    let select_file = select_file
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    let info = metalink::parse_metalink_file(&path)?;
+    let selected = metalink::select_files(&info, select_file.as_deref())?;
+
+    let content = std::fs::read(&path).map_err(|e| AppError::Fs(e.to_string()))?;
+    let metalink_b64 = base64::engine::general_purpose::STANDARD.encode(&content);
+
+    let mut options = Aria2Options::for_task(
+        base_cfg.save_path.clone(),
+        None,
+        base_cfg.user_agent.clone(),
+        base_cfg.referer.clone(),
+        base_cfg.headers.clone(),
+        base_cfg.proxy.clone(),
+        base_cfg.max_download_limit.clone(),
+    )?;
+    let final_save_path = options.save_dir();
+    options.select_file = select_file.clone();
+    options.pause = base_cfg.start_at.is_some().then_some(true);
+
+    let backend = resolve_target_backend(registry, base_cfg)?;
+    let gids = registry
+        .get(&backend)?
+        .add_metalink(metalink_b64, Some(options.to_rpc_value()))
+        .await?;
+
+    let fallback_name = std::path::Path::new(&path)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    // 任务数与选中的文件数不一致时无法对应，文件名随后由同步循环从 aria2 更新
+    let matched = gids.len() == selected.len();
+
+    for (i, gid) in gids.iter().enumerate() {
+        let file = selected.get(i).filter(|_| matched);
+        let mut task = create_persisted_task(
+            gid.clone(),
+            file.map(|f| f.name.clone()).unwrap_or_else(|| fallback_name.clone()),
+            format!("file://{}", path),
+            final_save_path.clone(),
+            backend.clone(),
+            base_cfg,
+        );
+        task.select_file = match file {
+            Some(file) => Some(file.index.to_string()),
+            None => select_file.clone(),
+        };
+        state.add_task(task);
+    }
+
+    crate::app_info!(
+        "Core::TaskAdd",
+        "metalink_added",
+        json!({ "path": path, "task_count": gids.len() })
+    );
+
+    gids.into_iter()
+        .next()
+        .ok_or_else(|| AppError::aria2("Metalink 中没有可下载的文件"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::MockAria2;
+    use crate::core::config::Category;
+    use crate::core::types::MetalinkDownloadConfig;
+
+    fn config_with_category() -> AppConfig {
+        AppConfig {
+            categories: vec![Category {
+                id: "video".to_string(),
+                name: "视频".to_string(),
+                color: "#ff5555".to_string(),
+                default_dir: "/media/videos".to_string(),
+                max_download_limit: "2M".to_string(),
+                user_agent: String::new(),
+            }],
+            ..AppConfig::default()
+        }
+    }
+
+    fn download(category: Option<&str>, save_path: Option<&str>) -> DownloadConfig {
+        DownloadConfig {
+            urls: vec!["https://example.com/a.mp4".to_string()],
+            save_path: save_path.map(str::to_string),
+            category: category.map(str::to_string),
+            tags: vec![" movie ".to_string(), "".to_string(), "movie".to_string(), "4k".to_string()],
+            ..DownloadConfig::default()
+        }
+    }
+
+    #[test]
+    fn category_fills_missing_defaults() {
+        let cfg = apply_category_defaults(download(Some("video"), Some("")), &config_with_category())
+            .unwrap();
+        assert_eq!(cfg.save_path.as_deref(), Some("/media/videos"));
+        assert_eq!(cfg.max_download_limit.as_deref(), Some("2M"));
+        // 分类未设置 UA 时保持为空
+        assert_eq!(cfg.user_agent, None);
+        assert_eq!(cfg.tags, vec!["movie", "4k"]);
+    }
+
+    #[test]
+    fn explicit_values_override_category() {
+        let cfg = apply_category_defaults(download(Some("video"), Some("/tmp")), &config_with_category())
+            .unwrap();
+        assert_eq!(cfg.save_path.as_deref(), Some("/tmp"));
+    }
+
+    #[test]
+    fn unknown_category_is_rejected() {
+        let result = apply_category_defaults(download(Some("music"), None), &config_with_category());
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn metalink_tasks_record_their_file_index() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+
+        let path = crate::aria2::mock::temp_dir("metalink").join("a.meta4");
+        std::fs::write(
+            &path,
+            r#"<metalink xmlns="urn:ietf:params:xml:ns:metalink">
+  <file name="a.iso"><url>https://example.com/a.iso</url></file>
+  <file name="b.iso"><url>https://example.com/b.iso</url></file>
+</metalink>"#,
+        )
+        .unwrap();
+
+        let cfg = DownloadConfig {
+            urls: Vec::new(),
+            tags: Vec::new(),
+            metalink_config: Some(MetalinkDownloadConfig {
+                path: path.to_string_lossy().to_string(),
+                select_file: Some("2".to_string()),
+            }),
+            ..download(None, Some("/downloads"))
+        };
+        let gid = add_download_task_inner(&store, &backends, &AppConfig::default(), cfg)
+            .await
+            .unwrap();
+
+        let options = &aria2.task(&gid).unwrap().options;
+        assert_eq!(options.get("select-file").and_then(|v| v.as_str()), Some("2"));
+        let task = store.get_task(&gid).unwrap();
+        assert_eq!(task.filename, "b.iso");
+        assert_eq!(task.select_file.as_deref(), Some("2"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn magnet_uses_display_name_and_rejects_duplicates() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        let config = AppConfig {
+            bt_trackers: "udp://global.example.org:6969".to_string(),
+            ..AppConfig::default()
+        };
+        let hash = "c12fe1c06bba254a9dc9f519b335aa7c1367a88a";
+        let magnet = |uri: String| DownloadConfig {
+            urls: vec![uri],
+            tags: Vec::new(),
+            ..download(None, None)
+        };
+
+        let gid = add_download_task_inner(
+            &store,
+            &backends,
+            &config,
+            magnet(format!("magnet:?xt=urn:btih:{}&dn=Big+Buck+Bunny&tr=udp%3A%2F%2Ft.example.org%3A1337", hash)),
+        )
+        .await
+        .unwrap();
+
+        let task = store.get_task(&gid).unwrap();
+        assert_eq!(task.filename, "Big Buck Bunny");
+        assert_eq!(task.info_hash.as_deref(), Some(hash));
+        let options = &aria2.task(&gid).unwrap().options;
+        assert_eq!(
+            options.get("bt-tracker").and_then(|v| v.as_str()),
+            Some("udp://t.example.org:1337,udp://global.example.org:6969")
+        );
+
+        // 同一 infohash 的 Base32 形式同样视为重复
+        let duplicate = add_download_task_inner(
+            &store,
+            &backends,
+            &config,
+            magnet("magnet:?xt=urn:btih:YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKEK".to_string()),
+        )
+        .await;
+        assert!(matches!(duplicate, Err(AppError::Validation(_))));
+        assert_eq!(aria2.calls("aria2.addUri").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn checksum_is_rejected_for_non_http_tasks() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        let checksum = Some("md5=900150983cd24fb0d6963f7d28e17f72".to_string());
+
+        let magnet = DownloadConfig {
+            urls: vec!["magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a".to_string()],
+            checksum: checksum.clone(),
+            ..download(None, None)
+        };
+        let result = add_download_task_inner(&store, &backends, &AppConfig::default(), magnet).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        let metalink = DownloadConfig {
+            urls: Vec::new(),
+            metalink_config: Some(MetalinkDownloadConfig {
+                path: "/nonexistent/a.meta4".to_string(),
+                select_file: None,
+            }),
+            checksum,
+            ..download(None, None)
+        };
+        let result = add_download_task_inner(&store, &backends, &AppConfig::default(), metalink).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+        assert!(aria2.calls("aria2.addUri").is_empty());
+    }
+}
@@ -1,21 +1,32 @@
+use crate::aria2::backend::{Aria2Backend, BackendRegistry};
 use crate::aria2::client as aria2_client;
+use crate::aria2::options::Aria2Options;
+use crate::core::checksum::Checksum;
 use crate::core::error::{AppError, AppResult};
 use crate::core::store::{PersistedTask, TaskStore};
 use crate::core::types::TaskState;
-use crate::utils;
 use chrono::Local;
 use futures::future::join_all;
 use serde_json::json;
+use std::sync::Arc;
 
 #[tauri::command]
-pub async fn pause_task(state: tauri::State<'_, TaskStore>, gid: String) -> AppResult<()> {
-    aria2_client::pause(gid.clone()).await?;
+pub async fn pause_task(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<()> {
+    backends.get(&state.backend_of(&gid))?.pause(gid.clone()).await?;
     state.update_task_state(&gid, TaskState::Paused);
     Ok(())
 }
 
 #[tauri::command]
-pub async fn resume_task(state: tauri::State<'_, TaskStore>, gid: String) -> AppResult<String> {
+pub async fn resume_task(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<String> {
     let should_smart_resume = if let Some(task) = state.get_task(&gid) {
         TaskState::from(task.state.as_str()).is_terminal()
     } else {
@@ -23,17 +34,17 @@ pub async fn resume_task(state: tauri::State<'_, TaskStore>, gid: String) -> App
     };
 
     if should_smart_resume {
-        return smart_resume_task(&state, gid).await;
+        return smart_resume_task(&state, &backends, gid).await;
     }
 
-    match aria2_client::resume(gid.clone()).await {
+    match backends.get(&state.backend_of(&gid))?.resume(gid.clone()).await {
         Ok(res) => {
             state.update_task_state(&gid, TaskState::Waiting);
             Ok(res)
         }
         Err(e) => {
             if e.is_aria2_not_found() {
-                smart_resume_task(&state, gid).await
+                smart_resume_task(&state, &backends, gid).await
             } else {
                 Err(e)
             }
@@ -42,34 +53,119 @@ pub async fn resume_task(state: tauri::State<'_, TaskStore>, gid: String) -> App
 }
 
 #[tauri::command]
-pub async fn cancel_task(state: tauri::State<'_, TaskStore>, gid: String) -> AppResult<()> {
-    aria2_client::remove(gid.clone()).await?;
+pub async fn cancel_task(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<()> {
+    backends.get(&state.backend_of(&gid))?.remove(gid.clone()).await?;
     state.update_task_state(&gid, TaskState::Removed);
     Ok(())
 }
 
 #[tauri::command]
-pub async fn pause_all_tasks(state: tauri::State<'_, TaskStore>) -> AppResult<()> {
-    aria2_client::pause_all().await?;
+pub async fn pause_all_tasks(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+) -> AppResult<()> {
+    for_each_backend(&backends, "pause_all", |backend| async move {
+        backend.pause_all().await
+    })
+    .await?;
     state.update_all_active_to_paused();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn resume_all_tasks(state: tauri::State<'_, TaskStore>) -> AppResult<()> {
-    aria2_client::unpause_all().await?;
-    state.update_all_paused_to_waiting();
+pub async fn resume_all_tasks(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+) -> AppResult<()> {
+    resume_paused_tasks(&state, &backends).await
+}
+
+/// 逐个恢复已暂停的任务。不使用 `unpauseAll`：设置了 `start_at` 的任务
+/// 需保持暂停，直到定时器到期启动。
+/// 默认后端上的失败（任务已不存在除外）在处理完其余任务后返回。
+pub(crate) async fn resume_paused_tasks(store: &TaskStore, registry: &BackendRegistry) -> AppResult<()> {
+    let paused = store
+        .get_all()
+        .into_iter()
+        .filter(|t| t.state == TaskState::Paused && t.start_at.is_none());
+
+    let mut first_error = None;
+    for task in paused {
+        let result = match registry.get(&task.backend_id) {
+            Ok(backend) => backend.resume(task.gid.clone()).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(_) => store.update_task_state(&task.gid, TaskState::Waiting),
+            Err(e) if e.is_aria2_not_found() => {}
+            Err(e) => {
+                crate::app_warn!(
+                    "Core::TaskControl",
+                    "resume_failed",
+                    json!({ "gid": task.gid, "backend": task.backend_id, "error": e.to_string() })
+                );
+                if task.backend_id == aria2_client::DEFAULT_BACKEND_ID && first_error.is_none() {
+                    first_error = Some(e);
+                }
+            }
+        }
+    }
+
+    first_error.map_or(Ok(()), Err)
+}
+
+/// 在所有已注册后端上执行同一操作。
+/// 默认后端失败时返回错误；其他后端失败仅记录日志，不影响其余后端。
+pub(crate) async fn for_each_backend<F, Fut>(
+    registry: &BackendRegistry,
+    scope: &str,
+    op: F,
+) -> AppResult<()>
+where
+    F: Fn(Arc<dyn Aria2Backend>) -> Fut,
+    Fut: std::future::Future<Output = AppResult<String>>,
+{
+    let ids = registry.ids();
+    let results = join_all(ids.iter().map(|id| {
+        let backend = registry.get(id);
+        let op = &op;
+        async move { op(backend?).await }
+    }))
+    .await;
+
+    for (backend, result) in ids.iter().zip(results) {
+        if let Err(error) = result {
+            if backend == aria2_client::DEFAULT_BACKEND_ID {
+                return Err(error);
+            }
+            crate::app_warn!(
+                "Core::TaskControl",
+                "backend_operation_failed",
+                json!({ "scope": scope, "backend": backend, "error": error.to_string() })
+            );
+        }
+    }
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn cancel_tasks(
     state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
     gids: Vec<String>,
 ) -> AppResult<()> {
     let futures: Vec<_> = gids
         .iter()
-        .map(|gid| aria2_client::remove(gid.clone()))
+        .map(|gid| {
+            let backend = backends.get(&state.backend_of(gid));
+            let gid = gid.clone();
+            async move { backend?.remove(gid).await }
+        })
         .collect();
     let results = join_all(futures).await;
 
@@ -101,7 +197,11 @@ pub async fn cancel_tasks(
     Ok(())
 }
 
-async fn smart_resume_task(state: &TaskStore, gid: String) -> AppResult<String> {
+pub(crate) async fn smart_resume_task(
+    state: &TaskStore,
+    registry: &BackendRegistry,
+    gid: String,
+) -> AppResult<String> {
     crate::app_info!(
         "Core::TaskControl",
         "smart_resume_started",
@@ -152,7 +252,7 @@ async fn smart_resume_task(state: &TaskStore, gid: String) -> AppResult<String>
             Some(task.headers.join("; "))
         };
 
-        let (options, _) = utils::build_aria2_options(
+        let mut options = Aria2Options::for_task(
             save_path_opt.clone(),
             filename_opt.clone(),
             ua_opt.clone(),
@@ -160,9 +260,11 @@ async fn smart_resume_task(state: &TaskStore, gid: String) -> AppResult<String>
             headers_str.clone(),
             proxy_opt.clone(),
             limit_opt.clone(),
-        );
+        )?;
+        options.checksum = Checksum::parse_optional(task.checksum.as_deref())?;
 
-        if let Err(error) = aria2_client::purge(gid.clone()).await {
+        let backend = registry.get(&task.backend_id)?;
+        if let Err(error) = backend.purge(gid.clone()).await {
             crate::app_warn!(
                 "Core::TaskControl",
                 "smart_resume_purge_failed",
@@ -170,12 +272,11 @@ async fn smart_resume_task(state: &TaskStore, gid: String) -> AppResult<String>
             );
         }
 
-        // 区分重下逻辑：URL (HTTP/Magnet) vs Local Torrent File
+        // 区分重下逻辑：URL (HTTP/Magnet) vs Local Torrent / Metalink File
         let result = if task.url.starts_with("file://") {
             let path = task.url.trim_start_matches("file://").to_string();
             // 构建基础 Config 传递通用设置
             let base_cfg = crate::core::types::DownloadConfig {
-                urls: vec![],
                 save_path: save_path_opt,
                 filename: filename_opt,
                 user_agent: ua_opt,
@@ -183,17 +284,39 @@ async fn smart_resume_task(state: &TaskStore, gid: String) -> AppResult<String>
                 headers: if task.headers.is_empty() { None } else { Some(task.headers.join("; ")) },
                 proxy: proxy_opt,
                 max_download_limit: limit_opt,
-                torrent_config: None, // 内部调用不嵌套
+                backend_id: Some(task.backend_id.clone()),
+                category: task.category.clone(),
+                tags: task.tags.clone(),
+                // 智能恢复即立即启动，仅保留定时停止
+                start_at: None,
+                stop_at: task.stop_at.clone(),
+                on_complete: task.on_complete.clone(),
+                checksum: task.checksum.clone(),
+                // 内部调用不嵌套 torrent / metalink 配置
+                ..Default::default()
             };
-            super::add::add_torrent_task_inner(
-                state,
-                path,
-                task.select_file.clone(),
-                task.trackers.clone(),
-                &base_cfg
-            ).await
+            if crate::core::metalink::is_metalink_path(&path) {
+                super::add::add_metalink_task_inner(
+                    state,
+                    registry,
+                    path,
+                    task.select_file.clone(),
+                    &base_cfg
+                ).await
+            } else {
+                super::add::add_torrent_task_inner(
+                    state,
+                    registry,
+                    path,
+                    task.select_file.clone(),
+                    task.trackers.clone(),
+                    &base_cfg
+                ).await
+            }
         } else {
-            aria2_client::add_uri(vec![task.url.clone()], Some(options)).await
+            backend
+                .add_uri(vec![task.url.clone()], Some(options.to_rpc_value()))
+                .await
         };
 
         match result {
@@ -215,10 +338,17 @@ async fn smart_resume_task(state: &TaskStore, gid: String) -> AppResult<String>
                     gid: new_gid.clone(),
                     state: TaskState::Waiting,
                     added_at: Local::now().to_rfc3339(),
-                    total_length: "0".to_string(),
-                    completed_length: "0".to_string(),
-                    download_speed: "0".to_string(),
+                    total_length: 0,
+                    completed_length: 0,
+                    download_speed: 0,
                     completed_at: None,
+                    error_message: String::new(),
+                    error_kind: None,
+                    retry_attempts: 0,
+                    next_retry_at: None,
+                    queue_position: None,
+                    start_at: None,
+                    checksum_status: None,
                     ..task
                 };
                 state.add_task(new_task);
@@ -238,3 +368,94 @@ async fn smart_resume_task(state: &TaskStore, gid: String) -> AppResult<String>
         Err(AppError::task_not_found(gid))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::{persisted_task, MockAria2, MockTask};
+
+    #[tokio::test]
+    async fn smart_resume_readds_lost_uri_task() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        let mut task = persisted_task("old", TaskState::Error);
+        task.user_agent = "Mua-Test".to_string();
+        task.headers = vec!["X-Token: 1".to_string()];
+        store.add_task(task);
+
+        let new_gid = smart_resume_task(&store, &backends, "old".to_string()).await.unwrap();
+
+        assert_ne!(new_gid, "old");
+        assert!(store.get_task("old").is_none());
+        let resumed = store.get_task(&new_gid).unwrap();
+        assert_eq!(resumed.state, TaskState::Waiting);
+        assert_eq!(resumed.user_agent, "Mua-Test");
+
+        let added = aria2.task(&new_gid).unwrap();
+        assert_eq!(added.uris, vec!["https://example.com/old.bin".to_string()]);
+        assert_eq!(added.options.get("user-agent"), Some(&json!("Mua-Test")));
+        assert_eq!(added.options.get("out"), Some(&json!("old.bin")));
+        assert_eq!(added.options.get("header"), Some(&json!(["X-Token: 1"])));
+        // 旧 GID 已不在 aria2 中，purge 失败不影响恢复
+        assert_eq!(aria2.calls("aria2.removeDownloadResult").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn smart_resume_purges_stale_result_before_readd() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(MockTask::new("stale", "error").with_file("/downloads", "stale.bin"));
+        let store = TaskStore::new();
+        store.add_task(persisted_task("stale", TaskState::Error));
+
+        let new_gid = smart_resume_task(&store, &backends, "stale".to_string()).await.unwrap();
+
+        assert!(aria2.task("stale").is_none());
+        assert!(aria2.task(&new_gid).is_some());
+    }
+
+    #[tokio::test]
+    async fn smart_resume_keeps_task_when_readd_fails() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.fail_next("aria2.addUri", "disk full");
+        let store = TaskStore::new();
+        store.add_task(persisted_task("keep", TaskState::Error));
+
+        assert!(smart_resume_task(&store, &backends, "keep".to_string()).await.is_err());
+        assert!(store.get_task("keep").is_some());
+    }
+
+    #[tokio::test]
+    async fn smart_resume_unknown_task_is_not_found() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+
+        let err = smart_resume_task(&store, &backends, "nope".to_string()).await.unwrap_err();
+        assert!(matches!(err, AppError::TaskNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn resume_all_keeps_scheduled_tasks_paused() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(MockTask::new("p1", "paused"));
+        aria2.insert(MockTask::new("s1", "paused"));
+        let store = TaskStore::new();
+        store.add_task(persisted_task("p1", TaskState::Paused));
+        store.add_task(PersistedTask {
+            start_at: Some("2099-01-01T08:00:00+08:00".to_string()),
+            ..persisted_task("s1", TaskState::Paused)
+        });
+
+        resume_paused_tasks(&store, &backends).await.unwrap();
+
+        assert_eq!(aria2.task("p1").unwrap().status, "waiting");
+        assert_eq!(store.get_task("p1").unwrap().state, TaskState::Waiting);
+        assert_eq!(aria2.task("s1").unwrap().status, "paused");
+        assert_eq!(store.get_task("s1").unwrap().state, TaskState::Paused);
+        assert!(aria2.calls("aria2.unpauseAll").is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::core::completion;
+use crate::core::config::ConfigState;
+use crate::core::error::{AppError, AppResult};
+use crate::core::store::TaskStore;
+use crate::core::types::CompletionAction;
+use serde_json::json;
+
+use super::add::normalize_tags;
+
+/// 修改任务所属分类（None 或空字符串表示取消分类）。仅更新记录，不改变已开始任务的保存目录。
+#[tauri::command]
+pub async fn set_task_category(
+    state: tauri::State<'_, TaskStore>,
+    config_state: tauri::State<'_, ConfigState>,
+    gid: String,
+    category: Option<String>,
+) -> AppResult<()> {
+    let category = category
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+
+    if let Some(id) = category.as_deref() {
+        if config_state.snapshot().category(id).is_none() {
+            return Err(AppError::validation(format!("未知的分类: {}", id)));
+        }
+    }
+
+    if !state.update_task(&gid, |t| t.category = category.clone()) {
+        return Err(AppError::task_not_found(gid));
+    }
+
+    crate::app_info!(
+        "Core::TaskLabels",
+        "category_changed",
+        json!({ "gid": gid, "category": category })
+    );
+    Ok(())
+}
+
+/// 覆盖任务的标签列表
+#[tauri::command]
+pub async fn set_task_tags(
+    state: tauri::State<'_, TaskStore>,
+    gid: String,
+    tags: Vec<String>,
+) -> AppResult<()> {
+    let tags = normalize_tags(&tags);
+    if !state.update_task(&gid, |t| t.tags = tags.clone()) {
+        return Err(AppError::task_not_found(gid));
+    }
+
+    crate::app_info!(
+        "Core::TaskLabels",
+        "tags_changed",
+        json!({ "gid": gid, "tags": tags })
+    );
+    Ok(())
+}
+
+/// 设置任务完成后的动作（None 表示不执行任何动作）
+#[tauri::command]
+pub async fn set_task_completion_action(
+    state: tauri::State<'_, TaskStore>,
+    gid: String,
+    action: Option<CompletionAction>,
+) -> AppResult<()> {
+    let action = action.filter(|a| *a != CompletionAction::None);
+    if let Some(ref action) = action {
+        completion::validate_action(action)?;
+    }
+
+    if !state.update_task(&gid, |t| t.on_complete = action.clone()) {
+        return Err(AppError::task_not_found(gid));
+    }
+
+    crate::app_info!(
+        "Core::TaskLabels",
+        "completion_action_changed",
+        json!({ "gid": gid, "action": action })
+    );
+    Ok(())
+}
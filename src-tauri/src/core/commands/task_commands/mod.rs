@@ -1,9 +1,17 @@
 mod add;
 mod control;
+mod labels;
+mod options;
 mod query;
+mod queue;
 mod remove;
+mod verify;
 
 pub use add::*;
 pub use control::*;
+pub use labels::*;
+pub use options::*;
 pub use query::*;
+pub use queue::*;
 pub use remove::*;
+pub use verify::*;
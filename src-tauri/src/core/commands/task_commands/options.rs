@@ -0,0 +1,208 @@
+use crate::aria2::backend::BackendRegistry;
+use crate::aria2::options::{split_headers, Aria2Options, ByteSize};
+use crate::core::error::{AppError, AppResult};
+use crate::core::store::{PersistedTask, TaskStore};
+use crate::core::types::TaskOptionsPatch;
+use serde_json::json;
+
+/// 校验并规整选项修改，返回 (aria2 选项, 规整后的修改)
+fn normalize_patch(patch: TaskOptionsPatch) -> AppResult<(Aria2Options, TaskOptionsPatch)> {
+    let max_download_limit = match patch.max_download_limit.as_deref() {
+        Some(limit) => Some(ByteSize::parse_optional(limit)?.unwrap_or_else(ByteSize::unlimited)),
+        None => None,
+    };
+    let trim = |v: Option<String>| v.map(|s| s.trim().to_string());
+
+    let normalized = TaskOptionsPatch {
+        user_agent: trim(patch.user_agent),
+        referer: trim(patch.referer),
+        headers: patch.headers,
+        proxy: trim(patch.proxy),
+        max_download_limit: max_download_limit.as_ref().map(|l| l.to_string()),
+    };
+
+    let options = Aria2Options {
+        user_agent: normalized.user_agent.clone(),
+        referer: normalized.referer.clone(),
+        // 空请求头也需显式下发，否则 aria2 会保留原有的请求头
+        header: normalized.headers.as_deref().map(split_headers),
+        all_proxy: normalized.proxy.clone(),
+        max_download_limit,
+        ..Aria2Options::default()
+    };
+
+    Ok((options, normalized))
+}
+
+fn apply_patch(task: &mut PersistedTask, patch: &TaskOptionsPatch) {
+    if let Some(ua) = &patch.user_agent {
+        task.user_agent = ua.clone();
+    }
+    if let Some(referer) = &patch.referer {
+        task.referer = referer.clone();
+    }
+    if let Some(headers) = &patch.headers {
+        task.headers = split_headers(headers);
+    }
+    if let Some(proxy) = &patch.proxy {
+        task.proxy = proxy.clone();
+    }
+    if let Some(limit) = &patch.max_download_limit {
+        // "0" 即不限速，持久化为空以与新建任务保持一致
+        task.max_download_limit = if limit == "0" {
+            String::new()
+        } else {
+            limit.clone()
+        };
+    }
+}
+
+/// 修改任务选项：运行中的任务通过 aria2.changeOption 实时生效，
+/// 同时写回 PersistedTask，使智能恢复重新添加时沿用新设置。
+#[tauri::command]
+pub async fn change_task_options(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+    options: TaskOptionsPatch,
+) -> AppResult<()> {
+    let task = state
+        .get_task(&gid)
+        .ok_or_else(|| AppError::task_not_found(gid.clone()))?;
+
+    let (aria2_options, normalized) = normalize_patch(options)?;
+    let rpc_options = aria2_options.to_rpc_value();
+    let has_rpc_changes = rpc_options.as_object().is_some_and(|m| !m.is_empty());
+
+    if has_rpc_changes && task.state.is_active() {
+        if let Err(error) = backends
+            .get(&task.backend_id)?
+            .change_option(gid.clone(), rpc_options)
+            .await
+        {
+            // aria2 中已不存在的任务只持久化，待恢复时生效
+            if !error.is_aria2_not_found() {
+                return Err(error);
+            }
+            crate::app_info!(
+                "Core::TaskOptions",
+                "change_option_task_gone_persist_only",
+                json!({ "gid": gid })
+            );
+        }
+    }
+
+    state.update_task(&gid, |t| apply_patch(t, &normalized));
+
+    crate::app_info!("Core::TaskOptions", "task_options_changed", json!({ "gid": gid }));
+    Ok(())
+}
+
+/// 读取 aria2 中任务当前生效的选项
+#[tauri::command]
+pub async fn get_task_options(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<serde_json::Map<String, serde_json::Value>> {
+    let task = state
+        .get_task(&gid)
+        .ok_or_else(|| AppError::task_not_found(gid.clone()))?;
+    backends.get(&task.backend_id)?.get_option(gid).await
+}
+
+/// 将文件序号列表渲染为 aria2 的 select-file 格式（连续序号合并为区间，如 `1-3,5`）
+fn format_select_file(indices: &[u32]) -> String {
+    let mut sorted = indices.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut parts = Vec::new();
+    let mut iter = sorted.into_iter().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap_or(end);
+        }
+        parts.push(if start == end {
+            start.to_string()
+        } else {
+            format!("{}-{}", start, end)
+        });
+    }
+    parts.join(",")
+}
+
+/// 修改多文件任务中需要下载的文件（序号从 1 开始，与 aria2 一致）
+#[tauri::command]
+pub async fn set_task_selected_files(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+    indices: Vec<u32>,
+) -> AppResult<()> {
+    let task = state
+        .get_task(&gid)
+        .ok_or_else(|| AppError::task_not_found(gid.clone()))?;
+
+    if indices.is_empty() || indices.contains(&0) {
+        return Err(AppError::validation("至少需要选择一个有效的文件"));
+    }
+
+    let select_file = format_select_file(&indices);
+
+    if task.state.is_active() {
+        let options = Aria2Options {
+            select_file: Some(select_file.clone()),
+            ..Aria2Options::default()
+        };
+        if let Err(error) = backends
+            .get(&task.backend_id)?
+            .change_option(gid.clone(), options.to_rpc_value())
+            .await
+        {
+            if !error.is_aria2_not_found() {
+                return Err(error);
+            }
+            crate::app_info!(
+                "Core::TaskOptions",
+                "select_file_task_gone_persist_only",
+                json!({ "gid": gid })
+            );
+        }
+    }
+
+    state.update_task(&gid, |t| t.select_file = Some(select_file.clone()));
+
+    crate::app_info!(
+        "Core::TaskOptions",
+        "selected_files_changed",
+        json!({ "gid": gid, "select_file": select_file })
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleared_headers_are_sent_explicitly() {
+        let (options, _) = normalize_patch(TaskOptionsPatch {
+            headers: Some(String::new()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(options.to_rpc_value(), json!({ "header": "" }));
+
+        let (options, _) = normalize_patch(TaskOptionsPatch {
+            headers: Some("A: 1; B: 2".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(options.to_rpc_value(), json!({ "header": ["A: 1", "B: 2"] }));
+
+        let (options, _) = normalize_patch(TaskOptionsPatch::default()).unwrap();
+        assert_eq!(options.to_rpc_value(), json!({}));
+    }
+}
@@ -1,12 +1,58 @@
-use crate::core::error::AppResult;
+use crate::aria2::backend::BackendRegistry;
+use crate::core::config::ConfigState;
+use crate::core::error::{AppError, AppResult};
+use crate::core::peers::{flatten_servers, PeerInfo, ServerInfo};
 use crate::core::store::TaskStore;
 use crate::core::sync::FrontendTask;
-use tauri::AppHandle;
 
 #[tauri::command]
 pub async fn get_tasks(
     state: tauri::State<'_, TaskStore>,
-    app_handle: AppHandle,
+    backends: tauri::State<'_, BackendRegistry>,
+    config_state: tauri::State<'_, ConfigState>,
 ) -> AppResult<Vec<FrontendTask>> {
-    crate::core::sync::sync_tasks(&state, &app_handle).await
+    crate::core::sync::sync_tasks(&state, &backends, &config_state.snapshot()).await
+}
+
+/// 查看 BT 任务当前连接的对等节点
+#[tauri::command]
+pub async fn get_task_peers(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<Vec<PeerInfo>> {
+    let backend_id = state
+        .get_task(&gid)
+        .map(|t| t.backend_id)
+        .ok_or_else(|| AppError::task_not_found(gid.clone()))?;
+    let backend = backends.get(&backend_id)?;
+
+    let peers = backend.get_peers(gid.clone()).await?;
+    // 分片总数仅用于进度换算，读取失败时退回按 bitfield 长度估算
+    let num_pieces = backend
+        .tell_status_fields(gid, vec!["numPieces"])
+        .await
+        .ok()
+        .and_then(|m| m.get("numPieces")?.as_str()?.parse::<u64>().ok());
+
+    Ok(peers
+        .iter()
+        .map(|peer| PeerInfo::from_aria2(peer, num_pieces))
+        .collect())
+}
+
+/// 查看 HTTP/FTP 任务当前使用的服务器连接
+#[tauri::command]
+pub async fn get_task_servers(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<Vec<ServerInfo>> {
+    let backend_id = state
+        .get_task(&gid)
+        .map(|t| t.backend_id)
+        .ok_or_else(|| AppError::task_not_found(gid.clone()))?;
+
+    let servers = backends.get(&backend_id)?.get_servers(gid).await?;
+    Ok(flatten_servers(&servers))
 }
@@ -0,0 +1,207 @@
+use crate::aria2::backend::BackendRegistry;
+use crate::core::error::{AppError, AppResult};
+use crate::core::store::TaskStore;
+use serde_json::json;
+
+/// 队列移动方式，对应 aria2.changePosition 的 (pos, how) 参数
+#[derive(Debug, Clone, Copy)]
+enum QueueMove {
+    Top,
+    Bottom,
+    Up,
+    Down,
+}
+
+impl QueueMove {
+    fn as_params(&self) -> (i64, &'static str) {
+        match self {
+            Self::Top => (0, "POS_SET"),
+            Self::Bottom => (0, "POS_END"),
+            Self::Up => (-1, "POS_CUR"),
+            Self::Down => (1, "POS_CUR"),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+}
+
+/// 在 aria2 等待队列中移动任务，并立即写回新位置（下一轮同步会校正其余任务）
+async fn move_task(
+    state: &TaskStore,
+    registry: &BackendRegistry,
+    gid: String,
+    movement: QueueMove,
+) -> AppResult<u32> {
+    let task = state
+        .get_task(&gid)
+        .ok_or_else(|| AppError::task_not_found(gid.clone()))?;
+
+    if task.queue_position.is_none() {
+        return Err(AppError::validation(
+            "仅等待中或已暂停的任务可以调整队列顺序",
+        ));
+    }
+
+    let (pos, how) = movement.as_params();
+    let new_position = registry
+        .get(&task.backend_id)?
+        .change_position(gid.clone(), pos, how)
+        .await?
+        .max(0) as u32;
+
+    state.update_task(&gid, |t| t.queue_position = Some(new_position));
+
+    crate::app_info!(
+        "Core::TaskQueue",
+        "task_moved",
+        json!({ "gid": gid, "move": movement.as_str(), "position": new_position })
+    );
+    Ok(new_position)
+}
+
+#[tauri::command]
+pub async fn move_task_to_top(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<u32> {
+    move_task(&state, &backends, gid, QueueMove::Top).await
+}
+
+#[tauri::command]
+pub async fn move_task_to_bottom(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<u32> {
+    move_task(&state, &backends, gid, QueueMove::Bottom).await
+}
+
+#[tauri::command]
+pub async fn move_task_up(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<u32> {
+    move_task(&state, &backends, gid, QueueMove::Up).await
+}
+
+#[tauri::command]
+pub async fn move_task_down(
+    state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gid: String,
+) -> AppResult<u32> {
+    move_task(&state, &backends, gid, QueueMove::Down).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::{persisted_task, MockAria2, MockTask};
+    use crate::core::types::TaskState;
+
+    /// 建立等待队列 a、b、c、d，并同步写入持久化的队列位置
+    fn queued(aria2: &MockAria2, store: &TaskStore) {
+        for (position, gid) in ["a", "b", "c", "d"].iter().enumerate() {
+            aria2.insert(MockTask::new(gid, "waiting"));
+            let mut task = persisted_task(gid, TaskState::Waiting);
+            task.queue_position = Some(position as u32);
+            store.add_task(task);
+        }
+    }
+
+    fn waiting_order(aria2: &MockAria2) -> Vec<String> {
+        aria2.tasks().into_iter().map(|t| t.gid).collect()
+    }
+
+    #[tokio::test]
+    async fn moves_send_matching_position_params() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        queued(&aria2, &store);
+
+        let cases = [
+            (
+                QueueMove::Top,
+                "c",
+                json!(["c", 0, "POS_SET"]),
+                0,
+                ["c", "a", "b", "d"],
+            ),
+            (
+                QueueMove::Bottom,
+                "c",
+                json!(["c", 0, "POS_END"]),
+                3,
+                ["a", "b", "d", "c"],
+            ),
+            (
+                QueueMove::Up,
+                "d",
+                json!(["d", -1, "POS_CUR"]),
+                1,
+                ["a", "d", "b", "c"],
+            ),
+            (
+                QueueMove::Down,
+                "a",
+                json!(["a", 1, "POS_CUR"]),
+                1,
+                ["d", "a", "b", "c"],
+            ),
+        ];
+        for (movement, gid, params, position, order) in cases {
+            let moved = move_task(&store, &backends, gid.to_string(), movement)
+                .await
+                .unwrap();
+            assert_eq!(moved, position, "{}", movement.as_str());
+            assert_eq!(json!(aria2.calls("aria2.changePosition").last()), params);
+            assert_eq!(store.get_task(gid).unwrap().queue_position, Some(position));
+            assert_eq!(waiting_order(&aria2), order);
+        }
+    }
+
+    #[tokio::test]
+    async fn moving_past_the_ends_keeps_position_in_range() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        queued(&aria2, &store);
+
+        let moved = move_task(&store, &backends, "a".to_string(), QueueMove::Up)
+            .await
+            .unwrap();
+        assert_eq!(moved, 0);
+        let moved = move_task(&store, &backends, "d".to_string(), QueueMove::Down)
+            .await
+            .unwrap();
+        assert_eq!(moved, 3);
+        assert_eq!(waiting_order(&aria2), ["a", "b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn tasks_outside_the_queue_are_rejected() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        aria2.insert(MockTask::new("active", "active"));
+        store.add_task(persisted_task("active", TaskState::Active));
+
+        let result = move_task(&store, &backends, "active".to_string(), QueueMove::Top).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        let result = move_task(&store, &backends, "missing".to_string(), QueueMove::Top).await;
+        assert!(matches!(result, Err(AppError::TaskNotFound(_))));
+
+        assert!(aria2.calls("aria2.changePosition").is_empty());
+    }
+}
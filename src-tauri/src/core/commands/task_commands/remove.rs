@@ -1,4 +1,5 @@
-use crate::aria2::client as aria2_client;
+use crate::aria2::backend::BackendRegistry;
+use crate::core::config::ConfigState;
 use crate::core::error::{AppError, AppResult};
 use crate::core::store::TaskStore;
 use crate::utils;
@@ -74,6 +75,16 @@ fn delete_task_files(save_path: &str, filename: &str) -> AppResult<()> {
 pub async fn remove_tasks(
     _app: AppHandle,
     state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
+    gids: Vec<String>,
+    delete_file: bool,
+) -> AppResult<()> {
+    remove_tasks_inner(&state, &backends, gids, delete_file).await
+}
+
+async fn remove_tasks_inner(
+    state: &TaskStore,
+    registry: &BackendRegistry,
     gids: Vec<String>,
     delete_file: bool,
 ) -> AppResult<()> {
@@ -87,14 +98,22 @@ pub async fn remove_tasks(
 
     let active_futures: Vec<_> = active_gids
         .iter()
-        .map(|gid| aria2_client::remove(gid.clone()))
+        .map(|gid| {
+            let backend = registry.get(&state.backend_of(gid));
+            let gid = gid.clone();
+            async move { backend?.remove(gid).await }
+        })
         .collect();
     let active_results = join_all(active_futures).await;
     log_batch_errors("remove_active_failed", &active_gids, active_results.clone());
 
     let purge_futures: Vec<_> = gids
         .iter()
-        .map(|gid| aria2_client::purge(gid.clone()))
+        .map(|gid| {
+            let backend = registry.get(&state.backend_of(gid));
+            let gid = gid.clone();
+            async move { backend?.purge(gid).await }
+        })
         .collect();
     let purge_results = join_all(purge_futures).await;
     log_batch_errors("purge_failed", &gids, purge_results.clone());
@@ -143,16 +162,30 @@ pub async fn remove_tasks(
 pub async fn remove_task_record(
     _app: AppHandle,
     state: tauri::State<'_, TaskStore>,
+    backends: tauri::State<'_, BackendRegistry>,
     gid: String,
     delete_file: bool,
 ) -> AppResult<()> {
-    remove_task_inner(&state, gid, delete_file).await
+    remove_task_inner(&state, &backends, gid, delete_file).await
 }
 
 #[tauri::command]
-pub async fn show_task_in_folder(state: tauri::State<'_, TaskStore>, gid: String) -> AppResult<()> {
+pub async fn show_task_in_folder(
+    state: tauri::State<'_, TaskStore>,
+    config_state: tauri::State<'_, ConfigState>,
+    gid: String,
+) -> AppResult<()> {
     if let Some(task) = state.get_task(&gid) {
-        let full_path = utils::get_full_path(&task.save_path, &task.filename);
+        let save_path = {
+            let config = config_state
+                .config
+                .lock()
+                .map_err(|_| AppError::config("配置状态不可用"))?;
+            config.resolve_local_path(&task.backend_id, &task.save_path).ok_or_else(|| {
+                AppError::validation(format!("远程目录未配置本地映射: {}", task.save_path))
+            })?
+        };
+        let full_path = utils::get_full_path(&save_path, &task.filename);
         utils::show_in_file_manager(&full_path);
         Ok(())
     } else {
@@ -160,24 +193,30 @@ pub async fn show_task_in_folder(state: tauri::State<'_, TaskStore>, gid: String
     }
 }
 
-async fn remove_task_inner(state: &TaskStore, gid: String, delete_file: bool) -> AppResult<()> {
+async fn remove_task_inner(
+    state: &TaskStore,
+    registry: &BackendRegistry,
+    gid: String,
+    delete_file: bool,
+) -> AppResult<()> {
     let task_opt = state.get_task(&gid);
+    let backend = registry.get(&state.backend_of(&gid))?;
 
     let is_active = task_opt.as_ref().is_some_and(|t| t.state.is_active());
 
     if is_active {
-        if let Err(error) = aria2_client::remove(gid.clone()).await {
+        if let Err(error) = backend.remove(gid.clone()).await {
             if !is_not_found(&error) {
                 return Err(error);
             }
         }
-        if let Err(error) = aria2_client::purge(gid.clone()).await {
+        if let Err(error) = backend.purge(gid.clone()).await {
             if !is_not_found(&error) {
                 return Err(error);
             }
         }
     } else {
-        if let Err(error) = aria2_client::purge(gid.clone()).await {
+        if let Err(error) = backend.purge(gid.clone()).await {
             if !is_not_found(&error) {
                 return Err(error);
             }
@@ -194,3 +233,64 @@ async fn remove_task_inner(state: &TaskStore, gid: String, delete_file: bool) ->
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::{persisted_task, MockAria2, MockTask};
+    use crate::core::types::TaskState;
+
+    #[tokio::test]
+    async fn batch_remove_stops_active_and_purges_results() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(MockTask::new("run", "active"));
+        aria2.insert(MockTask::new("done", "complete"));
+        let store = TaskStore::new();
+        store.add_task(persisted_task("run", TaskState::Active));
+        store.add_task(persisted_task("done", TaskState::Complete));
+
+        remove_tasks_inner(&store, &backends, vec!["run".to_string(), "done".to_string()], false)
+            .await
+            .unwrap();
+
+        assert!(store.get_all().is_empty());
+        assert!(aria2.tasks().is_empty());
+        // 仅活跃任务需要 remove，所有任务都需要 purge
+        assert_eq!(aria2.calls("aria2.remove").len(), 1);
+        assert_eq!(aria2.calls("aria2.removeDownloadResult").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn batch_remove_tolerates_tasks_unknown_to_aria2() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        store.add_task(persisted_task("lost", TaskState::Active));
+        store.add_task(persisted_task("old", TaskState::Error));
+
+        remove_tasks_inner(&store, &backends, vec!["lost".to_string(), "old".to_string()], false)
+            .await
+            .unwrap();
+
+        assert!(store.get_all().is_empty());
+    }
+
+    #[tokio::test]
+    async fn batch_remove_keeps_records_on_partial_failure() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(MockTask::new("a", "active"));
+        aria2.insert(MockTask::new("b", "paused"));
+        aria2.fail_next("aria2.remove", "internal error");
+        let store = TaskStore::new();
+        store.add_task(persisted_task("a", TaskState::Active));
+        store.add_task(persisted_task("b", TaskState::Paused));
+
+        let result =
+            remove_tasks_inner(&store, &backends, vec!["a".to_string(), "b".to_string()], false).await;
+
+        assert!(result.is_err());
+        assert_eq!(store.get_all().len(), 2);
+    }
+}
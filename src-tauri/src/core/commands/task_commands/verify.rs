@@ -0,0 +1,77 @@
+use crate::core::checksum::Checksum;
+use crate::core::config::ConfigState;
+use crate::core::error::{AppError, AppResult};
+use crate::core::store::TaskStore;
+use crate::core::types::{ChecksumStatus, TaskState};
+use crate::utils;
+use serde::Serialize;
+use serde_json::json;
+
+/// 文件校验结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumResult {
+    pub status: ChecksumStatus,
+    /// 期望值（`算法=十六进制摘要`）
+    pub expected: String,
+    /// 实际计算出的十六进制摘要
+    pub actual: String,
+}
+
+/// 计算已完成任务文件的摘要并与期望值比对，结果记录到任务上。
+/// 传入 `checksum` 时以其为期望值并保存到任务，否则使用添加任务时提供的校验值。
+#[tauri::command]
+pub async fn verify_task_file(
+    state: tauri::State<'_, TaskStore>,
+    config_state: tauri::State<'_, ConfigState>,
+    gid: String,
+    checksum: Option<String>,
+) -> AppResult<ChecksumResult> {
+    let task = state
+        .get_task(&gid)
+        .ok_or_else(|| AppError::task_not_found(gid.clone()))?;
+    if task.state != TaskState::Complete {
+        return Err(AppError::validation("只能校验已完成的任务"));
+    }
+
+    let expected = match Checksum::parse_optional(checksum.as_deref())? {
+        Some(checksum) => checksum,
+        None => Checksum::parse_optional(task.checksum.as_deref())?
+            .ok_or_else(|| AppError::validation("任务未设置校验值"))?,
+    };
+
+    let save_path = config_state
+        .snapshot()
+        .resolve_local_path(&task.backend_id, &task.save_path)
+        .ok_or_else(|| {
+            AppError::validation(format!("远程目录未配置本地映射: {}", task.save_path))
+        })?;
+    let full_path = utils::get_full_path(&save_path, &task.filename);
+
+    // 大文件哈希耗时较长，放到阻塞线程池执行
+    let checksum = expected.clone();
+    let (status, actual) = tauri::async_runtime::spawn_blocking(move || {
+        checksum.verify_file(std::path::Path::new(&full_path))
+    })
+    .await
+    .map_err(|e| crate::app_err!(e))?
+    .map_err(|e| AppError::io(format!("读取文件失败: {}", e)))?;
+
+    let expected = expected.to_string();
+    state.update_task(&gid, |t| {
+        t.checksum = Some(expected.clone());
+        t.checksum_status = Some(status);
+    });
+
+    crate::app_info!(
+        "Core::TaskVerify",
+        "task_file_verified",
+        json!({ "gid": gid, "expected": expected, "status": status })
+    );
+
+    Ok(ChecksumResult {
+        status,
+        expected,
+        actual,
+    })
+}
@@ -0,0 +1,421 @@
+//! 下载完成后的动作
+//! 同步循环检测到任务完成时调用 `handle_completions`：
+//! - 任务自身设置的运行命令 / 打开文件 / 显示文件立即执行；
+//! - 最后一个进行中的任务完成时，再执行全局设置的动作；
+//! - 睡眠与关机（无论来自任务还是全局设置）都等到没有进行中的任务后，经过倒计时再执行，
+//!   倒计时期间可通过命令取消，出现新的进行中任务时自动放弃。
+//!
+//! 命令不经过 shell 执行：先按空白（支持引号）拆分参数再替换占位符，
+//! 文件名中的特殊字符不会被解释，避免远程文件名注入命令。
+
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::core::config::AppConfig;
+use crate::core::error::{AppError, AppResult};
+use crate::core::events::{EVENT_POWER_ACTION_CANCELLED, EVENT_POWER_ACTION_PENDING};
+use crate::core::store::{PersistedTask, TaskStore};
+use crate::core::types::{CompletionAction, TaskState};
+use crate::utils;
+
+/// 等待执行的电源动作与倒计时
+#[derive(Default)]
+pub struct CompletionState {
+    /// 任务设置的电源动作，等待最后一个任务完成
+    pending_power: Mutex<Option<CompletionAction>>,
+    /// 正在倒计时的电源动作编号，取消后清空
+    countdown: Mutex<Option<u64>>,
+    next_countdown: AtomicU64,
+}
+
+/// 本轮完成的任务应执行的动作
+#[derive(Debug, PartialEq)]
+struct CompletionPlan<'a> {
+    file_actions: Vec<(CompletionAction, &'a PersistedTask)>,
+    power: Option<CompletionAction>,
+}
+
+fn plan<'a>(
+    completed: &'a [PersistedTask],
+    queue_drained: bool,
+    global: &CompletionAction,
+    pending_power: &mut Option<CompletionAction>,
+) -> CompletionPlan<'a> {
+    let mut file_actions = Vec::new();
+    for task in completed {
+        match task.on_complete {
+            Some(ref action) if action.is_power() => *pending_power = Some(action.clone()),
+            Some(CompletionAction::None) | None => {}
+            Some(ref action) => file_actions.push((action.clone(), task)),
+        }
+    }
+
+    let mut power = None;
+    if queue_drained {
+        if let Some(last) = completed.last() {
+            match global {
+                CompletionAction::None => {}
+                action if action.is_power() => power = Some(action.clone()),
+                action => file_actions.push((action.clone(), last)),
+            }
+        }
+        // 任务自身的设置优先于全局设置
+        if let Some(pending) = pending_power.take() {
+            power = Some(pending);
+        }
+    }
+
+    CompletionPlan {
+        file_actions,
+        power,
+    }
+}
+
+/// 睡眠、关机前倒计时的下限，保证用户有时间取消
+pub const MIN_POWER_COUNTDOWN_SECS: u32 = 10;
+
+/// 校验单个完成后动作：运行命令时命令拆分后不能为空
+pub fn validate_action(action: &CompletionAction) -> AppResult<()> {
+    if let CompletionAction::RunCommand { command } = action {
+        if split_command(command).is_empty() {
+            return Err(AppError::validation("完成后运行的命令不能为空"));
+        }
+    }
+    Ok(())
+}
+
+/// 校验全局完成后动作与电源动作倒计时
+pub fn validate(config: &AppConfig) -> AppResult<()> {
+    validate_action(&config.post_completion.action)?;
+    if config.post_completion.countdown_secs < MIN_POWER_COUNTDOWN_SECS {
+        return Err(AppError::validation(format!(
+            "睡眠/关机前的倒计时不能少于 {} 秒",
+            MIN_POWER_COUNTDOWN_SECS
+        )));
+    }
+    Ok(())
+}
+
+/// 处理本轮同步中新完成的任务；`queue_drained` 表示已没有进行中的任务
+pub fn handle_completions(
+    app: &AppHandle,
+    completed: &[PersistedTask],
+    queue_drained: bool,
+    config: &AppConfig,
+) {
+    if completed.is_empty() {
+        return;
+    }
+    let Some(state) = app.try_state::<CompletionState>() else {
+        return;
+    };
+
+    let plan = {
+        let Ok(mut pending) = state.pending_power.lock() else {
+            return;
+        };
+        plan(
+            completed,
+            queue_drained,
+            &config.post_completion.action,
+            &mut pending,
+        )
+    };
+
+    for (action, task) in plan.file_actions {
+        run_file_action(&action, task, config);
+    }
+    if let Some(power) = plan.power {
+        // 旧配置可能保存了过短的倒计时
+        let countdown = config.post_completion.countdown_secs.max(MIN_POWER_COUNTDOWN_SECS);
+        start_countdown(app, power, countdown);
+    }
+}
+
+/// 取消正在倒计时的电源动作以及等待中的任务电源动作，返回是否有动作被取消
+pub fn cancel_power_action(app: &AppHandle) -> bool {
+    let state = app.state::<CompletionState>();
+    let counting = state.countdown.lock().ok().and_then(|mut c| c.take()).is_some();
+    let pending = state.pending_power.lock().ok().and_then(|mut p| p.take()).is_some();
+
+    if counting || pending {
+        crate::app_info!("Core::Completion", "power_action_cancelled");
+        let _ = app.emit(EVENT_POWER_ACTION_CANCELLED, ());
+    }
+    counting || pending
+}
+
+fn start_countdown(app: &AppHandle, action: CompletionAction, seconds: u32) {
+    let state = app.state::<CompletionState>();
+    let id = state.next_countdown.fetch_add(1, Ordering::Relaxed) + 1;
+    if let Ok(mut countdown) = state.countdown.lock() {
+        *countdown = Some(id);
+    }
+
+    crate::app_info!(
+        "Core::Completion",
+        "power_action_scheduled",
+        json!({ "action": action, "seconds": seconds })
+    );
+    let _ = app.emit(
+        EVENT_POWER_ACTION_PENDING,
+        json!({ "action": action, "seconds": seconds }),
+    );
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(u64::from(seconds))).await;
+
+        let state = app.state::<CompletionState>();
+        let still_pending = state
+            .countdown
+            .lock()
+            .map(|mut countdown| {
+                let current = *countdown == Some(id);
+                if current {
+                    *countdown = None;
+                }
+                current
+            })
+            .unwrap_or(false);
+        if !still_pending {
+            return;
+        }
+
+        // 倒计时期间又开始了新的下载，放弃执行
+        let busy = app
+            .state::<TaskStore>()
+            .get_all()
+            .iter()
+            .any(|t| matches!(t.state, TaskState::Active | TaskState::Waiting));
+        if busy {
+            crate::app_info!("Core::Completion", "power_action_aborted_tasks_running");
+            let _ = app.emit(EVENT_POWER_ACTION_CANCELLED, ());
+            return;
+        }
+
+        run_power_action(&action);
+    });
+}
+
+/// 任务文件的本地路径；远程后端未配置路径映射时退回 aria2 上的路径
+fn task_path(task: &PersistedTask, config: &AppConfig) -> (String, bool) {
+    match config.resolve_local_path(&task.backend_id, &task.save_path) {
+        Some(dir) => (utils::get_full_path(&dir, &task.filename), true),
+        None => (utils::get_full_path(&task.save_path, &task.filename), false),
+    }
+}
+
+fn run_file_action(action: &CompletionAction, task: &PersistedTask, config: &AppConfig) {
+    let (path, is_local) = task_path(task, config);
+    crate::app_info!(
+        "Core::Completion",
+        "completion_action_triggered",
+        json!({ "gid": task.gid, "action": action })
+    );
+
+    match action {
+        CompletionAction::RunCommand { command } => {
+            let args = expand_command(command, &path, &task.filename, &task.gid);
+            let Some((program, rest)) = args.split_first() else {
+                return;
+            };
+            if let Err(e) = std::process::Command::new(program).args(rest).spawn() {
+                crate::app_warn!(
+                    "Core::Completion",
+                    "completion_command_failed",
+                    json!({ "gid": task.gid, "program": program, "error": e.to_string() })
+                );
+            }
+        }
+        CompletionAction::OpenFile | CompletionAction::RevealFile if !is_local => {
+            crate::app_warn!(
+                "Core::Completion",
+                "completion_path_not_local",
+                json!({ "gid": task.gid, "path": path })
+            );
+        }
+        CompletionAction::OpenFile => utils::open_with_default_app(&path),
+        CompletionAction::RevealFile => utils::show_in_file_manager(&path),
+        CompletionAction::None | CompletionAction::Sleep | CompletionAction::Shutdown => {}
+    }
+}
+
+fn run_power_action(action: &CompletionAction) {
+    let Some((program, args)) = power_command(action) else {
+        return;
+    };
+    crate::app_info!(
+        "Core::Completion",
+        "power_action_executing",
+        json!({ "action": action, "program": program })
+    );
+    if let Err(e) = std::process::Command::new(program).args(args).spawn() {
+        crate::app_error!(
+            "Core::Completion",
+            "power_action_failed",
+            json!({ "action": action, "error": e.to_string() })
+        );
+    }
+}
+
+fn power_command(action: &CompletionAction) -> Option<(&'static str, &'static [&'static str])> {
+    #[cfg(target_os = "macos")]
+    let command: (&'static str, &'static [&'static str]) = match action {
+        CompletionAction::Sleep => ("pmset", &["sleepnow"]),
+        CompletionAction::Shutdown => (
+            "osascript",
+            &["-e", "tell application \"System Events\" to shut down"],
+        ),
+        _ => return None,
+    };
+
+    #[cfg(target_os = "windows")]
+    let command: (&'static str, &'static [&'static str]) = match action {
+        CompletionAction::Sleep => ("rundll32.exe", &["powrprof.dll,SetSuspendState", "0,1,0"]),
+        CompletionAction::Shutdown => ("shutdown", &["/s", "/t", "0"]),
+        _ => return None,
+    };
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let command: (&'static str, &'static [&'static str]) = match action {
+        CompletionAction::Sleep => ("systemctl", &["suspend"]),
+        CompletionAction::Shutdown => ("systemctl", &["poweroff"]),
+        _ => return None,
+    };
+
+    Some(command)
+}
+
+/// 按空白拆分命令（单双引号内的空白保留），再替换各参数中的占位符
+fn expand_command(command: &str, path: &str, filename: &str, gid: &str) -> Vec<String> {
+    split_command(command)
+        .into_iter()
+        .map(|arg| {
+            arg.replace("{path}", path)
+                .replace("{filename}", filename)
+                .replace("{gid}", gid)
+        })
+        .collect()
+}
+
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut has_token = false;
+
+    for ch in command.chars() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(ch);
+                has_token = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::persisted_task;
+
+    fn completed(gid: &str, action: Option<CompletionAction>) -> PersistedTask {
+        let mut task = persisted_task(gid, TaskState::Complete);
+        task.on_complete = action;
+        task
+    }
+
+    #[test]
+    fn validation_rejects_empty_commands_and_short_countdown() {
+        let blank = CompletionAction::RunCommand {
+            command: " \t ".to_string(),
+        };
+        assert!(validate_action(&blank).is_err());
+        assert!(validate_action(&CompletionAction::Shutdown).is_ok());
+
+        let mut config = AppConfig::default();
+        assert!(validate(&config).is_ok());
+        config.post_completion.countdown_secs = 3;
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn placeholders_are_substituted_per_argument() {
+        let args = expand_command(
+            r#"/usr/bin/unzip -o "{path}" -d '/tmp/out dir' --tag={gid}"#,
+            "/downloads/a b; rm -rf ~.zip",
+            "a b; rm -rf ~.zip",
+            "abc",
+        );
+        assert_eq!(
+            args,
+            vec![
+                "/usr/bin/unzip",
+                "-o",
+                "/downloads/a b; rm -rf ~.zip",
+                "-d",
+                "/tmp/out dir",
+                "--tag=abc",
+            ]
+        );
+        assert_eq!(split_command(r#"echo "" x"#), vec!["echo", "", "x"]);
+    }
+
+    #[test]
+    fn task_actions_run_immediately_and_power_waits_for_drain() {
+        let tasks = vec![
+            completed("a", Some(CompletionAction::OpenFile)),
+            completed("b", Some(CompletionAction::Shutdown)),
+        ];
+        let mut pending = None;
+
+        let result = plan(&tasks, false, &CompletionAction::Sleep, &mut pending);
+        assert_eq!(result.file_actions.len(), 1);
+        assert_eq!(result.file_actions[0].1.gid, "a");
+        assert_eq!(result.power, None);
+        assert_eq!(pending, Some(CompletionAction::Shutdown));
+
+        // 最后一个任务完成时执行任务设置的关机，而不是全局的睡眠
+        let last = vec![completed("c", None)];
+        let result = plan(&last, true, &CompletionAction::Sleep, &mut pending);
+        assert_eq!(result.power, Some(CompletionAction::Shutdown));
+        assert_eq!(pending, None);
+    }
+
+    #[test]
+    fn global_action_targets_last_completed_task() {
+        let tasks = vec![completed("a", None), completed("b", None)];
+        let mut pending = None;
+
+        let global = CompletionAction::RunCommand {
+            command: "notify {filename}".to_string(),
+        };
+        let result = plan(&tasks, true, &global, &mut pending);
+        assert_eq!(result.file_actions.len(), 1);
+        assert_eq!(result.file_actions[0].1.gid, "b");
+        assert_eq!(result.power, None);
+
+        let result = plan(&tasks, false, &global, &mut pending);
+        assert!(result.file_actions.is_empty());
+    }
+}
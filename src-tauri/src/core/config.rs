@@ -1,7 +1,11 @@
+use crate::core::types::{CompletionAction, TaskErrorKind};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use crate::core::error::AppResult;
+use crate::core::migrations;
+use crate::core::recovery::{self, RecoveryLog, RecoveryReport};
+use serde_json::{json, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tauri::{AppHandle, Manager};
 
@@ -63,15 +67,368 @@ pub struct AppConfig {
     pub dht_listen_port: String,
     #[serde(rename = "listenPort", default = "default_bt_port")]
     pub listen_port: String,
+    /// 远程 aria2 后端；启用时不再启动内置 Sidecar
+    #[serde(rename = "remoteBackend", default)]
+    pub remote_backend: RemoteBackendConfig,
+    /// 与默认后端并行管理的其他远程 aria2 实例
+    #[serde(rename = "remoteBackends", default)]
+    pub remote_backends: Vec<RemoteBackendConfig>,
+    /// 失败任务的自动重试策略
+    #[serde(rename = "retryPolicy", default)]
+    pub retry_policy: RetryPolicy,
+    /// 历史任务保留策略
+    #[serde(rename = "historyRetention", default)]
+    pub history_retention: HistoryRetention,
+    /// 用户自定义的任务分类
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    /// 新建任务的自动分类规则，按顺序匹配，第一条命中的规则生效
+    #[serde(default)]
+    pub rules: Vec<DownloadRule>,
+    /// 按时段切换的全局限速计划
+    #[serde(rename = "bandwidthSchedule", default)]
+    pub bandwidth_schedule: BandwidthSchedule,
+    /// 备用限速（乌龟模式）
+    #[serde(rename = "altSpeed", default)]
+    pub alt_speed: AltSpeedLimits,
+    /// 全部任务完成后执行的动作
+    #[serde(rename = "postCompletion", default)]
+    pub post_completion: PostCompletion,
+}
+
+/// 全局完成动作：最后一个进行中的任务完成时执行（针对该任务）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PostCompletion {
+    #[serde(default)]
+    pub action: CompletionAction,
+    /// 睡眠、关机前的倒计时（秒），期间可取消
+    #[serde(rename = "countdownSecs", default = "default_power_countdown")]
+    pub countdown_secs: u32,
+}
+
+impl Default for PostCompletion {
+    fn default() -> Self {
+        Self {
+            action: CompletionAction::None,
+            countdown_secs: default_power_countdown(),
+        }
+    }
+}
+
+/// 备用限速（乌龟模式）：开启时优先于限速计划与手动指定的方案。
+/// 开关本身是运行时状态（见 `bandwidth::BandwidthState`），不写入配置
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AltSpeedLimits {
+    #[serde(rename = "maxDownloadLimit", default = "default_alt_download_limit")]
+    pub max_download_limit: String,
+    #[serde(rename = "maxUploadLimit", default = "default_alt_upload_limit")]
+    pub max_upload_limit: String,
+}
+
+impl Default for AltSpeedLimits {
+    fn default() -> Self {
+        Self {
+            max_download_limit: default_alt_download_limit(),
+            max_upload_limit: default_alt_upload_limit(),
+        }
+    }
+}
+
+/// 每周限速计划：按顺序取第一个覆盖当前时间的时段，均未覆盖时使用全局限速设置
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BandwidthSchedule {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub profiles: Vec<SpeedProfile>,
+    #[serde(default)]
+    pub entries: Vec<ScheduleEntry>,
+}
+
+/// 限速方案
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpeedProfile {
+    pub id: String,
+    pub name: String,
+    /// 下载限速，格式同 aria2，空或 `0` 表示不限速
+    #[serde(rename = "maxDownloadLimit", default = "default_string_empty")]
+    pub max_download_limit: String,
+    #[serde(rename = "maxUploadLimit", default = "default_string_empty")]
+    pub max_upload_limit: String,
+    /// 为 true 时进入该方案会暂停默认后端上的全部任务，离开时恢复
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// 计划中的一个时段，`end` 早于 `start` 时表示跨越午夜，两者相同表示全天
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScheduleEntry {
+    /// 生效的星期（1 = 周一 … 7 = 周日，以时段开始的日期为准），为空表示每天
+    #[serde(default)]
+    pub days: Vec<u32>,
+    /// 开始时间，`HH:MM`
+    pub start: String,
+    /// 结束时间，`HH:MM`
+    pub end: String,
+    #[serde(rename = "profileId")]
+    pub profile_id: String,
+}
+
+/// 下载规则：所有已填写的条件均满足时命中（同一条件的多个取值任一匹配即可），
+/// 命中后为任务补全未指定的分类、保存目录、限速、代理，并追加请求头
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DownloadRule {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_result_true")]
+    pub enabled: bool,
+    /// 主机名，同时匹配其子域名（`example.com` 匹配 `cdn.example.com`）
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// 文件扩展名，不含点，忽略大小写
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// MIME 类型，支持 `video/*` 形式的通配；需要向服务器发送 HEAD 请求探测
+    #[serde(rename = "mimeTypes", default)]
+    pub mime_types: Vec<String>,
+    /// 种子（或磁力链接显示名）名称的正则表达式，忽略大小写
+    #[serde(rename = "torrentNamePattern", default = "default_string_empty")]
+    pub torrent_name_pattern: String,
+    /// 命中后设置的分类 ID
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(rename = "saveDir", default = "default_string_empty")]
+    pub save_dir: String,
+    #[serde(rename = "maxDownloadLimit", default = "default_string_empty")]
+    pub max_download_limit: String,
+    #[serde(default = "default_string_empty")]
+    pub proxy: String,
+    /// 追加的请求头，如 `Cookie: a=1`
+    #[serde(default)]
+    pub headers: Vec<String>,
+}
+
+/// 任务分类：新建任务未指定对应参数时使用分类的默认值
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Category {
+    /// 分类 ID，写入任务记录；改名不影响已有任务
+    pub id: String,
+    pub name: String,
+    /// 界面展示用颜色，如 `#4f8cff`
+    #[serde(default = "default_string_empty")]
+    pub color: String,
+    /// 默认保存目录，为空时使用全局默认目录
+    #[serde(rename = "defaultDir", default = "default_string_empty")]
+    pub default_dir: String,
+    /// 默认单任务下载限速，格式同 aria2（如 `2M`）
+    #[serde(rename = "maxDownloadLimit", default = "default_string_empty")]
+    pub max_download_limit: String,
+    /// 默认 User-Agent
+    #[serde(rename = "userAgent", default = "default_string_empty")]
+    pub user_agent: String,
+}
+
+/// 历史任务（已完成、出错、已移除、文件缺失）的保留策略，0 表示不限制
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HistoryRetention {
+    /// 结束超过该天数的历史任务会被清理
+    #[serde(rename = "maxAgeDays", default)]
+    pub max_age_days: u32,
+    /// 最多保留的历史任务条数，超出时清理最早结束的
+    #[serde(rename = "maxCount", default)]
+    pub max_count: u32,
+}
+
+impl HistoryRetention {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age_days == 0 && self.max_count == 0
+    }
+}
+
+/// 自动重试策略：按错误分类决定是否重试，重试间隔指数退避
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// 默认关闭，由用户在设置中开启
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(rename = "maxAttempts", default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+    /// 第一次重试前的等待秒数，之后每次翻倍
+    #[serde(rename = "baseDelaySecs", default = "default_retry_base_delay")]
+    pub base_delay_secs: u64,
+    #[serde(rename = "maxDelaySecs", default = "default_retry_max_delay")]
+    pub max_delay_secs: u64,
+    #[serde(rename = "retryableKinds", default = "default_retryable_kinds")]
+    pub retryable_kinds: Vec<TaskErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: default_retry_max_attempts(),
+            base_delay_secs: default_retry_base_delay(),
+            max_delay_secs: default_retry_max_delay(),
+            retryable_kinds: default_retryable_kinds(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 指定分类的失败在已重试 `attempts` 次后是否还应继续重试
+    pub fn allows(&self, kind: TaskErrorKind, attempts: u32) -> bool {
+        self.enabled && attempts < self.max_attempts && self.retryable_kinds.contains(&kind)
+    }
+
+    /// 第 `attempts + 1` 次重试前的等待时长
+    pub fn delay_for(&self, attempts: u32) -> std::time::Duration {
+        let factor = 1u64.checked_shl(attempts.min(32)).unwrap_or(u64::MAX);
+        let secs = self
+            .base_delay_secs
+            .saturating_mul(factor)
+            .min(self.max_delay_secs.max(self.base_delay_secs));
+        std::time::Duration::from_secs(secs)
+    }
+}
+
+/// 远程 aria2 守护进程连接配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RemoteBackendConfig {
+    /// 后端 ID，写入任务记录用于路由；作为默认后端时忽略
+    #[serde(default = "default_string_empty")]
+    pub id: String,
+    #[serde(default = "default_string_empty")]
+    pub name: String,
+    #[serde(default = "default_result_false")]
+    pub enabled: bool,
+    #[serde(default = "default_string_empty")]
+    pub host: String,
+    #[serde(default = "default_remote_port")]
+    pub port: u16,
+    #[serde(rename = "useTls", default = "default_result_false")]
+    pub use_tls: bool,
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// 反向代理场景下 `/jsonrpc` 之前的路径前缀，例如 `/aria2`
+    #[serde(rename = "pathPrefix", default = "default_string_empty")]
+    pub path_prefix: String,
+    /// 远程目录到本地挂载点的映射，按顺序匹配第一个前缀
+    #[serde(rename = "pathMappings", default)]
+    pub path_mappings: Vec<PathMapping>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PathMapping {
+    pub remote: String,
+    pub local: String,
+}
+
+impl Default for RemoteBackendConfig {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            enabled: false,
+            host: String::new(),
+            port: default_remote_port(),
+            use_tls: false,
+            secret: None,
+            path_prefix: String::new(),
+            path_mappings: Vec::new(),
+        }
+    }
+}
+
+impl RemoteBackendConfig {
+    /// 将远程路径映射为本地路径，没有匹配的映射时返回 None
+    pub fn map_to_local(&self, remote_path: &str) -> Option<String> {
+        for mapping in &self.path_mappings {
+            let prefix = mapping.remote.trim_end_matches(['/', '\\']);
+            if prefix.is_empty() {
+                continue;
+            }
+            if let Some(rest) = remote_path.strip_prefix(prefix) {
+                // 仅在路径分隔处匹配，避免 /data 误匹配 /database
+                if rest.is_empty() || rest.starts_with(['/', '\\']) {
+                    let rest = rest.trim_start_matches(['/', '\\']);
+                    let local = std::path::Path::new(&mapping.local).join(rest);
+                    return Some(local.to_string_lossy().to_string());
+                }
+            }
+        }
+        None
+    }
+}
+
+impl AppConfig {
+    pub fn category(&self, id: &str) -> Option<&Category> {
+        self.categories.iter().find(|c| c.id == id)
+    }
+
+    /// 将某个后端上的 aria2 路径解析为本机可访问的路径。
+    /// 本地 Sidecar 原样返回；远程后端依赖路径映射，未映射时返回 None。
+    pub fn resolve_local_path(&self, backend_id: &str, aria2_path: &str) -> Option<String> {
+        if backend_id == crate::aria2::client::DEFAULT_BACKEND_ID {
+            if self.remote_backend.enabled {
+                self.remote_backend.map_to_local(aria2_path)
+            } else {
+                Some(aria2_path.to_string())
+            }
+        } else {
+            self.remote_backends
+                .iter()
+                .find(|b| b.id == backend_id)
+                .and_then(|b| b.map_to_local(aria2_path))
+        }
+    }
 }
 
 /// 当前配置版本号
-const CURRENT_CONFIG_VERSION: u32 = 1;
+const CURRENT_CONFIG_VERSION: u32 = migrations::CONFIG_VERSION;
 
 fn default_config_version() -> u32 {
     CURRENT_CONFIG_VERSION
 }
 
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay() -> u64 {
+    10
+}
+
+fn default_retry_max_delay() -> u64 {
+    600
+}
+
+fn default_retryable_kinds() -> Vec<TaskErrorKind> {
+    vec![
+        TaskErrorKind::Timeout,
+        TaskErrorKind::TooSlow,
+        TaskErrorKind::Network,
+        TaskErrorKind::Interrupted,
+        TaskErrorKind::NameResolution,
+        TaskErrorKind::ServerBusy,
+    ]
+}
+
+fn default_power_countdown() -> u32 {
+    60
+}
+
+fn default_alt_download_limit() -> String {
+    "512K".to_string()
+}
+
+fn default_alt_upload_limit() -> String {
+    "128K".to_string()
+}
+
+fn default_remote_port() -> u16 {
+    6800
+}
+
 fn default_seed_ratio() -> f64 {
     1.0
 }
@@ -147,6 +504,15 @@ impl Default for AppConfig {
             seed_ratio: 1.0,
             dht_listen_port: default_bt_port(),
             listen_port: default_bt_port(),
+            remote_backend: RemoteBackendConfig::default(),
+            remote_backends: Vec::new(),
+            retry_policy: RetryPolicy::default(),
+            history_retention: HistoryRetention::default(),
+            categories: Vec::new(),
+            rules: Vec::new(),
+            bandwidth_schedule: BandwidthSchedule::default(),
+            alt_speed: AltSpeedLimits::default(),
+            post_completion: PostCompletion::default(),
         }
     }
 }
@@ -155,6 +521,16 @@ pub struct ConfigState {
     pub config: Mutex<AppConfig>,
 }
 
+impl ConfigState {
+    /// 当前配置的副本（锁异常时回退为默认配置）
+    pub fn snapshot(&self) -> AppConfig {
+        self.config
+            .lock()
+            .map(|c| c.clone())
+            .unwrap_or_default()
+    }
+}
+
 pub fn get_config_path(app: &AppHandle) -> Option<PathBuf> {
     app.path()
         .app_config_dir()
@@ -171,26 +547,35 @@ pub fn load_config(app: &AppHandle) -> AppConfig {
         );
         if path.exists() {
             if let Ok(content) = fs::read_to_string(&path) {
-                if let Ok(mut config) = serde_json::from_str::<AppConfig>(&content) {
-                    crate::app_info!(
-                        "Core::Config",
-                        "loaded_from_disk",
-                        json!({ "start_minimized": config.start_minimized })
-                    );
-                    // Ensure secret exists
-                    if config.rpc_secret.is_none() {
-                        let secret = uuid::Uuid::new_v4().to_string();
-                        config.rpc_secret = Some(secret);
-                        // Save back
-                        let _ = save_config(app, &config);
+                match decode_config(&content) {
+                    Ok((mut config, migrated)) => {
+                        crate::app_info!(
+                            "Core::Config",
+                            "loaded_from_disk",
+                            json!({ "start_minimized": config.start_minimized, "migrated": migrated })
+                        );
+                        // Ensure secret exists
+                        let missing_secret = config.rpc_secret.is_none();
+                        if missing_secret {
+                            let secret = uuid::Uuid::new_v4().to_string();
+                            config.rpc_secret = Some(secret);
+                        }
+                        // Save back (旧版本文件同时升级为当前格式)
+                        if missing_secret || migrated {
+                            let _ = save_config(app, &config);
+                        }
+                        return config;
+                    }
+                    Err(e) => {
+                        crate::app_error!(
+                            "Core::Config",
+                            "deserialize_failed",
+                            json!({ "raw": content, "error": e.to_string() })
+                        );
+                        if let Some(config) = recover_config(app, &path, &content) {
+                            return config;
+                        }
                     }
-                    return config;
-                } else {
-                    crate::app_error!(
-                        "Core::Config",
-                        "deserialize_failed",
-                        json!({ "raw": content })
-                    );
                 }
             } else {
                 crate::app_error!(
@@ -219,9 +604,129 @@ pub fn load_config(app: &AppHandle) -> AppConfig {
     config
 }
 
+/// 解析配置文件：拆开版本信封并执行迁移链，返回 (配置, 是否经过迁移)
+fn decode_config(content: &str) -> AppResult<(AppConfig, bool)> {
+    let (version, mut data) = migrations::open_envelope(serde_json::from_str(content)?);
+    let migrated_to = migrations::migrate(
+        &mut data,
+        version,
+        migrations::CONFIG_MIGRATIONS,
+        "config",
+    )?;
+    let config = serde_json::from_value::<AppConfig>(data)?;
+    Ok((config, migrated_to != version))
+}
+
+/// 配置文件无法解析时：隔离原文件，优先使用最新的可用备份，
+/// 否则从原文件中逐字段保留仍然合法的设置，其余取默认值
+fn recover_config(app: &AppHandle, path: &Path, content: &str) -> Option<AppConfig> {
+    let (mut config, report) = recover_config_file(path, content)?;
+
+    if config.rpc_secret.is_none() {
+        config.rpc_secret = Some(uuid::Uuid::new_v4().to_string());
+    }
+    let _ = save_config(app, &config);
+
+    if let Some(log) = app.try_state::<RecoveryLog>() {
+        log.push(report);
+    }
+    Some(config)
+}
+
+/// `recover_config` 中与应用状态无关的部分：隔离、读取备份与逐字段抢救
+fn recover_config_file(path: &Path, content: &str) -> Option<(AppConfig, RecoveryReport)> {
+    let mut report = RecoveryReport::new(path);
+    match recovery::quarantine(path) {
+        Ok(target) => report.quarantined = Some(target.to_string_lossy().to_string()),
+        Err(e) => {
+            crate::app_error!(
+                "Core::Config",
+                "quarantine_failed",
+                json!({ "path": path.to_string_lossy(), "error": e.to_string() })
+            );
+            return None;
+        }
+    }
+
+    let restored = recovery::existing_backups(path).into_iter().find_map(|backup| {
+        let raw = fs::read_to_string(&backup).ok()?;
+        let (config, _) = decode_config(&raw).ok()?;
+        Some((backup, raw, config))
+    });
+
+    let config = match restored {
+        Some((backup, raw, config)) => {
+            report.restored_from = Some(backup.to_string_lossy().to_string());
+            report.recovered = serde_json::from_str::<Value>(&raw)
+                .map(|value| match migrations::open_envelope(value).1 {
+                    Value::Object(fields) => fields.len(),
+                    _ => 0,
+                })
+                .unwrap_or(0);
+            config
+        }
+        None => match salvage_config_fields(content) {
+            Some((config, recovered, lost)) => {
+                report.recovered = recovered;
+                report.lost = lost;
+                config
+            }
+            None => {
+                report.whole_file_lost = true;
+                AppConfig::default()
+            }
+        },
+    };
+
+    Some((config, report))
+}
+
+/// 以默认配置为基础逐个套用原文件中的字段，仅保留套用后仍能通过反序列化的字段。
+/// 返回 (配置, 保留的字段数, 丢弃的字段数)；原文件不是 JSON 对象时返回 None。
+fn salvage_config_fields(content: &str) -> Option<(AppConfig, usize, usize)> {
+    let raw = serde_json::from_str::<Value>(content).ok()?;
+    let (version, mut data) = migrations::open_envelope(raw);
+    // 迁移失败时仍尝试按当前结构逐字段抢救
+    let _ = migrations::migrate(&mut data, version, migrations::CONFIG_MIGRATIONS, "config");
+    let Value::Object(fields) = data else {
+        return None;
+    };
+
+    let defaults = AppConfig::default();
+    let Ok(mut merged) = serde_json::to_value(&defaults) else {
+        return Some((defaults, 0, fields.len()));
+    };
+
+    let mut recovered = 0;
+    let mut lost = 0;
+    for (key, value) in fields {
+        let mut candidate = merged.clone();
+        candidate[key.as_str()] = value;
+        if serde_json::from_value::<AppConfig>(candidate.clone()).is_ok() {
+            merged = candidate;
+            recovered += 1;
+        } else {
+            lost += 1;
+        }
+    }
+
+    let config = serde_json::from_value(merged).unwrap_or(defaults);
+    Some((config, recovered, lost))
+}
+
 pub fn save_config(app: &AppHandle, config: &AppConfig) -> crate::core::error::AppResult<()> {
     if let Some(path) = get_config_path(app) {
-        let json = serde_json::to_string_pretty(config)?;
+        let mut config = config.clone();
+        config.version = CURRENT_CONFIG_VERSION;
+        let envelope = migrations::seal_envelope(CURRENT_CONFIG_VERSION, &config)?;
+        let json = serde_json::to_string_pretty(&envelope)?;
+        if let Err(e) = recovery::rotate_backups(&path) {
+            crate::app_warn!(
+                "Core::Config",
+                "backup_rotate_failed",
+                json!({ "path": path.to_string_lossy(), "error": e.to_string() })
+            );
+        }
         crate::utils::atomic_write(&path, &json)?;
         Ok(())
     } else {
@@ -230,3 +735,154 @@ pub fn save_config(app: &AppHandle, config: &AppConfig) -> crate::core::error::A
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::temp_dir;
+
+    fn temp_config(name: &str) -> PathBuf {
+        temp_dir(name).join("mua_config.json")
+    }
+
+    #[test]
+    fn salvage_keeps_valid_fields_and_drops_invalid_ones() {
+        let path = temp_config("partial");
+        let content = format!(
+            r#"{{"version":{},"data":{{"rpcPort":6900,"maxConcurrentDownloads":"many"}}}}"#,
+            CURRENT_CONFIG_VERSION
+        );
+        fs::write(&path, &content).unwrap();
+
+        let (config, report) = recover_config_file(&path, &content).unwrap();
+        assert_eq!(config.rpc_port, 6900);
+        assert_eq!(
+            config.max_concurrent_downloads,
+            AppConfig::default().max_concurrent_downloads
+        );
+        assert_eq!(report.recovered, 1);
+        assert_eq!(report.lost, 1);
+        assert!(!report.whole_file_lost);
+        assert!(report.restored_from.is_none());
+        assert!(report.quarantined.is_some());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn non_json_content_is_reported_as_wholly_lost() {
+        let path = temp_config("garbage");
+        let content = "\u{0}\u{0}not json at all";
+        fs::write(&path, content).unwrap();
+
+        let (config, report) = recover_config_file(&path, content).unwrap();
+        assert_eq!(config.rpc_port, AppConfig::default().rpc_port);
+        assert!(report.whole_file_lost);
+        assert_eq!(report.recovered, 0);
+        assert!(report.restored_from.is_none());
+    }
+
+    #[test]
+    fn newest_valid_backup_is_preferred_over_salvage() {
+        let path = temp_config("backup");
+        let backup = AppConfig {
+            rpc_port: 7100,
+            ..AppConfig::default()
+        };
+        let envelope = migrations::seal_envelope(CURRENT_CONFIG_VERSION, &backup).unwrap();
+        fs::write(recovery::backup_path(&path, 1), "{ broken").unwrap();
+        fs::write(recovery::backup_path(&path, 2), envelope.to_string()).unwrap();
+
+        let content = r#"{"rpcPort":6900"#;
+        fs::write(&path, content).unwrap();
+
+        let (config, report) = recover_config_file(&path, content).unwrap();
+        assert_eq!(config.rpc_port, 7100);
+        assert_eq!(
+            report.restored_from.as_deref(),
+            Some(recovery::backup_path(&path, 2).to_string_lossy().as_ref())
+        );
+        assert!(!report.whole_file_lost);
+    }
+
+    fn nas() -> RemoteBackendConfig {
+        RemoteBackendConfig {
+            id: "nas".to_string(),
+            enabled: true,
+            path_mappings: vec![
+                PathMapping {
+                    remote: "/data".to_string(),
+                    local: "/mnt/nas".to_string(),
+                },
+                PathMapping {
+                    remote: "/downloads/".to_string(),
+                    local: "/home/user/Downloads".to_string(),
+                },
+            ],
+            ..RemoteBackendConfig::default()
+        }
+    }
+
+    fn mapped(backend: &RemoteBackendConfig, remote: &str) -> Option<PathBuf> {
+        backend.map_to_local(remote).map(PathBuf::from)
+    }
+
+    #[test]
+    fn maps_remote_paths_on_separator_boundaries() {
+        let backend = nas();
+        assert_eq!(
+            mapped(&backend, "/data/movies/a.mkv"),
+            Some(Path::new("/mnt/nas").join("movies/a.mkv"))
+        );
+        assert_eq!(mapped(&backend, "/data"), Some(PathBuf::from("/mnt/nas")));
+        // /data 不应匹配 /database
+        assert_eq!(mapped(&backend, "/database/a.mkv"), None);
+    }
+
+    #[test]
+    fn trailing_separator_in_mapping_is_ignored() {
+        let backend = nas();
+        assert_eq!(
+            mapped(&backend, "/downloads/x.iso"),
+            Some(Path::new("/home/user/Downloads").join("x.iso"))
+        );
+        assert_eq!(
+            mapped(&backend, "/downloads"),
+            Some(PathBuf::from("/home/user/Downloads"))
+        );
+    }
+
+    #[test]
+    fn unmapped_remote_paths_are_unresolved() {
+        let backend = nas();
+        assert_eq!(mapped(&backend, "/srv/other/a.mkv"), None);
+        assert_eq!(mapped(&RemoteBackendConfig::default(), "/data/a.mkv"), None);
+    }
+
+    #[test]
+    fn resolves_paths_per_backend() {
+        let resolve = |config: &AppConfig, backend: &str, path: &str| {
+            config.resolve_local_path(backend, path).map(PathBuf::from)
+        };
+        let default_id = crate::aria2::client::DEFAULT_BACKEND_ID;
+        let nas_path = Some(Path::new("/mnt/nas").join("a.mkv"));
+
+        let local = AppConfig {
+            remote_backends: vec![nas()],
+            ..AppConfig::default()
+        };
+        // 本地 Sidecar 原样返回
+        assert_eq!(
+            resolve(&local, default_id, "/data/a.mkv"),
+            Some(PathBuf::from("/data/a.mkv"))
+        );
+        assert_eq!(resolve(&local, "nas", "/data/a.mkv"), nas_path);
+        assert_eq!(resolve(&local, "unknown", "/data/a.mkv"), None);
+
+        let remote = AppConfig {
+            remote_backend: nas(),
+            ..AppConfig::default()
+        };
+        assert_eq!(resolve(&remote, default_id, "/data/a.mkv"), nas_path);
+        assert_eq!(resolve(&remote, default_id, "/srv/a.mkv"), None);
+    }
+}
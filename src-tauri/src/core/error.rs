@@ -1,9 +1,13 @@
 //! 应用统一错误类型
 //! 提供标准化的错误处理，支持 Tauri 命令返回
 
-use serde::Serialize;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
 use std::fmt;
 
+/// aria2 对 GID 不存在（或操作不适用于该任务）返回的错误码
+pub const ARIA2_ERR_NOT_FOUND: i64 = 1;
+
 /// 应用错误枚举
 /// 实现了 `serde::Serialize` 以支持 Tauri 命令返回
 #[derive(Debug, Clone, Serialize)]
@@ -11,10 +15,15 @@ use std::fmt;
 pub enum AppError {
     /// Aria2 RPC 通信错误
     Aria2(String),
+    /// aria2 返回的 JSON-RPC 错误，保留数值错误码；对前端的序列化形式与 `Aria2` 相同
+    #[serde(rename = "Aria2")]
+    Aria2Rpc(Aria2RpcError),
     /// 配置相关错误
     Config(String),
     /// 文件系统错误
     Fs(String),
+    /// 任务数据库（SQLite）错误
+    Database(String),
     /// 任务不存在
     TaskNotFound(String),
     /// 验证错误
@@ -27,8 +36,10 @@ impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Aria2(msg) => write!(f, "Aria2 错误: {}", msg),
+            Self::Aria2Rpc(err) => write!(f, "Aria2 错误: {}", err),
             Self::Config(msg) => write!(f, "配置错误: {}", msg),
             Self::Fs(msg) => write!(f, "文件系统错误: {}", msg),
+            Self::Database(msg) => write!(f, "数据库错误: {}", msg),
             Self::TaskNotFound(msg) => write!(f, "任务不存在: {}", msg),
             Self::Validation(msg) => write!(f, "验证错误: {}", msg),
             Self::Other(msg) => write!(f, "{}", msg),
@@ -38,6 +49,39 @@ impl fmt::Display for AppError {
 
 impl std::error::Error for AppError {}
 
+/// aria2 JSON-RPC 响应中的 `error` 对象
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aria2RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl Aria2RpcError {
+    /// 从响应的 `error` 字段解析，缺少错误码时记为 0
+    pub fn from_value(error: &Value) -> Self {
+        Self {
+            code: error.get("code").and_then(Value::as_i64).unwrap_or(0),
+            message: error
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Aria2RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+impl Serialize for Aria2RpcError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 // 实现常见错误类型转换，支持 ? 操作符
 
 impl From<std::io::Error> for AppError {
@@ -52,6 +96,12 @@ impl From<serde_json::Error> for AppError {
     }
 }
 
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Database(err.to_string())
+    }
+}
+
 impl From<tauri::Error> for AppError {
     fn from(err: tauri::Error) -> Self {
         Self::Other(err.to_string())
@@ -106,32 +156,46 @@ impl AppError {
         Self::Fs(msg.into())
     }
 
+    /// aria2 JSON-RPC 错误响应
+    pub fn aria2_rpc(error: &Value) -> Self {
+        Self::Aria2Rpc(Aria2RpcError::from_value(error))
+    }
+
+    /// aria2 明确返回了“GID 不存在”错误码；连接失败等其他错误一律返回 false
     pub fn is_aria2_not_found(&self) -> bool {
-        let Self::Aria2(message) = self else {
-            return false;
-        };
-
-        // 优先使用结构化 JSON 错误码判断
-        if let Ok(value) = serde_json::from_str::<serde_json::Value>(message) {
-            // aria2 标准错误码 1 = "GID not found"
-            if value.get("code").and_then(|v| v.as_i64()) == Some(1) {
-                return true;
-            }
-
-            if value
-                .get("message")
-                .and_then(|v| v.as_str())
-                .is_some_and(|m| m.to_lowercase().contains("not found"))
-            {
-                return true;
-            }
-
-            // 结构化 JSON 但未匹配 → 非 "not found" 错误
-            return false;
-        }
+        matches!(self, Self::Aria2Rpc(err) if err.code == ARIA2_ERR_NOT_FOUND)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn not_found_is_decided_by_error_code_only() {
+        let not_found = AppError::aria2_rpc(
+            &json!({ "code": 1, "message": "GID 2089b05ecca3d829 is not found" }),
+        );
+        assert!(not_found.is_aria2_not_found());
+
+        let other =
+            AppError::aria2_rpc(&json!({ "code": 2, "message": "GID 2089b05ecca3d829 timed out" }));
+        assert!(!other.is_aria2_not_found());
+
+        // 非 RPC 错误即使文本中包含 GID 或 not found 也不视为任务不存在
+        assert!(!AppError::aria2("connection refused while resolving gid").is_aria2_not_found());
+        assert!(!AppError::aria2("HTTP Error: 404 Not Found").is_aria2_not_found());
+        assert!(!AppError::task_not_found("a").is_aria2_not_found());
+    }
 
-        // 仅在非 JSON 消息时回退到字符串匹配
-        let lower = message.to_lowercase();
-        lower.contains("not found") || lower.contains("gid")
+    #[test]
+    fn rpc_errors_serialize_like_plain_aria2_errors() {
+        let error = AppError::aria2_rpc(&json!({ "code": 1, "message": "GID a is not found" }));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "type": "Aria2", "message": "GID a is not found (code 1)" })
+        );
+        assert_eq!(error.to_string(), "Aria2 错误: GID a is not found (code 1)");
     }
 }
@@ -2,3 +2,8 @@ pub const EVENT_TASKS_DELTA: &str = "tasks-delta";
 pub const EVENT_ARIA2_STDOUT: &str = "aria2-stdout";
 pub const EVENT_ARIA2_SIDECAR_ERROR: &str = "aria2-sidecar-error";
 pub const EVENT_TASK_COMPLETED: &str = "task-completed";
+pub const EVENT_GLOBAL_STATS: &str = "global-stats";
+pub const EVENT_STORE_RECOVERED: &str = "store-recovered";
+pub const EVENT_BANDWIDTH_PROFILE_CHANGED: &str = "bandwidth-profile-changed";
+pub const EVENT_POWER_ACTION_PENDING: &str = "power-action-pending";
+pub const EVENT_POWER_ACTION_CANCELLED: &str = "power-action-cancelled";
@@ -0,0 +1,186 @@
+//! 磁力链接解析
+//! 支持 `xt`（BitTorrent v1 `urn:btih:`，十六进制或 Base32；v2 `urn:btmh:1220…`）、
+//! `dn`（显示名称）、`tr`（Tracker，可重复）与 `xl`（文件大小）。
+//! 解析出的 infohash 用于识别重复添加的同一资源。
+
+use crate::core::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MagnetInfo {
+    /// v1 infohash（40 位小写十六进制）
+    pub info_hash: Option<String>,
+    /// v2 infohash（SHA-256，64 位小写十六进制，不含 multihash 前缀）
+    pub info_hash_v2: Option<String>,
+    pub display_name: Option<String>,
+    pub trackers: Vec<String>,
+    pub exact_length: Option<u64>,
+}
+
+impl MagnetInfo {
+    /// 是否包含给定的 infohash（v1 或 v2，不区分大小写）
+    pub fn has_hash(&self, hash: &str) -> bool {
+        let hash = hash.trim().to_ascii_lowercase();
+        self.info_hash.as_deref() == Some(hash.as_str())
+            || self.info_hash_v2.as_deref() == Some(hash.as_str())
+    }
+
+    /// 两个链接是否指向同一资源（任一 infohash 相同）
+    pub fn same_torrent(&self, other: &MagnetInfo) -> bool {
+        [&other.info_hash, &other.info_hash_v2]
+            .into_iter()
+            .flatten()
+            .any(|hash| self.has_hash(hash))
+    }
+
+    /// 用于去重的主键：优先使用 v1 infohash
+    pub fn key(&self) -> Option<&str> {
+        self.info_hash.as_deref().or(self.info_hash_v2.as_deref())
+    }
+}
+
+pub fn is_magnet(url: &str) -> bool {
+    url.get(..7).is_some_and(|scheme| scheme.eq_ignore_ascii_case("magnet:"))
+}
+
+pub fn parse_magnet(uri: &str) -> AppResult<MagnetInfo> {
+    let uri = uri.trim();
+    let invalid = |reason: &str| AppError::validation(format!("无效的磁力链接（{}）: {}", reason, uri));
+
+    if !is_magnet(uri) {
+        return Err(invalid("缺少 magnet: 前缀"));
+    }
+    let query = uri[7..].trim_start_matches('?');
+
+    let mut info = MagnetInfo::default();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = decode_component(value);
+        // 多值参数可写作 tr.1、xt.2 等
+        let key = key.split('.').next().unwrap_or(key).to_ascii_lowercase();
+
+        match key.as_str() {
+            "xt" => parse_exact_topic(&value, &mut info).map_err(|_| invalid("infohash 格式错误"))?,
+            "dn" if !value.trim().is_empty() => {
+                info.display_name.get_or_insert_with(|| value.trim().to_string());
+            }
+            "tr" if !value.trim().is_empty() => {
+                let tracker = value.trim().to_string();
+                if !info.trackers.contains(&tracker) {
+                    info.trackers.push(tracker);
+                }
+            }
+            "xl" => info.exact_length = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+
+    if info.key().is_none() {
+        return Err(invalid("缺少 infohash"));
+    }
+    Ok(info)
+}
+
+/// 解析 `xt` 参数；非 BitTorrent 的 URN（如 ed2k）忽略
+fn parse_exact_topic(value: &str, info: &mut MagnetInfo) -> Result<(), ()> {
+    let lower = value.trim().to_ascii_lowercase();
+
+    if let Some(hash) = lower.strip_prefix("urn:btih:") {
+        let hex = match hash.len() {
+            40 if hash.chars().all(|c| c.is_ascii_hexdigit()) => hash.to_string(),
+            32 => hex::encode(decode_base32(hash).ok_or(())?),
+            _ => return Err(()),
+        };
+        info.info_hash.get_or_insert(hex);
+    } else if let Some(multihash) = lower.strip_prefix("urn:btmh:") {
+        // 0x12 = sha2-256，0x20 = 32 字节
+        let hex = multihash.strip_prefix("1220").ok_or(())?;
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(());
+        }
+        info.info_hash_v2.get_or_insert(hex.to_string());
+    }
+    Ok(())
+}
+
+/// 按表单编码解码参数值（`+` 表示空格）
+fn decode_component(value: &str) -> String {
+    let value = value.replace('+', " ");
+    urlencoding::decode(&value)
+        .map(|v| v.into_owned())
+        .unwrap_or(value)
+}
+
+/// RFC 4648 Base32 解码（不含填充），用于 32 位的 btih
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in input.chars() {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "c12fe1c06bba254a9dc9f519b335aa7c1367a88a";
+
+    #[test]
+    fn parses_all_fields() {
+        let info = parse_magnet(&format!(
+            "magnet:?xt=urn:btih:{}&dn=Ubuntu+24.04%20Desktop&tr=udp%3A%2F%2Ftracker.example.org%3A1337&tr.1=https://t.example.com/announce&tr=udp%3A%2F%2Ftracker.example.org%3A1337&xl=1024",
+            HASH.to_uppercase()
+        ))
+        .unwrap();
+
+        assert_eq!(info.info_hash.as_deref(), Some(HASH));
+        assert_eq!(info.display_name.as_deref(), Some("Ubuntu 24.04 Desktop"));
+        assert_eq!(
+            info.trackers,
+            vec!["udp://tracker.example.org:1337", "https://t.example.com/announce"]
+        );
+        assert_eq!(info.exact_length, Some(1024));
+    }
+
+    #[test]
+    fn base32_and_v2_hashes() {
+        let base32 = parse_magnet("magnet:?xt=urn:btih:YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKEK").unwrap();
+        assert_eq!(base32.info_hash.as_deref(), Some(HASH));
+
+        let v2_hash = "a".repeat(64);
+        let hybrid = parse_magnet(&format!(
+            "magnet:?xt=urn:btih:{}&xt=urn:btmh:1220{}",
+            HASH, v2_hash
+        ))
+        .unwrap();
+        assert_eq!(hybrid.info_hash_v2.as_deref(), Some(v2_hash.as_str()));
+
+        let v2_only = parse_magnet(&format!("magnet:?xt=urn:btmh:1220{}", v2_hash)).unwrap();
+        assert_eq!(v2_only.key(), Some(v2_hash.as_str()));
+        assert!(v2_only.same_torrent(&hybrid));
+        assert!(!v2_only.same_torrent(&base32));
+    }
+
+    #[test]
+    fn rejects_invalid_links() {
+        assert!(parse_magnet("https://example.com/a.torrent").is_err());
+        assert!(parse_magnet("magnet:?dn=no-hash").is_err());
+        assert!(parse_magnet("magnet:?xt=urn:btih:1234").is_err());
+        assert!(parse_magnet("magnet:?xt=urn:btmh:1114abcd").is_err());
+    }
+}
@@ -0,0 +1,344 @@
+//! Metalink 文件解析
+//! 同时支持 Metalink 4（RFC 5854，`.meta4`）与 Metalink 3（`.metalink`），
+//! 用于在添加前预览文件名、大小、校验值与镜像列表。实际下载交给 aria2 的 `addMetalink`。
+
+use crate::core::checksum::Checksum;
+use crate::core::error::{AppError, AppResult};
+use roxmltree::Node;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetalinkInfo {
+    pub files: Vec<MetalinkFile>,
+    pub total_length: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetalinkFile {
+    pub name: String,
+    /// 未声明大小时为 None
+    pub length: Option<u64>,
+    pub hashes: Vec<MetalinkHash>,
+    pub mirrors: Vec<MetalinkMirror>,
+    /// 从 1 开始，与 aria2 的 select-file 一致
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetalinkHash {
+    /// 算法名称，支持的算法统一为 `sha-1`、`sha-256`、`md5`
+    pub algorithm: String,
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetalinkMirror {
+    pub url: String,
+    /// 镜像所在国家/地区代码
+    pub location: Option<String>,
+    /// Metalink 4 的优先级，数值越小越优先
+    pub priority: Option<u32>,
+    /// 非直链资源的类型（如 `torrent`）
+    pub media_type: Option<String>,
+}
+
+/// 元数据文件大小上限
+const MAX_METALINK_SIZE: u64 = 5 * 1024 * 1024;
+
+pub fn parse_metalink_file<P: AsRef<Path>>(path: P) -> AppResult<MetalinkInfo> {
+    let metadata = std::fs::metadata(&path).map_err(|e| AppError::Fs(e.to_string()))?;
+    if metadata.len() > MAX_METALINK_SIZE {
+        return Err(AppError::Validation(
+            "文件过大，不是有效的 Metalink 文件".into(),
+        ));
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|e| AppError::Fs(e.to_string()))?;
+    parse_metalink(&content)
+}
+
+pub fn parse_metalink(content: &str) -> AppResult<MetalinkInfo> {
+    // roxmltree 默认拒绝 DTD，不会展开外部实体
+    let doc = roxmltree::Document::parse(content)
+        .map_err(|e| AppError::Validation(format!("无效的 Metalink 文件: {}", e)))?;
+
+    let root = doc.root_element();
+    if root.tag_name().name() != "metalink" {
+        return Err(AppError::Validation(
+            "无效的 Metalink 文件: 缺少 metalink 根元素".into(),
+        ));
+    }
+
+    // Metalink 4 的 file 直接位于根元素下，Metalink 3 位于 files 元素内
+    let file_nodes = root
+        .children()
+        .flat_map(|node| match node.tag_name().name() {
+            "file" => vec![node],
+            "files" => elements(node, "file").collect(),
+            _ => Vec::new(),
+        });
+
+    let mut files = Vec::new();
+    for node in file_nodes {
+        let name = node
+            .attribute("name")
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| AppError::Validation("Metalink 文件中存在未命名的文件".into()))?;
+
+        files.push(MetalinkFile {
+            name: name.to_string(),
+            length: elements(node, "size")
+                .next()
+                .and_then(|n| n.text())
+                .and_then(|t| t.trim().parse().ok()),
+            hashes: parse_hashes(node),
+            mirrors: parse_mirrors(node),
+            index: files.len() + 1,
+        });
+    }
+
+    if files.is_empty() {
+        return Err(AppError::Validation(
+            "Metalink 文件中没有可下载的文件".into(),
+        ));
+    }
+
+    let total_length = files.iter().filter_map(|f| f.length).sum();
+    Ok(MetalinkInfo {
+        files,
+        total_length,
+    })
+}
+
+/// 是否为 Metalink 文件（按扩展名判断）
+pub fn is_metalink_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("meta4") || ext.eq_ignore_ascii_case("metalink")
+        })
+}
+
+/// 按 aria2 的 select-file 语法（如 `1,3-5`）筛选文件，未指定时返回全部
+pub fn select_files<'a>(
+    info: &'a MetalinkInfo,
+    spec: Option<&str>,
+) -> AppResult<Vec<&'a MetalinkFile>> {
+    let Some(spec) = spec.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(info.files.iter().collect());
+    };
+
+    let invalid = || AppError::Validation(format!("无效的文件序号: {}", spec));
+    let mut indices = Vec::new();
+    for part in spec.split(',') {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start, end),
+            None => (part, part),
+        };
+        let start: usize = start.trim().parse().map_err(|_| invalid())?;
+        let end: usize = end.trim().parse().map_err(|_| invalid())?;
+        if start == 0 || start > end || end > info.files.len() {
+            return Err(invalid());
+        }
+        indices.extend(start..=end);
+    }
+    indices.sort_unstable();
+    indices.dedup();
+
+    Ok(indices.into_iter().map(|i| &info.files[i - 1]).collect())
+}
+
+fn elements<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+/// 整个文件的校验值。分块校验（pieces 内的 hash）不在预览范围内
+fn parse_hashes(file: Node) -> Vec<MetalinkHash> {
+    let verification = elements(file, "verification").flat_map(|v| elements(v, "hash"));
+
+    elements(file, "hash")
+        .chain(verification)
+        .filter_map(|node| {
+            let algorithm = node.attribute("type")?.trim();
+            let digest = node.text()?.trim();
+            if algorithm.is_empty() || digest.is_empty() {
+                return None;
+            }
+
+            Some(
+                match Checksum::parse(&format!("{}={}", algorithm, digest)) {
+                    Ok(checksum) => MetalinkHash {
+                        algorithm: checksum.algorithm.as_str().to_string(),
+                        digest: checksum.digest,
+                    },
+                    Err(_) => MetalinkHash {
+                        algorithm: algorithm.to_ascii_lowercase(),
+                        digest: digest.to_ascii_lowercase(),
+                    },
+                },
+            )
+        })
+        .collect()
+}
+
+fn parse_mirrors(file: Node) -> Vec<MetalinkMirror> {
+    let v3_urls = elements(file, "resources").flat_map(|r| elements(r, "url"));
+
+    elements(file, "url")
+        .chain(elements(file, "metaurl"))
+        .chain(v3_urls)
+        .filter_map(|node| {
+            let url = node.text()?.trim();
+            if url.is_empty() {
+                return None;
+            }
+
+            // Metalink 3 以 type 区分直链（http/https/ftp）与种子等资源
+            let media_type = node
+                .attribute("mediatype")
+                .or_else(|| node.attribute("type").filter(|t| !is_direct_protocol(t)))
+                .map(str::to_string);
+
+            Some(MetalinkMirror {
+                url: url.to_string(),
+                location: node.attribute("location").map(str::to_string),
+                priority: node
+                    .attribute("priority")
+                    .and_then(|p| p.trim().parse().ok()),
+                media_type,
+            })
+        })
+        .collect()
+}
+
+fn is_direct_protocol(kind: &str) -> bool {
+    matches!(
+        kind.to_ascii_lowercase().as_str(),
+        "http" | "https" | "ftp" | "ftps"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_metalink4() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metalink xmlns="urn:ietf:params:xml:ns:metalink">
+  <file name="example.iso">
+    <size>1024</size>
+    <hash type="SHA-256">BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD</hash>
+    <pieces length="512" type="sha-1">
+      <hash>a9993e364706816aba3e25717850c26c9cd0d89d</hash>
+    </pieces>
+    <url location="de" priority="1">https://de.example.com/example.iso</url>
+    <url priority="2">ftp://ftp.example.com/example.iso</url>
+    <metaurl mediatype="torrent" priority="3">https://example.com/example.iso.torrent</metaurl>
+  </file>
+  <file name="example.sig">
+    <url>https://example.com/example.sig</url>
+  </file>
+</metalink>"#;
+
+        let info = parse_metalink(xml).unwrap();
+        assert_eq!(info.files.len(), 2);
+        assert_eq!(info.total_length, 1024);
+
+        let iso = &info.files[0];
+        assert_eq!(
+            (iso.name.as_str(), iso.length, iso.index),
+            ("example.iso", Some(1024), 1)
+        );
+        assert_eq!(
+            iso.hashes,
+            vec![MetalinkHash {
+                algorithm: "sha-256".to_string(),
+                digest: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+                    .to_string(),
+            }]
+        );
+        assert_eq!(iso.mirrors.len(), 3);
+        assert_eq!(iso.mirrors[0].location.as_deref(), Some("de"));
+        assert_eq!(iso.mirrors[0].priority, Some(1));
+        assert_eq!(iso.mirrors[2].media_type.as_deref(), Some("torrent"));
+
+        assert_eq!(info.files[1].length, None);
+        assert_eq!(info.files[1].index, 2);
+    }
+
+    #[test]
+    fn parses_metalink3() {
+        let xml = r#"<metalink version="3.0" xmlns="http://www.metalinker.org/">
+  <files>
+    <file name="tool.tar.gz">
+      <size>2048</size>
+      <verification>
+        <hash type="md5">900150983cd24fb0d6963f7d28e17f72</hash>
+        <hash type="sha1">a9993e364706816aba3e25717850c26c9cd0d89d</hash>
+      </verification>
+      <resources>
+        <url type="http" location="us" preference="100">http://us.example.com/tool.tar.gz</url>
+        <url type="bittorrent">http://example.com/tool.torrent</url>
+      </resources>
+    </file>
+  </files>
+</metalink>"#;
+
+        let info = parse_metalink(xml).unwrap();
+        let file = &info.files[0];
+        assert_eq!(file.name, "tool.tar.gz");
+        assert_eq!(
+            file.hashes
+                .iter()
+                .map(|h| h.algorithm.as_str())
+                .collect::<Vec<_>>(),
+            vec!["md5", "sha-1"]
+        );
+        assert_eq!(file.mirrors[0].media_type, None);
+        assert_eq!(file.mirrors[1].media_type.as_deref(), Some("bittorrent"));
+    }
+
+    #[test]
+    fn selects_files_by_index() {
+        let xml = r#"<metalink xmlns="urn:ietf:params:xml:ns:metalink">
+  <file name="a"/><file name="b"/><file name="c"/><file name="d"/>
+</metalink>"#;
+        let info = parse_metalink(xml).unwrap();
+        let names = |spec: Option<&str>| -> Vec<String> {
+            select_files(&info, spec)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names(None), vec!["a", "b", "c", "d"]);
+        assert_eq!(names(Some("4, 1-2,2")), vec!["a", "b", "d"]);
+        assert!(select_files(&info, Some("0")).is_err());
+        assert!(select_files(&info, Some("3-5")).is_err());
+        assert!(select_files(&info, Some("x")).is_err());
+
+        assert!(is_metalink_path("/tmp/ubuntu.META4"));
+        assert!(is_metalink_path("mirror.metalink"));
+        assert!(!is_metalink_path("/tmp/ubuntu.torrent"));
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        assert!(parse_metalink("not xml").is_err());
+        assert!(parse_metalink("<rss><file name=\"a\"/></rss>").is_err());
+        assert!(parse_metalink("<metalink xmlns=\"urn:ietf:params:xml:ns:metalink\"/>").is_err());
+        assert!(parse_metalink(
+            "<!DOCTYPE metalink [<!ENTITY x SYSTEM \"file:///etc/passwd\">]><metalink><file name=\"&x;\"/></metalink>"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! 持久化数据的版本迁移
+//! 磁盘上的 JSON 以 `{ "version": N, "data": ... }` 信封保存，读取时按版本号依次执行迁移链，
+//! 最终得到当前版本的结构后再反序列化。没有信封的旧文件按版本 1 处理。
+//! 任务数据库中每行记录的版本由 `PRAGMA user_version` 记录，迁移链与 JSON 文件共用。
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::aria2::options::split_headers;
+use crate::core::error::{AppError, AppResult};
+
+/// 任务记录的当前版本
+pub const TASK_RECORD_VERSION: u32 = 2;
+/// 配置文件的当前版本
+pub const CONFIG_VERSION: u32 = 2;
+
+/// 将数据从 `to - 1` 版本升级到 `to` 版本
+pub struct Migration {
+    pub to: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut Value) -> AppResult<()>,
+}
+
+/// 单条任务记录的迁移链
+pub const TASK_MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    description: "长度与速度改为整数，headers 拆分为列表",
+    apply: task_v2_numeric_fields,
+}];
+
+/// 配置文件的迁移链
+pub const CONFIG_MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    description: "配置改用版本信封保存",
+    apply: config_v2_envelope,
+}];
+
+/// 拆开信封，返回 (版本, 数据)。
+/// 无信封时从对象自身的 `version` 字段推断（配置文件曾内嵌版本号），都没有则视为版本 1。
+pub fn open_envelope(raw: Value) -> (u32, Value) {
+    if let Value::Object(mut map) = raw {
+        let version = map.get("version").and_then(Value::as_u64);
+        if let (Some(version), true) = (version, map.contains_key("data") && map.len() == 2) {
+            let data = map.remove("data").unwrap_or(Value::Null);
+            return (version as u32, data);
+        }
+        return (version.unwrap_or(1) as u32, Value::Object(map));
+    }
+    (1, raw)
+}
+
+/// 以当前版本封装数据
+pub fn seal_envelope<T: Serialize>(version: u32, data: &T) -> AppResult<Value> {
+    Ok(json!({ "version": version, "data": serde_json::to_value(data)? }))
+}
+
+/// 从 `from` 版本开始依次执行迁移，返回最终版本。
+/// 数据版本高于当前程序（降级使用）时不做修改，按现有结构尽量读取。
+pub fn migrate(value: &mut Value, from: u32, chain: &[Migration], scope: &str) -> AppResult<u32> {
+    let latest = chain.last().map(|m| m.to).unwrap_or(1);
+    if from > latest {
+        crate::app_warn!(
+            "Core::Migration",
+            "data_version_newer_than_app",
+            json!({ "scope": scope, "version": from, "supported": latest })
+        );
+        return Ok(from);
+    }
+
+    let mut version = from;
+    for migration in chain.iter().filter(|m| m.to > from) {
+        if migration.to != version + 1 {
+            return Err(AppError::config(format!(
+                "{scope} 迁移链不连续: {version} -> {}",
+                migration.to
+            )));
+        }
+        (migration.apply)(value)?;
+        crate::app_info!(
+            "Core::Migration",
+            "migration_applied",
+            json!({ "scope": scope, "to": migration.to, "description": migration.description })
+        );
+        version = migration.to;
+    }
+    Ok(version)
+}
+
+/// 对数组中的每条记录执行迁移（旧版 tasks.json 的 data 为任务数组）
+pub fn migrate_records(
+    records: &mut Value,
+    from: u32,
+    chain: &[Migration],
+    scope: &str,
+) -> AppResult<u32> {
+    let Value::Array(items) = records else {
+        return Err(AppError::config(format!("{scope} 数据不是数组")));
+    };
+    let latest = chain.last().map(|m| m.to).unwrap_or(1);
+    for item in items.iter_mut() {
+        migrate(item, from, chain, scope)?;
+    }
+    Ok(from.max(latest))
+}
+
+/// 旧版以字符串保存长度与速度（早期甚至写入过 "0 B/s" 这样的格式化文本），
+/// headers 曾是以分号分隔的单个字符串
+fn task_v2_numeric_fields(record: &mut Value) -> AppResult<()> {
+    let Value::Object(map) = record else {
+        return Err(AppError::config("任务记录不是对象"));
+    };
+
+    for key in ["total_length", "completed_length", "download_speed"] {
+        let number = match map.get(key) {
+            None | Some(Value::Null) => Some(0),
+            Some(Value::Number(n)) => n.as_u64(),
+            Some(Value::String(s)) => parse_formatted_bytes(s),
+            Some(_) => None,
+        };
+        let number = number.unwrap_or_else(|| {
+            crate::app_warn!(
+                "Core::Migration",
+                "task_field_zeroed",
+                json!({ "gid": map.get("gid"), "field": key, "value": map.get(key) })
+            );
+            0
+        });
+        map.insert(key.to_string(), json!(number));
+    }
+
+    if let Some(Value::String(raw)) = map.get("headers") {
+        let headers = split_headers(raw);
+        map.insert("headers".to_string(), json!(headers));
+    }
+
+    Ok(())
+}
+
+/// 解析旧版写入的大小文本：纯数字按字节处理，也接受 "1.5 MiB"、"512 KB/s" 这类格式化结果
+/// （单位按 1024 进制，与前端格式化保持一致）
+fn parse_formatted_bytes(raw: &str) -> Option<u64> {
+    let trimmed = raw.trim();
+    let trimmed = trimmed.strip_suffix("/s").unwrap_or(trimmed).trim_end();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let exponent = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" | "byte" | "bytes" => 0,
+        "k" | "kb" | "kib" => 1,
+        "m" | "mb" | "mib" => 2,
+        "g" | "gb" | "gib" => 3,
+        "t" | "tb" | "tib" => 4,
+        _ => return None,
+    };
+
+    if exponent == 0 {
+        return number.parse::<u64>().ok();
+    }
+    let value = number.parse::<f64>().ok()?;
+    if !value.is_finite() || value < 0.0 {
+        return None;
+    }
+    Some((value * 1024f64.powi(exponent)).round() as u64)
+}
+
+/// 内容不变，仅同步内嵌的版本号（信封由保存时写入）
+fn config_v2_envelope(config: &mut Value) -> AppResult<()> {
+    if let Value::Object(map) = config {
+        map.insert("version".to_string(), json!(2));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::AppConfig;
+    use crate::core::store::PersistedTask;
+
+    const TASKS_V1: &str = include_str!("../../tests/fixtures/tasks_v1.json");
+    const CONFIG_V1: &str = include_str!("../../tests/fixtures/mua_config_v1.json");
+    const CONFIG_V0: &str = include_str!("../../tests/fixtures/mua_config_v0.json");
+
+    fn load_tasks(raw: &str) -> Vec<PersistedTask> {
+        let (version, mut data) = open_envelope(serde_json::from_str(raw).unwrap());
+        let version = migrate_records(&mut data, version, TASK_MIGRATIONS, "tasks").unwrap();
+        assert_eq!(version, TASK_RECORD_VERSION);
+        serde_json::from_value(data).unwrap()
+    }
+
+    fn load_config(raw: &str) -> AppConfig {
+        let (version, mut data) = open_envelope(serde_json::from_str(raw).unwrap());
+        let version = migrate(&mut data, version, CONFIG_MIGRATIONS, "config").unwrap();
+        assert_eq!(version, CONFIG_VERSION);
+        serde_json::from_value(data).unwrap()
+    }
+
+    #[test]
+    fn migrates_v1_task_file() {
+        let tasks = load_tasks(TASKS_V1);
+        assert_eq!(tasks.len(), 3);
+
+        let done = tasks.iter().find(|t| t.gid == "2089b05ecca3d829").unwrap();
+        assert_eq!(done.total_length, 1_048_576);
+        assert_eq!(done.completed_length, 1_048_576);
+        assert_eq!(done.download_speed, 0);
+        assert_eq!(done.headers, vec!["Cookie: a=1", "X-Token: abc"]);
+        // 缺失的后续字段取默认值
+        assert_eq!(done.backend_id, "default");
+        assert_eq!(done.retry_attempts, 0);
+
+        let formatted = tasks.iter().find(|t| t.gid == "d270c8a6b7e2a1f0").unwrap();
+        assert_eq!(formatted.download_speed, 0);
+        assert_eq!(formatted.headers, vec!["Referer: https://example.com"]);
+    }
+
+    #[test]
+    fn current_task_envelope_round_trips() {
+        let tasks = load_tasks(TASKS_V1);
+        let sealed = seal_envelope(TASK_RECORD_VERSION, &tasks).unwrap();
+        let reloaded = load_tasks(&sealed.to_string());
+        assert_eq!(reloaded, tasks);
+    }
+
+    #[test]
+    fn migrates_v1_and_unversioned_config() {
+        let config = load_config(CONFIG_V1);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.rpc_port, 6801);
+        assert_eq!(config.max_concurrent_downloads, 5);
+
+        let config = load_config(CONFIG_V0);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(!config.close_to_tray);
+    }
+
+    #[test]
+    fn formatted_sizes_are_parsed_back_to_bytes() {
+        let mut record = json!({
+            "gid": "a",
+            "total_length": "1.5 MiB",
+            "completed_length": "512 KB",
+            "download_speed": "2.00 KB/s",
+        });
+        task_v2_numeric_fields(&mut record).unwrap();
+        assert_eq!(record["total_length"], 1_572_864);
+        assert_eq!(record["completed_length"], 524_288);
+        assert_eq!(record["download_speed"], 2_048);
+
+        let mut record = json!({ "gid": "b", "total_length": "about 2 GB", "completed_length": -1 });
+        task_v2_numeric_fields(&mut record).unwrap();
+        assert_eq!(record["total_length"], 0);
+        assert_eq!(record["completed_length"], 0);
+        assert_eq!(record["download_speed"], 0);
+    }
+
+    #[test]
+    fn newer_data_is_left_untouched() {
+        let mut value = json!({ "total_length": "12" });
+        let version = migrate(&mut value, 9, TASK_MIGRATIONS, "tasks").unwrap();
+        assert_eq!(version, 9);
+        assert_eq!(value["total_length"], "12");
+    }
+}
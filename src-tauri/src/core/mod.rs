@@ -1,10 +1,23 @@
+pub mod bandwidth;
 pub mod boot;
+pub mod checksum;
 pub mod commands;
+pub mod completion;
 pub mod config;
 pub mod events;
 pub mod error;
 pub mod logging;
+pub mod magnet;
+pub mod metalink;
+pub mod migrations;
+pub mod peers;
+pub mod recovery;
+pub mod retry;
+pub mod rules;
+pub mod stats;
 pub mod store;
 pub mod sync;
+pub mod task_db;
+pub mod timer;
 pub mod types;
 pub mod torrent;
@@ -0,0 +1,227 @@
+//! 任务连接详情
+//! 将 aria2.getPeers / aria2.getServers 的原始字符串结果转换为前端可直接展示的结构。
+
+use crate::aria2::client::{Aria2FileServers, Aria2Peer};
+use serde::Serialize;
+
+/// 单个 BT 对等节点
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+    pub ip: String,
+    pub port: u16,
+    /// 由 peerId 推断的客户端名称，无法识别时为原始前缀
+    pub client: String,
+    pub download_speed: u64,
+    pub upload_speed: u64,
+    pub seeder: bool,
+    pub am_choking: bool,
+    pub peer_choking: bool,
+    /// 对方已拥有的分片比例（0.0 - 100.0）
+    pub progress: f64,
+}
+
+/// 单个 HTTP/FTP 连接
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub file_index: usize,
+    pub uri: String,
+    pub current_uri: String,
+    pub download_speed: u64,
+}
+
+/// Azureus 风格 peerId（`-XX1234-`）的常见客户端代号
+const AZUREUS_CLIENTS: &[(&str, &str)] = &[
+    ("qB", "qBittorrent"),
+    ("TR", "Transmission"),
+    ("UT", "µTorrent"),
+    ("UM", "µTorrent Mac"),
+    ("LT", "libtorrent"),
+    ("lt", "libTorrent"),
+    ("DE", "Deluge"),
+    ("AZ", "Vuze"),
+    ("BC", "BitComet"),
+    ("BI", "BiglyBT"),
+    ("XL", "Xunlei"),
+    ("SD", "Thunder"),
+    ("FD", "Free Download Manager"),
+    ("KT", "KTorrent"),
+    ("BT", "BitTorrent"),
+    ("WW", "WebTorrent"),
+];
+
+fn parse_flag(raw: &str) -> bool {
+    raw == "true"
+}
+
+fn parse_num(raw: &str) -> u64 {
+    raw.parse().unwrap_or(0)
+}
+
+/// 根据 peerId 推断客户端名称
+fn client_from_peer_id(peer_id: &str) -> String {
+    let bytes = urlencoding::decode_binary(peer_id.as_bytes());
+    let id: String = bytes
+        .iter()
+        .take(20)
+        .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+        .collect();
+
+    if let Some(rest) = id.strip_prefix("A2-") {
+        // aria2: A2-<major>-<minor>-<patch>-
+        let version: Vec<&str> = rest.split('-').filter(|s| !s.is_empty()).take(3).collect();
+        return format!("aria2 {}", version.join("."));
+    }
+
+    if id.len() >= 8 && id.starts_with('-') && id.as_bytes()[7] == b'-' {
+        let code = &id[1..3];
+        let version = &id[3..7];
+        if let Some((_, name)) = AZUREUS_CLIENTS.iter().find(|(c, _)| *c == code) {
+            let version: Vec<String> = version
+                .trim_end_matches('0')
+                .chars()
+                .map(|c| c.to_string())
+                .collect();
+            return if version.is_empty() {
+                name.to_string()
+            } else {
+                format!("{} {}", name, version.join("."))
+            };
+        }
+        return id[..8].to_string();
+    }
+
+    id.chars().take(8).collect()
+}
+
+/// 根据十六进制 bitfield 计算分片完成比例。
+/// bitfield 末尾存在补齐位，已知分片总数时以其为分母。
+fn bitfield_progress(bitfield: &str, num_pieces: Option<u64>) -> f64 {
+    let have: u64 = bitfield
+        .chars()
+        .filter_map(|c| c.to_digit(16))
+        .map(|d| d.count_ones() as u64)
+        .sum();
+    let total = num_pieces
+        .filter(|n| *n > 0)
+        .unwrap_or(bitfield.len() as u64 * 4);
+
+    if total == 0 {
+        return 0.0;
+    }
+    (have.min(total) as f64 / total as f64) * 100.0
+}
+
+impl PeerInfo {
+    pub fn from_aria2(peer: &Aria2Peer, num_pieces: Option<u64>) -> Self {
+        Self {
+            ip: peer.ip.clone(),
+            port: peer.port.parse().unwrap_or(0),
+            client: client_from_peer_id(&peer.peer_id),
+            download_speed: parse_num(&peer.download_speed),
+            upload_speed: parse_num(&peer.upload_speed),
+            seeder: parse_flag(&peer.seeder),
+            am_choking: parse_flag(&peer.am_choking),
+            peer_choking: parse_flag(&peer.peer_choking),
+            progress: bitfield_progress(&peer.bitfield, num_pieces),
+        }
+    }
+}
+
+/// 按文件展开连接列表
+pub fn flatten_servers(files: &[Aria2FileServers]) -> Vec<ServerInfo> {
+    files
+        .iter()
+        .flat_map(|file| {
+            let file_index = file.index.parse().unwrap_or(0);
+            file.servers.iter().map(move |server| ServerInfo {
+                file_index,
+                uri: server.uri.clone(),
+                current_uri: server.current_uri.clone(),
+                download_speed: parse_num(&server.download_speed),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::client::Aria2Server;
+
+    #[test]
+    fn recognizes_azureus_style_peer_ids() {
+        assert_eq!(
+            client_from_peer_id("%2DqB4630%2D%A1%B2%C3%D4%E5%F6%07%18%29%3A%4B%5C"),
+            "qBittorrent 4.6.3"
+        );
+        assert_eq!(
+            client_from_peer_id("-TR3000-abcdefghijkl"),
+            "Transmission 3"
+        );
+        // 未收录的代号保留原始前缀
+        assert_eq!(client_from_peer_id("-ZZ1200-abcdefghijkl"), "-ZZ1200-");
+    }
+
+    #[test]
+    fn recognizes_aria2_peer_ids() {
+        assert_eq!(
+            client_from_peer_id("A2-1-37-0-%A1%B2%C3%D4%E5%F6%07%18%29%3A"),
+            "aria2 1.37.0"
+        );
+    }
+
+    #[test]
+    fn unknown_peer_ids_fall_back_to_printable_prefix() {
+        assert_eq!(
+            client_from_peer_id("M7-2-2--%00%01%02%03abcdefgh"),
+            "M7-2-2--"
+        );
+        assert_eq!(client_from_peer_id("%00%01abcdefghijklmnopqr"), "..abcdef");
+        assert_eq!(client_from_peer_id(""), "");
+    }
+
+    #[test]
+    fn bitfield_padding_is_ignored_when_piece_count_is_known() {
+        // 9 个分片占用 2 个字节，后 7 位为补齐位
+        assert_eq!(bitfield_progress("ff80", Some(9)), 100.0);
+        assert!((bitfield_progress("ff00", Some(9)) - 800.0 / 9.0).abs() < 1e-9);
+        // 补齐位被错误置位时不超过 100%
+        assert_eq!(bitfield_progress("ffff", Some(9)), 100.0);
+        // 分片总数未知时按 bitfield 长度计算
+        assert_eq!(bitfield_progress("ff00", None), 50.0);
+        assert_eq!(bitfield_progress("", None), 0.0);
+    }
+
+    #[test]
+    fn flattens_servers_per_file() {
+        let server = |uri: &str, speed: &str| Aria2Server {
+            uri: uri.to_string(),
+            current_uri: format!("{}?mirror=1", uri),
+            download_speed: speed.to_string(),
+        };
+        let files = vec![
+            Aria2FileServers {
+                index: "1".to_string(),
+                servers: vec![
+                    server("https://a.example.com/f", "1024"),
+                    server("https://b.example.com/f", "x"),
+                ],
+            },
+            Aria2FileServers {
+                index: "2".to_string(),
+                servers: vec![server("https://a.example.com/g", "10")],
+            },
+        ];
+
+        let servers = flatten_servers(&files);
+        assert_eq!(servers.len(), 3);
+        assert_eq!(servers[0].file_index, 1);
+        assert_eq!(servers[0].download_speed, 1024);
+        assert_eq!(servers[0].current_uri, "https://a.example.com/f?mirror=1");
+        assert_eq!(servers[1].download_speed, 0);
+        assert_eq!(servers[2].file_index, 2);
+        assert_eq!(servers[2].uri, "https://a.example.com/g");
+    }
+}
@@ -0,0 +1,227 @@
+//! 持久化文件的备份与损坏恢复
+//! - 写入前保留最近 N 份滚动备份（`<name>.bak.1` 最新）
+//! - 无法解析的文件改名隔离（`<name>.corrupt-<时间>`），不会被后续保存覆盖
+//! - 恢复结果记录为 `RecoveryReport`，通过事件与命令告知前端
+
+use chrono::Local;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::core::error::AppResult;
+
+/// 每个文件保留的滚动备份数量
+pub const BACKUP_COUNT: usize = 3;
+
+/// 一次恢复的结果
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryReport {
+    /// 受影响的文件名，如 `mua_config.json`
+    pub file: String,
+    /// 损坏文件被隔离后的路径
+    pub quarantined: Option<String>,
+    /// 用于恢复的备份路径
+    pub restored_from: Option<String>,
+    /// 成功恢复的记录数（配置文件为字段数）
+    pub recovered: usize,
+    /// 无法恢复而丢弃的记录数
+    pub lost: usize,
+    /// 文件内容完全无法解析（如不是 JSON），其中的数据全部丢失
+    pub whole_file_lost: bool,
+}
+
+impl RecoveryReport {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            file: file_name(path),
+            quarantined: None,
+            restored_from: None,
+            recovered: 0,
+            lost: 0,
+            whole_file_lost: false,
+        }
+    }
+}
+
+/// 启动过程中产生的恢复报告，等待前端读取
+#[derive(Default)]
+pub struct RecoveryLog {
+    reports: Mutex<Vec<RecoveryReport>>,
+}
+
+impl RecoveryLog {
+    pub fn push(&self, report: RecoveryReport) {
+        crate::app_warn!(
+            "Core::Recovery",
+            "file_recovered",
+            json!({
+                "file": report.file,
+                "quarantined": report.quarantined,
+                "restored_from": report.restored_from,
+                "recovered": report.recovered,
+                "lost": report.lost,
+            })
+        );
+        if let Ok(mut reports) = self.reports.lock() {
+            reports.push(report);
+        }
+    }
+
+    pub fn all(&self) -> Vec<RecoveryReport> {
+        self.reports.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// 第 `index` 份备份的路径（从 1 开始，1 为最新）
+pub fn backup_path(path: &Path, index: usize) -> PathBuf {
+    with_suffix(path, &format!(".bak.{index}"))
+}
+
+/// 现有备份的路径，由新到旧
+pub fn existing_backups(path: &Path) -> Vec<PathBuf> {
+    (1..=BACKUP_COUNT)
+        .map(|i| backup_path(path, i))
+        .filter(|p| p.exists())
+        .collect()
+}
+
+/// 将现有备份依次后移一位并丢弃最旧的一份，为新的 `.bak.1` 腾出位置
+pub fn shift_backups(path: &Path) -> AppResult<()> {
+    let oldest = backup_path(path, BACKUP_COUNT);
+    if oldest.exists() {
+        std::fs::remove_file(&oldest)?;
+    }
+    for i in (1..BACKUP_COUNT).rev() {
+        let from = backup_path(path, i);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(path, i + 1))?;
+        }
+    }
+    Ok(())
+}
+
+/// 复制当前文件为最新备份；与最新备份内容相同时跳过，避免连续保存挤掉旧备份
+pub fn rotate_backups(path: &Path) -> AppResult<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let latest = backup_path(path, 1);
+    if latest.exists() && std::fs::read(&latest)? == std::fs::read(path)? {
+        return Ok(());
+    }
+    shift_backups(path)?;
+    std::fs::copy(path, &latest)?;
+    Ok(())
+}
+
+/// 将损坏的文件改名隔离，返回隔离后的路径
+pub fn quarantine(path: &Path) -> AppResult<PathBuf> {
+    let target = with_suffix(path, &format!(".corrupt-{}", Local::now().format("%Y%m%d-%H%M%S")));
+    std::fs::rename(path, &target)?;
+    crate::app_warn!(
+        "Core::Recovery",
+        "file_quarantined",
+        json!({ "path": path.to_string_lossy(), "target": target.to_string_lossy() })
+    );
+    Ok(target)
+}
+
+/// 从损坏的 JSON 数组中尽量提取完整的顶层对象（例如写入中断导致的截断文件）。
+/// 逐个扫描 `{...}`，无法解析的对象跳过；返回 (可解析的对象, 被丢弃的对象数)。
+pub fn salvage_array_records(content: &str) -> (Vec<Value>, usize) {
+    let mut records = Vec::new();
+    let mut lost = 0;
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = None;
+
+    for (idx, ch) in content.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' => {
+                if depth == 0 {
+                    start = Some(idx);
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(begin) = start.take() {
+                        match serde_json::from_str::<Value>(&content[begin..=idx]) {
+                            Ok(value) => records.push(value),
+                            Err(_) => lost += 1,
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 末尾未闭合的对象视为丢失
+    if start.is_some() {
+        lost += 1;
+    }
+
+    (records, lost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::temp_dir;
+
+    #[test]
+    fn salvages_complete_objects_from_truncated_array() {
+        let content = r#"[{"gid":"a","name":"x}{"},{"gid":"b"},{"gid":"c","na"#;
+        let (records, lost) = salvage_array_records(content);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["name"], "x}{");
+        assert_eq!(records[1]["gid"], "b");
+        assert_eq!(lost, 1);
+    }
+
+    #[test]
+    fn rotation_keeps_bounded_distinct_backups() {
+        let dir = temp_dir("recovery");
+        let path = dir.join("mua_config.json");
+
+        for i in 0..5 {
+            std::fs::write(&path, format!("{{\"v\":{i}}}")).unwrap();
+            rotate_backups(&path).unwrap();
+            // 内容未变化时不产生新备份
+            rotate_backups(&path).unwrap();
+        }
+
+        let backups = existing_backups(&path);
+        assert_eq!(backups.len(), BACKUP_COUNT);
+        assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), "{\"v\":4}");
+        assert_eq!(std::fs::read_to_string(&backups[2]).unwrap(), "{\"v\":2}");
+    }
+}
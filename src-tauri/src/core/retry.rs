@@ -0,0 +1,215 @@
+//! 失败任务自动重试
+//! 同步时按 `RetryPolicy` 为可重试的失败任务安排下一次重试时间，
+//! 后台循环在到期后通过智能恢复重新提交任务，并累计重试次数。
+
+use chrono::{DateTime, Local};
+use serde_json::json;
+
+use crate::aria2::backend::BackendRegistry;
+use crate::core::commands::smart_resume_task;
+use crate::core::config::RetryPolicy;
+use crate::core::store::{PersistedTask, TaskStore};
+use crate::core::types::TaskState;
+
+/// 为任务安排或取消下一次重试，返回 true 表示字段有变化。
+/// 已安排的重试时间保持不变，避免每轮同步都推迟。
+pub fn schedule(task: &mut PersistedTask, policy: &RetryPolicy, now: DateTime<Local>) -> bool {
+    let retryable = task.state == TaskState::Error
+        && task
+            .error_kind
+            .is_some_and(|kind| policy.allows(kind, task.retry_attempts));
+
+    let next = match (retryable, &task.next_retry_at) {
+        (false, _) => None,
+        (true, Some(existing)) => Some(existing.clone()),
+        (true, None) => {
+            let delay = chrono::Duration::from_std(policy.delay_for(task.retry_attempts))
+                .unwrap_or_else(|_| chrono::Duration::zero());
+            Some((now + delay).to_rfc3339())
+        }
+    };
+
+    if task.next_retry_at != next {
+        task.next_retry_at = next;
+        return true;
+    }
+    false
+}
+
+fn is_due(task: &PersistedTask, now: DateTime<Local>) -> bool {
+    task.state == TaskState::Error
+        && task
+            .next_retry_at
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .is_some_and(|at| at <= now)
+}
+
+/// 执行所有已到期的重试，返回重新提交后的新 GID
+pub async fn run_due_retries(
+    store: &TaskStore,
+    registry: &BackendRegistry,
+    policy: &RetryPolicy,
+    now: DateTime<Local>,
+) -> Vec<String> {
+    if !policy.enabled {
+        return Vec::new();
+    }
+
+    let due: Vec<PersistedTask> = store
+        .get_all()
+        .into_iter()
+        .filter(|task| is_due(task, now))
+        .collect();
+
+    let mut resubmitted = Vec::new();
+    for task in due {
+        let attempt = task.retry_attempts.saturating_add(1);
+        match smart_resume_task(store, registry, task.gid.clone()).await {
+            Ok(new_gid) => {
+                crate::app_info!(
+                    "Core::Retry",
+                    "retry_submitted",
+                    json!({
+                        "old_gid": task.gid,
+                        "new_gid": new_gid,
+                        "attempt": attempt,
+                        "error_kind": task.error_kind,
+                    })
+                );
+                store.update_task(&new_gid, |t| t.retry_attempts = attempt);
+                resubmitted.push(new_gid);
+            }
+            Err(error) => {
+                // 提交失败同样计入次数，下一轮同步会按新的次数重新安排
+                crate::app_warn!(
+                    "Core::Retry",
+                    "retry_submit_failed",
+                    json!({ "gid": task.gid, "attempt": attempt, "error": error.to_string() })
+                );
+                store.update_task(&task.gid, |t| {
+                    t.retry_attempts = attempt;
+                    t.next_retry_at = None;
+                });
+            }
+        }
+    }
+
+    resubmitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::{persisted_task, MockAria2};
+    use crate::core::types::TaskErrorKind;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            enabled: true,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn failed(gid: &str, kind: TaskErrorKind, attempts: u32) -> PersistedTask {
+        let mut task = persisted_task(gid, TaskState::Error);
+        task.error_kind = Some(kind);
+        task.retry_attempts = attempts;
+        task
+    }
+
+    #[test]
+    fn schedules_retryable_failures_with_backoff() {
+        let policy = policy();
+        let now = Local::now();
+
+        let mut first = failed("a", TaskErrorKind::Network, 0);
+        assert!(schedule(&mut first, &policy, now));
+        let expected = now + chrono::Duration::seconds(policy.base_delay_secs as i64);
+        assert_eq!(first.next_retry_at, Some(expected.to_rfc3339()));
+
+        // 已安排的时间不随后续同步推迟
+        assert!(!schedule(&mut first, &policy, now + chrono::Duration::seconds(5)));
+
+        let mut second = failed("b", TaskErrorKind::Network, 2);
+        schedule(&mut second, &policy, now);
+        let expected = now + chrono::Duration::seconds(policy.base_delay_secs as i64 * 4);
+        assert_eq!(second.next_retry_at, Some(expected.to_rfc3339()));
+    }
+
+    #[test]
+    fn skips_non_retryable_and_exhausted_tasks() {
+        let policy = policy();
+        let now = Local::now();
+
+        // 默认关闭，需用户在设置中开启
+        assert!(!schedule(&mut failed("x", TaskErrorKind::Network, 0), &RetryPolicy::default(), now));
+
+        let mut disk_full = failed("a", TaskErrorKind::DiskFull, 0);
+        assert!(!schedule(&mut disk_full, &policy, now));
+        assert_eq!(disk_full.next_retry_at, None);
+
+        let mut exhausted = failed("b", TaskErrorKind::Network, policy.max_attempts);
+        exhausted.next_retry_at = Some(now.to_rfc3339());
+        assert!(schedule(&mut exhausted, &policy, now));
+        assert_eq!(exhausted.next_retry_at, None);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy {
+            base_delay_secs: 10,
+            max_delay_secs: 60,
+            ..policy()
+        };
+        assert_eq!(policy.delay_for(0).as_secs(), 10);
+        assert_eq!(policy.delay_for(2).as_secs(), 40);
+        assert_eq!(policy.delay_for(3).as_secs(), 60);
+        assert_eq!(policy.delay_for(100).as_secs(), 60);
+    }
+
+    #[tokio::test]
+    async fn due_retry_resubmits_and_counts_attempt() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        let now = Local::now();
+
+        let mut due = failed("due", TaskErrorKind::Timeout, 1);
+        due.next_retry_at = Some((now - chrono::Duration::seconds(1)).to_rfc3339());
+        store.add_task(due);
+        let mut later = failed("later", TaskErrorKind::Timeout, 0);
+        later.next_retry_at = Some((now + chrono::Duration::seconds(30)).to_rfc3339());
+        store.add_task(later);
+
+        let resubmitted = run_due_retries(&store, &backends, &policy(), now).await;
+
+        assert_eq!(resubmitted.len(), 1);
+        assert!(store.get_task("due").is_none());
+        let retried = store.get_task(&resubmitted[0]).unwrap();
+        assert_eq!(retried.state, TaskState::Waiting);
+        assert_eq!(retried.retry_attempts, 2);
+        assert_eq!(retried.next_retry_at, None);
+        assert!(store.get_task("later").is_some());
+    }
+
+    #[tokio::test]
+    async fn failed_resubmission_still_counts_attempt() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.fail_next("aria2.addUri", "internal error");
+        let store = TaskStore::new();
+        let now = Local::now();
+
+        let mut due = failed("due", TaskErrorKind::Network, 0);
+        due.next_retry_at = Some(now.to_rfc3339());
+        store.add_task(due);
+
+        let resubmitted = run_due_retries(&store, &backends, &policy(), now).await;
+
+        assert!(resubmitted.is_empty());
+        let task = store.get_task("due").unwrap();
+        assert_eq!(task.retry_attempts, 1);
+        assert_eq!(task.next_retry_at, None);
+    }
+}
@@ -0,0 +1,447 @@
+//! 下载规则引擎
+//! 新建任务时按 `AppConfig.rules` 的顺序逐条匹配主机、扩展名、MIME 类型与种子名称，
+//! 第一条命中的规则为任务补全未指定的参数；用户在添加时显式填写的值始终优先。
+
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::aria2::options::split_headers;
+use crate::core::config::{AppConfig, DownloadRule};
+use crate::core::error::{AppError, AppResult};
+use crate::core::types::DownloadConfig;
+use crate::utils;
+
+/// MIME 探测（HEAD 请求）的超时时间，避免拖慢添加任务
+const MIME_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 用于匹配规则的任务特征
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleSubject {
+    pub host: String,
+    /// 小写扩展名，不含点
+    pub extension: String,
+    pub mime_type: Option<String>,
+    pub torrent_name: Option<String>,
+}
+
+impl RuleSubject {
+    /// 从下载配置提取特征（不含 MIME 类型，需要时由 `probe_mime` 补充）
+    pub fn from_download(cfg: &DownloadConfig) -> Self {
+        let url = cfg.urls.first().map(String::as_str).unwrap_or_default();
+
+        let torrent_name = match cfg.torrent_config {
+            Some(ref torrent) => crate::core::torrent::parse_torrent_file(&torrent.path)
+                .map(|info| info.name)
+                .ok(),
+            None if url.starts_with("magnet:") => {
+                Some(utils::deduce_filename(cfg.filename.clone(), &cfg.urls))
+            }
+            None => None,
+        };
+
+        // Metalink 以其中第一个文件的名称参与扩展名匹配
+        let metalink_name = cfg
+            .metalink_config
+            .as_ref()
+            .and_then(|m| crate::core::metalink::parse_metalink_file(&m.path).ok())
+            .and_then(|info| info.files.into_iter().next())
+            .map(|file| file.name);
+
+        let filename = match (&cfg.torrent_config, &torrent_name, metalink_name) {
+            (Some(_), Some(name), _) => name.clone(),
+            (_, _, Some(name)) => name,
+            _ => utils::deduce_filename(cfg.filename.clone(), &cfg.urls),
+        };
+
+        Self {
+            host: host_of(url),
+            extension: extension_of(&filename),
+            mime_type: None,
+            torrent_name,
+        }
+    }
+}
+
+/// 规则匹配结果
+pub struct RuleEvaluation<'a> {
+    pub subject: RuleSubject,
+    pub rule: Option<&'a DownloadRule>,
+}
+
+/// 提取特征并查找第一条命中的规则；仅当存在 MIME 条件时才探测 MIME 类型
+pub async fn evaluate<'a>(rules: &'a [DownloadRule], cfg: &DownloadConfig) -> RuleEvaluation<'a> {
+    let mut subject = RuleSubject::from_download(cfg);
+
+    let needs_mime = rules.iter().any(|r| r.enabled && !r.mime_types.is_empty());
+    if needs_mime && cfg.torrent_config.is_none() {
+        if let Some(url) = cfg.urls.first() {
+            subject.mime_type = probe_mime(url, cfg).await;
+        }
+    }
+
+    let rule = find_match(rules, &subject);
+    RuleEvaluation { subject, rule }
+}
+
+/// 按顺序返回第一条命中的已启用规则
+pub fn find_match<'a>(
+    rules: &'a [DownloadRule],
+    subject: &RuleSubject,
+) -> Option<&'a DownloadRule> {
+    rules
+        .iter()
+        .find(|rule| rule.enabled && matches(rule, subject))
+}
+
+fn matches(rule: &DownloadRule, subject: &RuleSubject) -> bool {
+    let mut has_condition = false;
+
+    if !rule.hosts.is_empty() {
+        has_condition = true;
+        if !rule.hosts.iter().any(|h| host_matches(h, &subject.host)) {
+            return false;
+        }
+    }
+
+    if !rule.extensions.is_empty() {
+        has_condition = true;
+        let matched = rule.extensions.iter().any(|ext| {
+            let ext = ext.trim().trim_start_matches('.');
+            !ext.is_empty() && ext.eq_ignore_ascii_case(&subject.extension)
+        });
+        if !matched {
+            return false;
+        }
+    }
+
+    if !rule.mime_types.is_empty() {
+        has_condition = true;
+        let Some(ref mime) = subject.mime_type else {
+            return false;
+        };
+        if !rule.mime_types.iter().any(|p| mime_matches(p, mime)) {
+            return false;
+        }
+    }
+
+    if !rule.torrent_name_pattern.trim().is_empty() {
+        has_condition = true;
+        let Some(ref name) = subject.torrent_name else {
+            return false;
+        };
+        match compile_pattern(&rule.torrent_name_pattern) {
+            Ok(re) if re.is_match(name) => {}
+            Ok(_) => return false,
+            Err(e) => {
+                crate::app_warn!(
+                    "Core::Rules",
+                    "invalid_rule_pattern",
+                    json!({ "rule_id": rule.id, "error": e.to_string() })
+                );
+                return false;
+            }
+        }
+    }
+
+    // 没有任何条件的规则不匹配任何任务，避免误配置成“全部命中”
+    has_condition
+}
+
+/// 将规则的动作应用到下载配置：仅补全未指定的字段，请求头按行追加去重
+pub fn apply_rule(mut cfg: DownloadConfig, rule: &DownloadRule) -> DownloadConfig {
+    fn fill(target: &mut Option<String>, value: &str) {
+        let missing = target
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or_default()
+            .is_empty();
+        if missing && !value.trim().is_empty() {
+            *target = Some(value.trim().to_string());
+        }
+    }
+
+    if let Some(ref category) = rule.category {
+        fill(&mut cfg.category, category);
+    }
+    fill(&mut cfg.save_path, &rule.save_dir);
+    fill(&mut cfg.max_download_limit, &rule.max_download_limit);
+    fill(&mut cfg.proxy, &rule.proxy);
+
+    if !rule.headers.is_empty() {
+        let mut headers = cfg
+            .headers
+            .as_deref()
+            .map(split_headers)
+            .unwrap_or_default();
+        for header in rule
+            .headers
+            .iter()
+            .map(|h| h.trim())
+            .filter(|h| !h.is_empty())
+        {
+            if !headers.iter().any(|existing| existing == header) {
+                headers.push(header.to_string());
+            }
+        }
+        cfg.headers = Some(headers.join("\n"));
+    }
+
+    cfg
+}
+
+/// 保存配置前校验规则：ID 唯一、正则可编译、引用的分类存在
+pub fn validate(config: &AppConfig) -> AppResult<()> {
+    for (idx, rule) in config.rules.iter().enumerate() {
+        if rule.id.trim().is_empty() {
+            return Err(AppError::validation(format!(
+                "第 {} 条规则缺少 ID",
+                idx + 1
+            )));
+        }
+        if config.rules[..idx].iter().any(|r| r.id == rule.id) {
+            return Err(AppError::validation(format!("规则 ID 重复: {}", rule.id)));
+        }
+        if !rule.torrent_name_pattern.trim().is_empty() {
+            compile_pattern(&rule.torrent_name_pattern).map_err(|e| {
+                AppError::validation(format!("规则「{}」的名称正则无效: {}", rule.name, e))
+            })?;
+        }
+        if let Some(ref category) = rule.category {
+            if config.category(category).is_none() {
+                return Err(AppError::validation(format!(
+                    "规则「{}」引用了未知的分类: {}",
+                    rule.name, category
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern.trim())
+        .case_insensitive(true)
+        .build()
+}
+
+/// 通过 HEAD 请求获取 Content-Type（不含参数），失败时返回 None
+pub async fn probe_mime(url: &str, cfg: &DownloadConfig) -> Option<String> {
+    let lower = url.to_lowercase();
+    if !lower.starts_with("http://") && !lower.starts_with("https://") {
+        return None;
+    }
+
+    let mut builder = reqwest::Client::builder().timeout(MIME_PROBE_TIMEOUT);
+    if let Some(proxy) = cfg.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+        builder = builder.proxy(reqwest::Proxy::all(proxy.trim()).ok()?);
+    }
+    let client = builder.build().ok()?;
+
+    let mut request = client.head(url);
+    if let Some(ua) = cfg.user_agent.as_deref().filter(|v| !v.is_empty()) {
+        request = request.header(reqwest::header::USER_AGENT, ua);
+    }
+    if let Some(referer) = cfg.referer.as_deref().filter(|v| !v.is_empty()) {
+        request = request.header(reqwest::header::REFERER, referer);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            crate::app_debug!(
+                "Core::Rules",
+                "mime_probe_failed",
+                json!({ "url": url, "error": e.to_string() })
+            );
+            return None;
+        }
+    };
+
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+}
+
+/// 提取 URL 的主机名（小写，不含用户信息与端口）
+fn host_of(url: &str) -> String {
+    let Some((_, rest)) = url.split_once("://") else {
+        return String::new();
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = if host.starts_with('[') {
+        // IPv6 字面量：[::1]:8080
+        host.split(']')
+            .next()
+            .map(|h| format!("{h}]"))
+            .unwrap_or_default()
+    } else {
+        host.split(':').next().unwrap_or_default().to_string()
+    };
+    host.to_lowercase()
+}
+
+fn extension_of(filename: &str) -> String {
+    std::path::Path::new(filename)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("*.").to_lowercase();
+    if pattern.is_empty() || host.is_empty() {
+        return false;
+    }
+    host == pattern || host.ends_with(&format!(".{pattern}"))
+}
+
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    match pattern.strip_suffix("/*") {
+        Some(kind) => mime.split('/').next() == Some(kind),
+        None => !pattern.is_empty() && pattern == mime,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str) -> DownloadRule {
+        DownloadRule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            hosts: Vec::new(),
+            extensions: Vec::new(),
+            mime_types: Vec::new(),
+            torrent_name_pattern: String::new(),
+            category: None,
+            save_dir: String::new(),
+            max_download_limit: String::new(),
+            proxy: String::new(),
+            headers: Vec::new(),
+        }
+    }
+
+    fn download(url: &str) -> DownloadConfig {
+        DownloadConfig {
+            urls: vec![url.to_string()],
+            ..DownloadConfig::default()
+        }
+    }
+
+    #[test]
+    fn extracts_subject_from_url() {
+        let subject = RuleSubject::from_download(&download(
+            "https://user@CDN.Example.com:8443/a/Movie.MKV?x=1",
+        ));
+        assert_eq!(subject.host, "cdn.example.com");
+        assert_eq!(subject.extension, "mkv");
+        assert_eq!(subject.torrent_name, None);
+
+        let magnet = RuleSubject::from_download(&download(
+            "magnet:?xt=urn:btih:0123456789abcdef0123456789abcdef01234567&dn=Ubuntu%2024.04.iso",
+        ));
+        assert_eq!(magnet.torrent_name.as_deref(), Some("Ubuntu 24.04.iso"));
+        assert_eq!(magnet.extension, "iso");
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let mut by_host = rule("host");
+        by_host.hosts = vec!["example.com".to_string()];
+        let mut by_ext = rule("ext");
+        by_ext.extensions = vec![".mkv".to_string()];
+        let empty = rule("empty");
+        let rules = vec![empty, by_host, by_ext];
+
+        let subject = RuleSubject::from_download(&download("https://cdn.example.com/a.mkv"));
+        assert_eq!(
+            find_match(&rules, &subject).map(|r| r.id.as_str()),
+            Some("host")
+        );
+
+        let subject = RuleSubject::from_download(&download("https://other.org/a.mkv"));
+        assert_eq!(
+            find_match(&rules, &subject).map(|r| r.id.as_str()),
+            Some("ext")
+        );
+
+        // 子域名匹配不应误配 notexample.com
+        let subject = RuleSubject::from_download(&download("https://notexample.com/a.zip"));
+        assert!(find_match(&rules, &subject).is_none());
+    }
+
+    #[test]
+    fn all_conditions_must_match() {
+        let mut video = rule("video");
+        video.hosts = vec!["*.example.com".to_string()];
+        video.mime_types = vec!["video/*".to_string()];
+
+        let mut subject = RuleSubject::from_download(&download("https://cdn.example.com/stream"));
+        assert!(find_match(std::slice::from_ref(&video), &subject).is_none());
+
+        subject.mime_type = Some("video/mp4".to_string());
+        assert!(find_match(std::slice::from_ref(&video), &subject).is_some());
+
+        video.enabled = false;
+        assert!(find_match(std::slice::from_ref(&video), &subject).is_none());
+    }
+
+    #[test]
+    fn torrent_name_pattern_is_case_insensitive() {
+        let mut anime = rule("anime");
+        anime.torrent_name_pattern = r"^\[subs\].*1080p".to_string();
+        let subject = RuleSubject {
+            torrent_name: Some("[SUBS] Show - 01 [1080P]".to_string()),
+            ..RuleSubject::default()
+        };
+        assert!(find_match(std::slice::from_ref(&anime), &subject).is_some());
+    }
+
+    #[test]
+    fn actions_only_fill_missing_fields() {
+        let mut docs = rule("docs");
+        docs.save_dir = "/data/docs".to_string();
+        docs.max_download_limit = "1M".to_string();
+        docs.headers = vec!["Cookie: a=1".to_string(), "X-Token: t".to_string()];
+
+        let mut cfg = download("https://example.com/a.pdf");
+        cfg.max_download_limit = Some("512K".to_string());
+        cfg.headers = Some("X-Token: t".to_string());
+
+        let cfg = apply_rule(cfg, &docs);
+        assert_eq!(cfg.save_path.as_deref(), Some("/data/docs"));
+        assert_eq!(cfg.max_download_limit.as_deref(), Some("512K"));
+        assert_eq!(cfg.headers.as_deref(), Some("X-Token: t\nCookie: a=1"));
+    }
+
+    #[test]
+    fn validation_rejects_bad_rules() {
+        let mut config = AppConfig::default();
+        let mut bad = rule("bad");
+        bad.torrent_name_pattern = "(".to_string();
+        config.rules = vec![bad];
+        assert!(validate(&config).is_err());
+
+        let mut unknown = rule("unknown");
+        unknown.category = Some("missing".to_string());
+        config.rules = vec![unknown];
+        assert!(validate(&config).is_err());
+
+        config.rules = vec![rule("a"), rule("a")];
+        assert!(validate(&config).is_err());
+
+        config.rules = vec![rule("a")];
+        assert!(validate(&config).is_ok());
+    }
+}
@@ -0,0 +1,120 @@
+//! 全局统计
+//! 基于 aria2.getGlobalStat 汇总所有在线后端的速度与任务数量，
+//! 供托盘速度显示与前端状态栏使用。
+
+use crate::aria2::backend::BackendRegistry;
+use crate::aria2::client::{Aria2GlobalStat, DEFAULT_BACKEND_ID};
+use crate::core::error::AppResult;
+use futures::future::join_all;
+use serde::Serialize;
+use serde_json::json;
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalStats {
+    pub download_speed: u64,
+    pub upload_speed: u64,
+    pub num_active: u64,
+    pub num_waiting: u64,
+    pub num_stopped: u64,
+    pub num_stopped_total: u64,
+}
+
+impl GlobalStats {
+    fn accumulate(&mut self, stat: &Aria2GlobalStat) {
+        let parse = |v: &str| v.parse::<u64>().unwrap_or(0);
+        self.download_speed += parse(&stat.download_speed);
+        self.upload_speed += parse(&stat.upload_speed);
+        self.num_active += parse(&stat.num_active);
+        self.num_waiting += parse(&stat.num_waiting);
+        self.num_stopped += parse(&stat.num_stopped);
+        self.num_stopped_total += parse(&stat.num_stopped_total);
+    }
+}
+
+/// 汇总所有后端的全局统计。默认后端不可用时返回错误，其他后端离线则跳过。
+pub async fn fetch_global_stats(registry: &BackendRegistry) -> AppResult<GlobalStats> {
+    let ids = registry.ids();
+    let results = join_all(
+        ids.iter()
+            .map(|id| async move { registry.get(id)?.get_global_stat().await }),
+    )
+    .await;
+
+    let mut stats = GlobalStats::default();
+    for (backend, result) in ids.iter().zip(results) {
+        match result {
+            Ok(stat) => stats.accumulate(&stat),
+            Err(e) if backend == DEFAULT_BACKEND_ID => return Err(e),
+            Err(e) => {
+                crate::app_debug!(
+                    "Core::Stats",
+                    "backend_global_stat_failed",
+                    json!({ "backend": backend, "error": e.to_string() })
+                );
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::backend::HttpBackend;
+    use crate::aria2::mock::{MockAria2, MockTask};
+    use crate::core::config::RemoteBackendConfig;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn sums_stats_across_backends() {
+        let local = MockAria2::start().await;
+        local.insert(MockTask::new("a", "active").with_progress(0, 1000, 100));
+        local.insert(MockTask::new("b", "waiting"));
+        let nas = MockAria2::start().await;
+        nas.insert(MockTask::new("c", "active").with_progress(0, 1000, 50));
+        nas.insert(MockTask::new("d", "complete"));
+
+        let registry = local.registry();
+        nas.register_remote(&registry, "nas");
+
+        let stats = fetch_global_stats(&registry).await.unwrap();
+        assert_eq!(
+            stats,
+            GlobalStats {
+                download_speed: 150,
+                upload_speed: 0,
+                num_active: 2,
+                num_waiting: 1,
+                num_stopped: 1,
+                num_stopped_total: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn offline_remote_is_skipped_but_default_failure_is_fatal() {
+        let local = MockAria2::start().await;
+        local.insert(MockTask::new("a", "active").with_progress(0, 1000, 100));
+
+        let registry = local.registry();
+        let offline = RemoteBackendConfig {
+            id: "nas".to_string(),
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port: 1,
+            ..RemoteBackendConfig::default()
+        };
+        registry.register("nas", Arc::new(HttpBackend::remote("nas", &offline)));
+
+        let stats = fetch_global_stats(&registry).await.unwrap();
+        assert_eq!(stats.download_speed, 100);
+        assert_eq!(stats.num_active, 1);
+
+        let registry = BackendRegistry::new();
+        registry.register(DEFAULT_BACKEND_ID, Arc::new(HttpBackend::local(1, None)));
+        local.register_remote(&registry, "local");
+        assert!(fetch_global_stats(&registry).await.is_err());
+    }
+}
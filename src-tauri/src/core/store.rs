@@ -1,16 +1,21 @@
-use crate::core::types::TaskState;
+use crate::core::config::HistoryRetention;
+use crate::core::error::AppResult;
+use crate::core::migrations;
+use crate::core::recovery::{self, RecoveryLog, RecoveryReport};
+use crate::core::task_db::TaskDb;
+use crate::core::types::{ChecksumStatus, CompletionAction, TaskErrorKind, TaskState};
 use chrono::Local;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Manager};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PersistedTask {
     pub gid: String,
     pub filename: String,
@@ -18,14 +23,23 @@ pub struct PersistedTask {
     pub save_path: String,
     pub added_at: String,
     pub state: TaskState,
-    pub total_length: String,
-    pub completed_length: String,
-    pub download_speed: String,
+    pub total_length: u64,
+    pub completed_length: u64,
+    pub download_speed: u64,
     #[serde(default)]
     pub completed_at: Option<String>,
     // Add other fields we want to persist if Aria2 loses them
     #[serde(default)]
     pub error_message: String,
+    /// aria2 错误码对应的失败分类
+    #[serde(default)]
+    pub error_kind: Option<TaskErrorKind>,
+    /// 已执行的自动重试次数
+    #[serde(default)]
+    pub retry_attempts: u32,
+    /// 下一次自动重试的时间（RFC3339），未安排时为 None
+    #[serde(default)]
+    pub next_retry_at: Option<String>,
     #[serde(default)]
     pub user_agent: String,
     #[serde(default)]
@@ -41,6 +55,40 @@ pub struct PersistedTask {
     pub select_file: Option<String>,
     #[serde(default)]
     pub trackers: Option<String>,
+    /// 任务所属的 aria2 后端
+    #[serde(default = "default_backend_id")]
+    pub backend_id: String,
+    /// 在 aria2 等待队列中的位置（从 0 开始），不在队列中时为 None
+    #[serde(default)]
+    pub queue_position: Option<u32>,
+    /// 所属分类 ID
+    #[serde(default)]
+    pub category: Option<String>,
+    /// 自由标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 定时启动时间（RFC 3339），启动后清空
+    #[serde(default)]
+    pub start_at: Option<String>,
+    /// 定时停止时间（RFC 3339），停止后清空
+    #[serde(default)]
+    pub stop_at: Option<String>,
+    /// 任务完成后执行的动作
+    #[serde(default)]
+    pub on_complete: Option<CompletionAction>,
+    /// 期望的校验值（`算法=十六进制摘要`）
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// 最近一次校验的结果
+    #[serde(default)]
+    pub checksum_status: Option<ChecksumStatus>,
+    /// BT 任务的 infohash（小写十六进制），用于识别重复添加
+    #[serde(default)]
+    pub info_hash: Option<String>,
+}
+
+fn default_backend_id() -> String {
+    crate::aria2::client::DEFAULT_BACKEND_ID.to_string()
 }
 
 impl PersistedTask {
@@ -68,47 +116,278 @@ impl PersistedTask {
 /// Minimum interval between save operations (ms)
 const SAVE_DEBOUNCE_MS: u64 = 1500;
 
+/// 任务数据库文件名
+const TASKS_DB_FILE: &str = "tasks.db";
+/// 旧版 JSON 存储，首次打开数据库时迁移
+const LEGACY_TASKS_FILE: &str = "tasks.json";
+
+/// 已打开的数据库、其中的任务以及被隔离的损坏行数
+type OpenedDb = (TaskDb, Vec<PersistedTask>, usize);
+
+/// 尚未写入数据库的变更（按 GID 合并，同一任务只保留最后一次修改）
+#[derive(Default)]
+struct PendingWrites {
+    upserts: HashMap<String, PersistedTask>,
+    deletes: HashSet<String>,
+}
+
+impl PendingWrites {
+    fn upsert(&mut self, task: &PersistedTask) {
+        self.deletes.remove(&task.gid);
+        self.upserts.insert(task.gid.clone(), task.clone());
+    }
+
+    fn delete(&mut self, gid: &str) {
+        self.upserts.remove(gid);
+        self.deletes.insert(gid.to_string());
+    }
+
+    fn is_empty(&self) -> bool {
+        self.upserts.is_empty() && self.deletes.is_empty()
+    }
+
+    /// 写入失败时放回未完成的变更，不覆盖期间产生的更新
+    fn requeue(&mut self, failed: PendingWrites) {
+        for (gid, task) in failed.upserts {
+            if !self.deletes.contains(&gid) {
+                self.upserts.entry(gid).or_insert(task);
+            }
+        }
+        for gid in failed.deletes {
+            if !self.upserts.contains_key(&gid) {
+                self.deletes.insert(gid);
+            }
+        }
+    }
+}
+
+/// 任务存储：内存中保存全部任务供读取，变更按行增量写入 SQLite
 pub struct TaskStore {
     pub tasks: Mutex<HashMap<String, PersistedTask>>,
-    file_path: Mutex<Option<PathBuf>>,
+    db: Arc<Mutex<Option<TaskDb>>>,
+    pending: Arc<Mutex<PendingWrites>>,
     last_save_time: AtomicU64,
     /// Trailing debounce: set to true when a save is skipped due to debounce
     trailing_pending: AtomicBool,
     /// Guard to prevent multiple trailing save threads
     trailing_scheduled: AtomicBool,
+    /// 是否需要在下一轮后台同步中执行历史保留策略
+    retention_requested: AtomicBool,
 }
 
 impl TaskStore {
     pub fn new() -> Self {
         Self {
             tasks: Mutex::new(HashMap::new()),
-            file_path: Mutex::new(None),
+            db: Arc::new(Mutex::new(None)),
+            pending: Arc::new(Mutex::new(PendingWrites::default())),
             last_save_time: AtomicU64::new(0),
             trailing_pending: AtomicBool::new(false),
             trailing_scheduled: AtomicBool::new(false),
+            retention_requested: AtomicBool::new(false),
         }
     }
 
     pub fn init(&self, app: &AppHandle) {
         if let Ok(app_data_dir) = app.path().app_data_dir() {
-            let path = app_data_dir.join("tasks.json");
-            if let Ok(mut fp) = self.file_path.lock() {
-                *fp = Some(path.clone());
+            let db_path = app_data_dir.join(TASKS_DB_FILE);
+            let legacy_path = app_data_dir.join(LEGACY_TASKS_FILE);
+            match self.open(&db_path, &legacy_path) {
+                Ok(reports) => {
+                    if let Some(log) = app.try_state::<RecoveryLog>() {
+                        for report in reports {
+                            log.push(report);
+                        }
+                    }
+                }
+                Err(e) => {
+                    crate::app_error!(
+                        "Core::Store",
+                        "tasks_db_open_failed",
+                        json!({ "path": db_path.to_string_lossy(), "error": e.to_string() })
+                    );
+                }
+            }
+        }
+    }
+
+    /// 打开任务数据库并载入全部任务；数据库为空时从旧版 tasks.json 迁移。
+    /// 返回过程中发生的损坏恢复报告。
+    pub fn open(&self, db_path: &Path, legacy_path: &Path) -> AppResult<Vec<RecoveryReport>> {
+        if let Some(parent) = db_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut reports = Vec::new();
+
+        let (mut db, mut loaded, corrupt_rows) = match Self::open_db(db_path) {
+            Ok(opened) => opened,
+            Err(e) => {
+                crate::app_error!(
+                    "Core::Store",
+                    "tasks_db_corrupt",
+                    json!({ "path": db_path.to_string_lossy(), "error": e.to_string() })
+                );
+                let (opened, report) = Self::recover_db(db_path)?;
+                reports.push(report);
+                opened
             }
+        };
+
+        if corrupt_rows > 0 {
+            let mut report = RecoveryReport::new(db_path);
+            report.recovered = loaded.len();
+            report.lost = corrupt_rows;
+            reports.push(report);
+        }
+
+        if db.is_empty()? && legacy_path.exists() {
+            if let Some(report) = Self::migrate_legacy_json(&mut db, legacy_path)? {
+                reports.push(report);
+            }
+            loaded = db.load_all()?.0;
+        }
+
+        if !loaded.is_empty() {
+            Self::snapshot_db(&db, db_path);
+        }
+
+        crate::app_info!(
+            "Core::Store",
+            "tasks_loaded",
+            json!({ "path": db_path.to_string_lossy(), "count": loaded.len() })
+        );
+        if let Ok(mut tasks) = self.tasks.lock() {
+            for t in loaded {
+                tasks.insert(t.gid.clone(), t);
+            }
+        }
+        if let Ok(mut guard) = self.db.lock() {
+            *guard = Some(db);
+        }
+        Ok(reports)
+    }
+
+    fn open_db(db_path: &Path) -> AppResult<OpenedDb> {
+        let mut db = TaskDb::open(db_path)?;
+        let (tasks, corrupt_rows) = db.load_all()?;
+        Ok((db, tasks, corrupt_rows))
+    }
+
+    /// 数据库无法打开时隔离损坏文件（连同 WAL），由新到旧尝试备份，均不可用时新建空库
+    fn recover_db(db_path: &Path) -> AppResult<(OpenedDb, RecoveryReport)> {
+        let mut report = RecoveryReport::new(db_path);
+        report.quarantined = Some(recovery::quarantine(db_path)?.to_string_lossy().to_string());
+        for side in ["-wal", "-shm"] {
+            let mut side_path = db_path.as_os_str().to_owned();
+            side_path.push(side);
+            let side_path = Path::new(&side_path);
+            if side_path.exists() {
+                recovery::quarantine(side_path)?;
+            }
+        }
 
-            // Load existing
-            if path.exists() {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if let Ok(list) = serde_json::from_str::<Vec<PersistedTask>>(&content) {
-                        if let Ok(mut tasks) = self.tasks.lock() {
-                            for t in list {
-                                tasks.insert(t.gid.clone(), t);
+        for backup in recovery::existing_backups(db_path) {
+            fs::copy(&backup, db_path)?;
+            match Self::open_db(db_path) {
+                Ok(opened) => {
+                    report.restored_from = Some(backup.to_string_lossy().to_string());
+                    report.recovered = opened.1.len();
+                    report.lost = opened.2;
+                    return Ok((opened, report));
+                }
+                Err(e) => {
+                    crate::app_warn!(
+                        "Core::Store",
+                        "tasks_backup_unusable",
+                        json!({ "backup": backup.to_string_lossy(), "error": e.to_string() })
+                    );
+                    let _ = fs::remove_file(db_path);
+                }
+            }
+        }
+
+        Ok((Self::open_db(db_path)?, report))
+    }
+
+    /// 启动时保留一份数据库快照，最多保留 `recovery::BACKUP_COUNT` 份
+    fn snapshot_db(db: &TaskDb, db_path: &Path) {
+        let result = recovery::shift_backups(db_path)
+            .and_then(|_| db.backup_to(&recovery::backup_path(db_path, 1)));
+        if let Err(e) = result {
+            crate::app_warn!(
+                "Core::Store",
+                "tasks_backup_failed",
+                json!({ "path": db_path.to_string_lossy(), "error": e.to_string() })
+            );
+        }
+    }
+
+    /// 一次性导入 tasks.json，成功后重命名为 tasks.json.migrated 保留备查。
+    /// 文件损坏时隔离原文件，并尽量逐条抢救其中完整的任务记录。
+    fn migrate_legacy_json(
+        db: &mut TaskDb,
+        legacy_path: &Path,
+    ) -> AppResult<Option<RecoveryReport>> {
+        let content = fs::read_to_string(legacy_path)?;
+        let (list, report) = match Self::decode_legacy_json(&content) {
+            Ok(list) => (list, None),
+            Err(e) => {
+                crate::app_error!(
+                    "Core::Store",
+                    "legacy_tasks_parse_failed",
+                    json!({ "path": legacy_path.to_string_lossy(), "error": e.to_string() })
+                );
+                let (records, mut lost) = recovery::salvage_array_records(&content);
+                let list: Vec<PersistedTask> = records
+                    .into_iter()
+                    .filter_map(|mut record| {
+                        let decoded = migrations::migrate(
+                            &mut record,
+                            1,
+                            migrations::TASK_MIGRATIONS,
+                            "tasks",
+                        )
+                        .and_then(|_| Ok(serde_json::from_value::<PersistedTask>(record)?));
+                        match decoded {
+                            Ok(task) => Some(task),
+                            Err(_) => {
+                                lost += 1;
+                                None
                             }
                         }
-                    }
-                }
+                    })
+                    .collect();
+
+                let mut report = RecoveryReport::new(legacy_path);
+                report.recovered = list.len();
+                report.lost = lost;
+                report.quarantined =
+                    Some(recovery::quarantine(legacy_path)?.to_string_lossy().to_string());
+                (list, Some(report))
             }
+        };
+
+        db.write(list.iter(), std::iter::empty())?;
+
+        if report.is_none() {
+            let mut migrated = legacy_path.as_os_str().to_owned();
+            migrated.push(".migrated");
+            fs::rename(legacy_path, &migrated)?;
+
+            crate::app_info!(
+                "Core::Store",
+                "legacy_tasks_migrated",
+                json!({ "count": list.len(), "backup": Path::new(&migrated).to_string_lossy() })
+            );
         }
+        Ok(report)
+    }
+
+    /// 解析 tasks.json：拆开版本信封（旧版为裸数组）并逐条执行迁移链
+    fn decode_legacy_json(content: &str) -> AppResult<Vec<PersistedTask>> {
+        let (version, mut data) = migrations::open_envelope(serde_json::from_str(content)?);
+        migrations::migrate_records(&mut data, version, migrations::TASK_MIGRATIONS, "tasks")?;
+        Ok(serde_json::from_value(data)?)
     }
 
     pub fn save(&self) {
@@ -124,14 +403,12 @@ impl TaskStore {
 
             // Schedule a deferred trailing write if not already scheduled
             if !self.trailing_scheduled.swap(true, Ordering::AcqRel) {
-                // Snapshot data eagerly for the deferred write
-                let snapshot = self.snapshot_for_write();
-                if let Some((path, list)) = snapshot {
-                    std::thread::spawn(move || {
-                        std::thread::sleep(Duration::from_millis(SAVE_DEBOUNCE_MS));
-                        Self::write_to_disk(path, list);
-                    });
-                }
+                let db = self.db.clone();
+                let pending = self.pending.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_millis(SAVE_DEBOUNCE_MS));
+                    Self::flush(&db, &pending);
+                });
             }
             return;
         }
@@ -143,32 +420,36 @@ impl TaskStore {
 
     /// Actual persistence logic (no debounce check)
     fn save_inner(&self) {
-        if let Some((path, list)) = self.snapshot_for_write() {
-            // Offload IO to thread
-            std::thread::spawn(move || {
-                Self::write_to_disk(path, list);
-            });
-        }
+        let db = self.db.clone();
+        let pending = self.pending.clone();
+        // Offload IO to thread
+        std::thread::spawn(move || {
+            Self::flush(&db, &pending);
+        });
     }
 
-    /// Snapshot current state for writing. Returns (path, sorted task list) or None.
-    fn snapshot_for_write(&self) -> Option<(PathBuf, Vec<PersistedTask>)> {
-        let path = self.file_path.lock().ok()?.clone()?;
-        let mut list: Vec<PersistedTask> = self.tasks.lock().ok()?.values().cloned().collect();
-        // Sort for stable JSON output
-        list.sort_by(|a, b| b.added_at.cmp(&a.added_at));
-        Some((path, list))
-    }
+    /// 将累积的变更写入数据库（阻塞，供后台线程或退出时调用）
+    fn flush(db: &Mutex<Option<TaskDb>>, pending: &Mutex<PendingWrites>) {
+        let Ok(mut db_guard) = db.lock() else {
+            return;
+        };
+        let Some(db) = db_guard.as_mut() else {
+            return;
+        };
 
-    /// Write task list to disk (blocking, intended for background threads)
-    fn write_to_disk(path: PathBuf, list: Vec<PersistedTask>) {
-        if let Ok(json) = serde_json::to_string_pretty(&list) {
-            if let Err(e) = crate::utils::atomic_write(&path, &json) {
-                crate::app_error!(
-                    "Core::Store",
-                    "tasks_save_failed",
-                    json!({ "path": path.to_string_lossy(), "error": e.to_string() })
-                );
+        let batch = match pending.lock() {
+            Ok(mut guard) if !guard.is_empty() => std::mem::take(&mut *guard),
+            _ => return,
+        };
+
+        if let Err(e) = db.write(batch.upserts.values(), batch.deletes.iter()) {
+            crate::app_error!(
+                "Core::Store",
+                "tasks_save_failed",
+                json!({ "rows": batch.upserts.len() + batch.deletes.len(), "error": e.to_string() })
+            );
+            if let Ok(mut guard) = pending.lock() {
+                guard.requeue(batch);
             }
         }
     }
@@ -177,16 +458,36 @@ impl TaskStore {
     pub fn force_save(&self) {
         self.trailing_pending.store(false, Ordering::Release);
         self.last_save_time.store(0, Ordering::Relaxed);
-        self.save_inner();
+        Self::flush(&self.db, &self.pending);
+    }
+
+    fn mark_upsert(&self, task: &PersistedTask) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.upsert(task);
+        }
+    }
+
+    fn mark_delete(&self, gid: &str) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.delete(gid);
+        }
     }
 
     pub fn add_task(&self, task: PersistedTask) {
         if let Ok(mut tasks) = self.tasks.lock() {
+            self.mark_upsert(&task);
             tasks.insert(task.gid.clone(), task);
         }
         self.save();
     }
 
+    /// 查询任务所属后端，未知任务回退到默认后端
+    pub fn backend_of(&self, gid: &str) -> String {
+        self.get_task(gid)
+            .map(|t| t.backend_id)
+            .unwrap_or_else(default_backend_id)
+    }
+
     pub fn get_task(&self, gid: &str) -> Option<PersistedTask> {
         if let Ok(tasks) = self.tasks.lock() {
             return tasks.get(gid).cloned();
@@ -197,26 +498,51 @@ impl TaskStore {
     pub fn update_task_state(&self, gid: &str, state: TaskState) {
         if let Ok(mut tasks) = self.tasks.lock() {
             if let Some(t) = tasks.get_mut(gid) {
-                t.transition_state(state);
+                if t.transition_state(state) {
+                    self.mark_upsert(t);
+                }
             }
         }
         self.save();
     }
 
+    /// 就地修改单个任务并保存，返回任务是否存在
+    pub fn update_task<F>(&self, gid: &str, f: F) -> bool
+    where
+        F: FnOnce(&mut PersistedTask),
+    {
+        let mut found = false;
+        if let Ok(mut tasks) = self.tasks.lock() {
+            if let Some(t) = tasks.get_mut(gid) {
+                f(t);
+                self.mark_upsert(t);
+                found = true;
+            }
+        }
+        if found {
+            self.save();
+        }
+        found
+    }
+
     pub fn update_filename(&self, gid: &str, filename: &str) {
         if let Ok(mut tasks) = self.tasks.lock() {
             if let Some(t) = tasks.get_mut(gid) {
                 t.filename = filename.to_string();
+                self.mark_upsert(t);
             }
         }
         self.save();
     }
 
-    // Batch update all tasks
+    // Batch update all tasks (only rows that actually changed are written)
     pub fn update_all(&self, updated_tasks: Vec<PersistedTask>) {
         if let Ok(mut tasks) = self.tasks.lock() {
             for task in updated_tasks {
-                tasks.insert(task.gid.clone(), task);
+                if tasks.get(&task.gid) != Some(&task) {
+                    self.mark_upsert(&task);
+                    tasks.insert(task.gid.clone(), task);
+                }
             }
         }
         self.save();
@@ -227,16 +553,17 @@ impl TaskStore {
         &self,
         gid: &str,
         state: TaskState,
-        completed: &str,
-        speed: &str,
-        total: &str,
+        completed: u64,
+        speed: u64,
+        total: u64,
     ) {
         if let Ok(mut tasks) = self.tasks.lock() {
             if let Some(t) = tasks.get_mut(gid) {
                 t.state = state;
-                t.completed_length = completed.to_string();
-                t.download_speed = speed.to_string();
-                t.total_length = total.to_string();
+                t.completed_length = completed;
+                t.download_speed = speed;
+                t.total_length = total;
+                self.mark_upsert(t);
             }
         }
         // Save is usually called after batch update to avoid IO spam
@@ -256,6 +583,9 @@ impl TaskStore {
         let mut removed = false;
         if let Ok(mut tasks) = self.tasks.lock() {
             removed = tasks.remove(gid).is_some();
+            if removed {
+                self.mark_delete(gid);
+            }
         }
         self.save();
         removed
@@ -268,18 +598,22 @@ impl TaskStore {
                 if task.state == TaskState::Active || task.state == TaskState::Waiting {
                     task.state = TaskState::Paused;
                     task.completed_at = Some(now.clone());
+                    self.mark_upsert(task);
                 }
             }
         }
         self.save();
     }
 
+    /// 将已暂停的任务标记为等待中；等待定时启动（设置了 `start_at`）的任务保持暂停
     pub fn update_all_paused_to_waiting(&self) {
         if let Ok(mut tasks) = self.tasks.lock() {
             for task in tasks.values_mut() {
-                if task.state == TaskState::Paused {
-                    task.state = TaskState::Waiting;
-                    task.completed_at = None;
+                if task.state == TaskState::Paused
+                    && task.start_at.is_none()
+                    && task.transition_state(TaskState::Waiting)
+                {
+                    self.mark_upsert(task);
                 }
             }
         }
@@ -291,7 +625,9 @@ impl TaskStore {
         if let Ok(mut tasks) = self.tasks.lock() {
             for gid in gids {
                 if let Some(t) = tasks.get_mut(gid) {
-                    t.transition_state(state);
+                    if t.transition_state(state) {
+                        self.mark_upsert(t);
+                    }
                 }
             }
         }
@@ -305,6 +641,7 @@ impl TaskStore {
         if let Ok(mut tasks) = self.tasks.lock() {
             for gid in gids {
                 if tasks.remove(gid).is_some() {
+                    self.mark_delete(gid);
                     removed.push(gid.clone());
                 }
             }
@@ -313,6 +650,58 @@ impl TaskStore {
         removed
     }
 
+    /// 请求在下一轮后台同步中执行历史保留策略，由同步循环负责清理并通知前端
+    pub fn request_retention(&self) {
+        self.retention_requested.store(true, Ordering::Release);
+    }
+
+    /// 取出并清除保留策略执行请求
+    pub fn take_retention_request(&self) -> bool {
+        self.retention_requested.swap(false, Ordering::AcqRel)
+    }
+
+    /// 按保留策略清理历史任务（已完成、出错、已移除、文件缺失），返回被清理的 GID。
+    /// 未打开数据库时不做任何处理。
+    pub fn apply_retention(&self, policy: &HistoryRetention) -> Vec<String> {
+        if policy.is_unlimited() {
+            return Vec::new();
+        }
+
+        // 先落盘，保证查询基于最新状态
+        Self::flush(&self.db, &self.pending);
+
+        let expired = match self.db.lock() {
+            Ok(guard) => match guard.as_ref() {
+                Some(db) => db.expired_history(policy, Local::now().timestamp()),
+                None => return Vec::new(),
+            },
+            Err(_) => return Vec::new(),
+        };
+
+        let expired = match expired {
+            Ok(gids) => gids,
+            Err(e) => {
+                crate::app_warn!(
+                    "Core::Store",
+                    "history_retention_query_failed",
+                    json!({ "error": e.to_string() })
+                );
+                return Vec::new();
+            }
+        };
+
+        if !expired.is_empty() {
+            let removed = self.remove_tasks_batch(&expired);
+            Self::flush(&self.db, &self.pending);
+            crate::app_info!(
+                "Core::Store",
+                "history_pruned",
+                json!({ "count": removed.len() })
+            );
+        }
+        expired
+    }
+
     /// Get all filenames currently known to the store (to prevent duplicates)
     pub fn get_active_filenames(&self) -> Vec<String> {
         if let Ok(tasks) = self.tasks.lock() {
@@ -326,3 +715,184 @@ impl TaskStore {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::{persisted_task, temp_dir};
+
+    fn open_store_with_reports(dir: &Path) -> (TaskStore, Vec<RecoveryReport>) {
+        let store = TaskStore::new();
+        let reports = store
+            .open(&dir.join(TASKS_DB_FILE), &dir.join(LEGACY_TASKS_FILE))
+            .expect("open store");
+        (store, reports)
+    }
+
+    fn open_store(dir: &Path) -> TaskStore {
+        open_store_with_reports(dir).0
+    }
+
+    fn finished(gid: &str, days_ago: i64) -> PersistedTask {
+        let mut task = persisted_task(gid, TaskState::Complete);
+        task.completed_at = Some((Local::now() - chrono::Duration::days(days_ago)).to_rfc3339());
+        task
+    }
+
+    #[test]
+    fn migrates_legacy_json_once() {
+        let dir = temp_dir("migrate");
+        let legacy = vec![
+            persisted_task("a", TaskState::Complete),
+            persisted_task("b", TaskState::Paused),
+        ];
+        fs::write(dir.join(LEGACY_TASKS_FILE), serde_json::to_string(&legacy).unwrap()).unwrap();
+
+        let store = open_store(&dir);
+        assert_eq!(store.get_all().len(), 2);
+        assert!(!dir.join(LEGACY_TASKS_FILE).exists());
+        assert!(dir.join("tasks.json.migrated").exists());
+
+        // 再次打开时直接读取数据库
+        let reopened = open_store(&dir);
+        assert_eq!(reopened.get_task("b").unwrap().state, TaskState::Paused);
+    }
+
+    #[test]
+    fn salvages_records_from_corrupt_legacy_json() {
+        let dir = temp_dir("corrupt");
+        let records: Vec<String> = ["a", "b"]
+            .iter()
+            .map(|gid| serde_json::to_string(&persisted_task(gid, TaskState::Complete)).unwrap())
+            .collect();
+        // 写入中断：第三条记录被截断
+        let content = format!("[{},{},{{\"gid\":\"c\",\"file", records[0], records[1]);
+        fs::write(dir.join(LEGACY_TASKS_FILE), content).unwrap();
+
+        let (store, reports) = open_store_with_reports(&dir);
+
+        assert_eq!(store.get_all().len(), 2);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].recovered, 2);
+        assert_eq!(reports[0].lost, 1);
+        // 原文件被隔离而不是被覆盖
+        assert!(!dir.join(LEGACY_TASKS_FILE).exists());
+        let quarantined = reports[0].quarantined.clone().unwrap();
+        assert!(Path::new(&quarantined).exists());
+    }
+
+    #[test]
+    fn restores_corrupt_database_from_backup() {
+        let dir = temp_dir("restore");
+        {
+            let store = open_store(&dir);
+            store.add_task(persisted_task("a", TaskState::Complete));
+            store.force_save();
+        }
+        // 第二次启动时生成快照
+        drop(open_store(&dir));
+        fs::remove_file(dir.join(TASKS_DB_FILE)).unwrap();
+        fs::write(dir.join(TASKS_DB_FILE), b"definitely not sqlite").unwrap();
+
+        let (store, reports) = open_store_with_reports(&dir);
+
+        assert!(store.get_task("a").is_some());
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].restored_from.is_some());
+        assert!(Path::new(reports[0].quarantined.as_ref().unwrap()).exists());
+    }
+
+    #[test]
+    fn upgrades_v1_database_rows() {
+        let dir = temp_dir("upgrade");
+        {
+            let conn = rusqlite::Connection::open(dir.join(TASKS_DB_FILE)).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE tasks (
+                    gid TEXT PRIMARY KEY, state TEXT NOT NULL, backend_id TEXT NOT NULL,
+                    added_at TEXT NOT NULL, finished_at INTEGER, data TEXT NOT NULL
+                );
+                PRAGMA user_version = 1;",
+            )
+            .unwrap();
+            let legacy = include_str!("../../tests/fixtures/tasks_v1.json");
+            let records: Vec<serde_json::Value> = serde_json::from_str(legacy).unwrap();
+            for record in records {
+                conn.execute(
+                    "INSERT INTO tasks (gid, state, backend_id, added_at, data)
+                     VALUES (?1, ?2, 'default', ?3, ?4)",
+                    rusqlite::params![
+                        record["gid"].as_str().unwrap(),
+                        record["state"].as_str().unwrap(),
+                        record["added_at"].as_str().unwrap(),
+                        record.to_string()
+                    ],
+                )
+                .unwrap();
+            }
+        }
+
+        let store = open_store(&dir);
+        let task = store.get_task("a1b2c3d4e5f60718").unwrap();
+        assert_eq!(task.total_length, 2048);
+        assert_eq!(task.completed_length, 512);
+        assert_eq!(store.get_all().len(), 3);
+    }
+
+    #[test]
+    fn persists_incremental_changes() {
+        let dir = temp_dir("incremental");
+        let store = open_store(&dir);
+        store.add_task(persisted_task("keep", TaskState::Active));
+        store.add_task(persisted_task("drop", TaskState::Active));
+        store.update_task("keep", |t| t.filename = "renamed.bin".to_string());
+        store.remove_task("drop");
+        store.force_save();
+
+        let reopened = open_store(&dir);
+        let tasks = reopened.get_all();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].filename, "renamed.bin");
+    }
+
+    #[test]
+    fn retention_prunes_old_and_excess_history() {
+        let dir = temp_dir("retention");
+        let store = open_store(&dir);
+        store.add_task(finished("old", 40));
+        store.add_task(finished("recent1", 1));
+        store.add_task(finished("recent2", 2));
+        store.add_task(finished("recent3", 3));
+        store.add_task(persisted_task("running", TaskState::Active));
+        // 等待自动重试的失败任务不受保留策略影响
+        let mut retrying = finished("retrying", 60);
+        retrying.state = TaskState::Error;
+        retrying.next_retry_at = Some(Local::now().to_rfc3339());
+        store.add_task(retrying);
+
+        let pruned = store.apply_retention(&HistoryRetention {
+            max_age_days: 30,
+            max_count: 2,
+        });
+
+        assert_eq!(pruned.len(), 2);
+        assert!(store.get_task("old").is_none());
+        assert!(store.get_task("recent3").is_none());
+        assert!(store.get_task("recent1").is_some());
+        assert!(store.get_task("running").is_some());
+        assert!(store.get_task("retrying").is_some());
+
+        let reopened = open_store(&dir);
+        assert_eq!(reopened.get_all().len(), 4);
+    }
+
+    #[test]
+    fn retention_request_is_taken_once() {
+        let store = TaskStore::new();
+        assert!(!store.take_retention_request());
+        store.request_retention();
+        store.request_retention();
+        assert!(store.take_retention_request());
+        assert!(!store.take_retention_request());
+    }
+}
@@ -1,11 +1,24 @@
+use crate::aria2::backend::BackendRegistry;
 use crate::aria2::client::{self as aria2_client, Aria2Task};
+use crate::aria2::ws::{self as aria2_ws, Aria2Notification};
+use crate::core::completion;
+use crate::core::config::{AppConfig, ConfigState};
 use crate::core::error::{AppError, AppResult};
-use crate::core::events::{EVENT_TASKS_DELTA, EVENT_TASK_COMPLETED};
+use crate::core::events::{
+    EVENT_GLOBAL_STATS, EVENT_STORE_RECOVERED, EVENT_TASKS_DELTA, EVENT_TASK_COMPLETED,
+};
+use crate::core::recovery::RecoveryLog;
+use crate::core::retry;
+use crate::core::stats::{self, GlobalStats};
 use crate::core::store::TaskStore;
-use crate::core::types::TaskState;
+use crate::core::timer;
+use crate::core::types::{ChecksumStatus, CompletionAction, TaskErrorKind, TaskState};
+use chrono::Local;
+use futures::future::join_all;
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::Ordering;
+use std::sync::Mutex;
 use tauri::AppHandle;
 
 // 连接日志的状态跟踪
@@ -13,6 +26,12 @@ use tauri::AppHandle;
 static LAST_CONNECTION_STATUS: std::sync::atomic::AtomicBool =
     std::sync::atomic::AtomicBool::new(true);
 
+// 已离线的非默认后端（用于去重连接日志）
+static OFFLINE_BACKENDS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// 历史任务保留策略的执行间隔
+const HISTORY_RETENTION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
 /// 计算剩余秒数（纯数值，不含格式化）
 fn calculate_remaining_secs(raw_speed: u64, total: u64, completed: u64) -> u64 {
     if raw_speed == 0 || total <= completed {
@@ -28,18 +47,21 @@ use crate::core::store::PersistedTask;
 fn sync_task_fields(task: &mut PersistedTask, at: &Aria2Task) -> bool {
     let mut dirty = false;
 
-    if task.completed_length != at.completed_length {
-        task.completed_length = at.completed_length.clone();
+    let completed_length = at.completed_length.parse().unwrap_or(0);
+    if task.completed_length != completed_length {
+        task.completed_length = completed_length;
         dirty = true;
     }
 
-    if task.total_length != at.total_length {
-        task.total_length = at.total_length.clone();
+    let total_length = at.total_length.parse().unwrap_or(0);
+    if task.total_length != total_length {
+        task.total_length = total_length;
         dirty = true;
     }
 
-    if task.download_speed != at.download_speed {
-        task.download_speed = at.download_speed.clone();
+    let download_speed = at.download_speed.parse().unwrap_or(0);
+    if task.download_speed != download_speed {
+        task.download_speed = download_speed;
         dirty = true;
     }
 
@@ -50,8 +72,51 @@ fn sync_task_fields(task: &mut PersistedTask, at: &Aria2Task) -> bool {
         }
     }
 
-    // 始终从 Aria2 同步文件名以处理自动重命名（例如 file.1.mp4）
-    if let Some(file) = at.files.first() {
+    // 仅 error 状态携带有效的错误码；任务恢复或重新开始后清除
+    let error_kind = if at.status == "error" {
+        at.error_code
+            .as_deref()
+            .and_then(TaskErrorKind::from_aria2_code)
+            .or(Some(TaskErrorKind::Unknown))
+    } else {
+        None
+    };
+    if task.error_kind != error_kind {
+        task.error_kind = error_kind;
+        dirty = true;
+    }
+
+    // aria2 下载完成后按 checksum 选项校验失败，同样记录为校验不匹配
+    if error_kind == Some(TaskErrorKind::ChecksumMismatch)
+        && task.checksum_status != Some(ChecksumStatus::Mismatch)
+    {
+        task.checksum_status = Some(ChecksumStatus::Mismatch);
+        dirty = true;
+    }
+
+    let info_hash = at
+        .info_hash
+        .as_deref()
+        .filter(|h| !h.is_empty())
+        .map(str::to_ascii_lowercase);
+    if info_hash.is_some() && task.info_hash != info_hash {
+        task.info_hash = info_hash;
+        dirty = true;
+    }
+
+    // 多文件种子以种子名称作为任务名，单文件任务始终从 Aria2 同步文件名以处理自动重命名（例如 file.1.mp4）
+    let torrent_name = at
+        .bittorrent
+        .as_ref()
+        .and_then(|bt| bt.info.as_ref())
+        .map(|info| info.name.as_str())
+        .filter(|name| at.files.len() > 1 && !name.is_empty());
+    if let Some(name) = torrent_name {
+        if task.filename != name {
+            task.filename = name.to_string();
+            dirty = true;
+        }
+    } else if let Some(file) = at.files.first() {
         if !file.path.is_empty() {
             let path = std::path::Path::new(&file.path);
             if let Some(name) = path.file_name() {
@@ -87,6 +152,12 @@ pub struct FrontendTask {
     pub save_path: String,
     #[serde(rename = "errorMessage")]
     pub error_message: String,
+    #[serde(rename = "errorKind")]
+    pub error_kind: Option<TaskErrorKind>,
+    #[serde(rename = "retryAttempts")]
+    pub retry_attempts: u32,
+    #[serde(rename = "nextRetryAt")]
+    pub next_retry_at: Option<String>,
     #[serde(rename = "userAgent")]
     pub user_agent: String,
     pub referer: String,
@@ -96,12 +167,83 @@ pub struct FrontendTask {
     pub max_download_limit: String,
     #[serde(rename = "completedAt")]
     pub completed_at: Option<String>,
+    #[serde(rename = "backendId")]
+    pub backend_id: String,
+    #[serde(rename = "numSeeders")]
+    pub num_seeders: u64,
+    pub connections: u64,
+    pub bittorrent: Option<FrontendBtInfo>,
+    pub files: Vec<FrontendFile>,
+    #[serde(rename = "queuePosition")]
+    pub queue_position: Option<u32>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(rename = "startAt")]
+    pub start_at: Option<String>,
+    #[serde(rename = "stopAt")]
+    pub stop_at: Option<String>,
+    #[serde(rename = "onComplete")]
+    pub on_complete: Option<CompletionAction>,
+    pub checksum: Option<String>,
+    #[serde(rename = "checksumStatus")]
+    pub checksum_status: Option<ChecksumStatus>,
+}
+
+/// 任务内的单个文件（多文件种子 / Metalink 逐文件展示进度）
+#[derive(Debug, Clone, serde::Serialize, PartialEq)]
+pub struct FrontendFile {
+    pub index: u32,
+    pub path: String,
+    pub length: u64,
+    #[serde(rename = "completedLength")]
+    pub completed_length: u64,
+    pub selected: bool,
+}
+
+impl FrontendFile {
+    fn from_aria2(file: &aria2_client::Aria2File) -> Self {
+        Self {
+            index: file.index.parse().unwrap_or(0),
+            path: file.path.clone(),
+            length: file.length.parse().unwrap_or(0),
+            completed_length: file.completed_length.parse().unwrap_or(0),
+            selected: file.selected == "true",
+        }
+    }
+}
+
+/// BT 任务的种子元信息（仅在 aria2 返回 bittorrent 字段时存在）
+#[derive(Debug, Clone, serde::Serialize, PartialEq)]
+pub struct FrontendBtInfo {
+    pub name: Option<String>,
+    #[serde(rename = "infoHash")]
+    pub info_hash: Option<String>,
+    pub comment: Option<String>,
+    pub mode: Option<String>,
+    #[serde(rename = "creationDate")]
+    pub creation_date: Option<u64>,
+    #[serde(rename = "announceList")]
+    pub announce_list: Vec<String>,
+}
+
+impl FrontendBtInfo {
+    fn from_aria2(at: &aria2_client::Aria2Task) -> Option<Self> {
+        let bt = at.bittorrent.as_ref()?;
+        Some(Self {
+            name: bt.info.as_ref().map(|info| info.name.clone()),
+            info_hash: at.info_hash.clone(),
+            comment: bt.comment.clone(),
+            mode: bt.mode.clone(),
+            creation_date: bt.creation_date,
+            announce_list: bt.announce_list.iter().flatten().cloned().collect(),
+        })
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(tag = "op", rename_all = "snake_case")]
 pub enum TaskDeltaChange {
-    Upsert { task: FrontendTask },
+    Upsert { task: Box<FrontendTask> },
     Remove { id: String },
 }
 
@@ -130,52 +272,99 @@ fn to_task_map(tasks: &[FrontendTask]) -> HashMap<String, FrontendTask> {
     map
 }
 
-pub async fn sync_tasks(state: &TaskStore, app_handle: &AppHandle) -> AppResult<Vec<FrontendTask>> {
+pub async fn sync_tasks(
+    state: &TaskStore,
+    registry: &BackendRegistry,
+    config: &AppConfig,
+) -> AppResult<Vec<FrontendTask>> {
     // 1. 从 Store 获取所有任务
     let mut store_tasks = state.get_all();
 
-    // 2. 从 Aria2 获取（失败时快速返回，避免冗余日志）
-    let all_tasks = match aria2_client::get_all_tasks().await {
-        Ok(t) => {
-            // 检查是否刚从故障中恢复
-            if !LAST_CONNECTION_STATUS.load(Ordering::Acquire) {
-                crate::app_info!("Core::Sync", "aria2_connection_restored");
-                LAST_CONNECTION_STATUS.store(true, Ordering::Release);
-            }
-            t
-        }
-        Err(e) => {
-            // 检查这是否是新的故障
-            if LAST_CONNECTION_STATUS.load(Ordering::Acquire) {
-                crate::app_warn!(
-                    "Core::Sync",
-                    "aria2_connection_lost",
-                    json!({ "error": e.to_string() })
-                );
-                LAST_CONNECTION_STATUS.store(false, Ordering::Release);
+    // 2. 从各个 Aria2 后端获取（默认后端失败时快速返回，避免冗余日志）
+    let backends = registry.ids();
+    let fetches = join_all(
+        backends
+            .iter()
+            .map(|id| async move { registry.get(id)?.tell_all().await }),
+    )
+    .await;
+
+    // 3. 创建 (后端, GID) -> Aria2Task 的映射以方便查找
+    let mut aria2_map: HashMap<(String, String), Aria2Task> = HashMap::new();
+    // tellWaiting 按 aria2 实际队列顺序返回等待中/已暂停的任务
+    let mut queue_positions: HashMap<(String, String), u32> = HashMap::new();
+    let mut online_backends: HashSet<String> = HashSet::new();
+
+    for (backend, fetched) in backends.iter().zip(fetches) {
+        match fetched {
+            Ok(tasks) => {
+                if backend == aria2_client::DEFAULT_BACKEND_ID {
+                    // 检查是否刚从故障中恢复
+                    if !LAST_CONNECTION_STATUS.load(Ordering::Acquire) {
+                        crate::app_info!("Core::Sync", "aria2_connection_restored");
+                        LAST_CONNECTION_STATUS.store(true, Ordering::Release);
+                    }
+                } else if let Ok(mut offline) = OFFLINE_BACKENDS.lock() {
+                    if offline.as_mut().is_some_and(|set| set.remove(backend)) {
+                        crate::app_info!(
+                            "Core::Sync",
+                            "backend_connection_restored",
+                            json!({ "backend": backend })
+                        );
+                    }
+                }
+
+                online_backends.insert(backend.clone());
+                let mut position = 0u32;
+                for t in tasks {
+                    if t.status == "waiting" || t.status == "paused" {
+                        queue_positions.insert((backend.clone(), t.gid.clone()), position);
+                        position += 1;
+                    }
+                    aria2_map.insert((backend.clone(), t.gid.clone()), t);
+                }
             }
-            // 如果已经是 false，则保持沉默（静默模式）
+            Err(e) if backend == aria2_client::DEFAULT_BACKEND_ID => {
+                // 检查这是否是新的故障
+                if LAST_CONNECTION_STATUS.load(Ordering::Acquire) {
+                    crate::app_warn!(
+                        "Core::Sync",
+                        "aria2_connection_lost",
+                        json!({ "error": e.to_string() })
+                    );
+                    LAST_CONNECTION_STATUS.store(false, Ordering::Release);
+                }
+                // 如果已经是 false，则保持沉默（静默模式）
 
-            return Err(AppError::aria2(format!("无法连接到 Aria2: {}", e)));
+                return Err(AppError::aria2(format!("无法连接到 Aria2: {}", e)));
+            }
+            Err(e) => {
+                // 其他后端离线不影响整体同步，其任务保留最后已知状态
+                if let Ok(mut offline) = OFFLINE_BACKENDS.lock() {
+                    if offline
+                        .get_or_insert_with(HashSet::new)
+                        .insert(backend.clone())
+                    {
+                        crate::app_warn!(
+                            "Core::Sync",
+                            "backend_connection_lost",
+                            json!({ "backend": backend, "error": e.to_string() })
+                        );
+                    }
+                }
+            }
         }
-    };
-
-    // 3. 创建 GID -> Aria2Task 的映射以方便查找
-    let mut aria2_map: HashMap<String, Aria2Task> = HashMap::new();
-    for t in all_tasks {
-        aria2_map.insert(t.gid.clone(), t);
     }
 
     let mut result: Vec<FrontendTask> = Vec::new();
-    let mut total_dl = 0u64;
-    let mut total_ul = 0u64;
 
     // 跟踪是否需要将更改保存到磁盘
     let mut dirty = false;
 
     // 4. 同步逻辑 & 构造视图模型
     for task in store_tasks.iter_mut() {
-        let aria_task = aria2_map.get(&task.gid);
+        let backend_online = online_backends.contains(&task.backend_id);
+        let aria_task = aria2_map.get(&(task.backend_id.clone(), task.gid.clone()));
 
         let mut mapped_state = if let Some(at) = aria_task {
             TaskState::from_aria2_status(&at.status)
@@ -186,10 +375,13 @@ pub async fn sync_tasks(state: &TaskStore, app_handle: &AppHandle) -> AppResult<
         // 终态时的文件存在性检查
         // "missing" 只适用于已完成但文件被删除的情况
         // "error" 状态保持不变（下载失败本来就没有文件）
+        // 远程后端下仅对配置了路径映射的目录做检查
         if mapped_state == TaskState::Complete {
-            let full_path = std::path::Path::new(&task.save_path).join(&task.filename);
-            if !full_path.exists() {
-                mapped_state = TaskState::Missing;
+            if let Some(local_dir) = config.resolve_local_path(&task.backend_id, &task.save_path) {
+                let full_path = std::path::Path::new(&local_dir).join(&task.filename);
+                if !full_path.exists() {
+                    mapped_state = TaskState::Missing;
+                }
             }
         }
 
@@ -204,27 +396,42 @@ pub async fn sync_tasks(state: &TaskStore, app_handle: &AppHandle) -> AppResult<
             if sync_task_fields(task, at) {
                 dirty = true;
             }
+        } else if !backend_online {
+            // 后端离线：保留状态，仅清零速度
+            if task.download_speed != 0 {
+                task.download_speed = 0;
+                dirty = true;
+            }
         } else if mapped_state != TaskState::Missing
             && mapped_state != TaskState::Error
             && mapped_state != TaskState::Complete
             && mapped_state != TaskState::Removed
         {
-            match aria2_client::tell_status(
-                task.gid.clone(),
-                vec![
-                    "gid",
-                    "status",
-                    "totalLength",
-                    "completedLength",
-                    "downloadSpeed",
-                    "uploadLength",
-                    "uploadSpeed",
-                    "files",
-                    "errorMessage",
-                ],
-            )
-            .await
-            {
+            let fallback = match registry.get(&task.backend_id) {
+                Ok(backend) => {
+                    backend
+                        .tell_status(
+                            task.gid.clone(),
+                            vec![
+                                "gid",
+                                "status",
+                                "totalLength",
+                                "completedLength",
+                                "downloadSpeed",
+                                "uploadLength",
+                                "uploadSpeed",
+                                "dir",
+                                "files",
+                                "errorCode",
+                                "errorMessage",
+                            ],
+                        )
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+
+            match fallback {
                 Ok(aria_task) => {
                     let fallback_state = TaskState::from_aria2_status(&aria_task.status);
                     if task.transition_state(fallback_state) {
@@ -242,17 +449,38 @@ pub async fn sync_tasks(state: &TaskStore, app_handle: &AppHandle) -> AppResult<
                         dirty = true;
                     }
 
-                    if task.download_speed != "0" {
-                        task.download_speed = "0".to_string();
+                    // aria2 已不再持有该任务（通常是 aria2 未保存会话就退出），等同于被中断
+                    if task.error_kind.is_none() {
+                        task.error_kind = Some(TaskErrorKind::Interrupted);
+                        dirty = true;
+                    }
+
+                    if task.download_speed != 0 {
+                        task.download_speed = 0;
                         dirty = true;
                     }
                 }
             }
         }
 
-        let total = task.total_length.parse::<u64>().unwrap_or(0);
-        let completed = task.completed_length.parse::<u64>().unwrap_or(0);
-        let raw_speed = task.download_speed.parse::<u64>().unwrap_or(0);
+        if retry::schedule(task, &config.retry_policy, Local::now()) {
+            dirty = true;
+        }
+
+        // 离线后端保留最后已知的队列位置
+        if backend_online {
+            let position = queue_positions
+                .get(&(task.backend_id.clone(), task.gid.clone()))
+                .copied();
+            if task.queue_position != position {
+                task.queue_position = position;
+                dirty = true;
+            }
+        }
+
+        let total = task.total_length;
+        let completed = task.completed_length;
+        let raw_speed = task.download_speed;
 
         let remaining_secs = calculate_remaining_secs(raw_speed, total, completed);
 
@@ -262,14 +490,6 @@ pub async fn sync_tasks(state: &TaskStore, app_handle: &AppHandle) -> AppResult<
             0.0
         };
 
-        if task.state == TaskState::Active {
-            total_dl += raw_speed;
-            // 累加上传速度（从 aria2_map 获取）
-            if let Some(at) = aria2_map.get(&task.gid) {
-                total_ul += at.upload_speed.parse::<u64>().unwrap_or(0);
-            }
-        }
-
         result.push(FrontendTask {
             id: task.gid.clone(),
             filename: task.filename.clone(),
@@ -283,29 +503,67 @@ pub async fn sync_tasks(state: &TaskStore, app_handle: &AppHandle) -> AppResult<
             added_at: task.added_at.clone(),
             save_path: task.save_path.clone(),
             error_message: task.error_message.clone(),
+            error_kind: task.error_kind,
+            retry_attempts: task.retry_attempts,
+            next_retry_at: task.next_retry_at.clone(),
             user_agent: task.user_agent.clone(),
             referer: task.referer.clone(),
             proxy: task.proxy.clone(),
             headers: task.headers.clone(),
             max_download_limit: task.max_download_limit.clone(),
             completed_at: task.completed_at.clone(),
+            backend_id: task.backend_id.clone(),
+            num_seeders: aria_task
+                .and_then(|at| at.num_seeders.as_deref())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            connections: aria_task
+                .and_then(|at| at.connections.as_deref())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            bittorrent: aria_task.and_then(FrontendBtInfo::from_aria2),
+            files: aria_task
+                .map(|at| at.files.iter().map(FrontendFile::from_aria2).collect())
+                .unwrap_or_default(),
+            queue_position: task.queue_position,
+            category: task.category.clone(),
+            tags: task.tags.clone(),
+            start_at: task.start_at.clone(),
+            stop_at: task.stop_at.clone(),
+            on_complete: task.on_complete.clone(),
+            checksum: task.checksum.clone(),
+            checksum_status: task.checksum_status,
         });
     }
 
+    // 等待队列按 aria2 实际顺序排列（稳定排序，其余任务保持添加时间倒序）
+    result.sort_by(compare_queue_order);
+
     // 5. 清理孤儿任务（Aria2 中存在但 Store 中不存在的任务）
-    // 创建一个 Store GID 的集合以方便查找
-    let store_gids: std::collections::HashSet<String> =
-        store_tasks.iter().map(|t| t.gid.clone()).collect();
-
-    for (gid, _) in aria2_map.iter() {
-        if !store_gids.contains(gid) {
-            crate::app_warn!("Core::Sync", "orphan_task_detected", json!({ "gid": gid }));
-            // 生成清理任务，不阻塞同步过程
-            let gid_clone = gid.clone();
-            tauri::async_runtime::spawn(async move {
-                let _ = aria2_client::remove(gid_clone.clone()).await;
-                let _ = aria2_client::purge(gid_clone).await;
-            });
+    // 仅针对本应用独占的后端：远程守护进程可能被其他客户端共享，不能擅自清理
+    let default_backend = registry
+        .get(aria2_client::DEFAULT_BACKEND_ID)
+        .ok()
+        .filter(|backend| backend.is_exclusive());
+    if let Some(default_backend) = default_backend {
+        // 创建一个 Store GID 的集合以方便查找
+        let store_gids: HashSet<&str> = store_tasks
+            .iter()
+            .filter(|t| t.backend_id == aria2_client::DEFAULT_BACKEND_ID)
+            .map(|t| t.gid.as_str())
+            .collect();
+
+        for (backend, gid) in aria2_map.keys() {
+            if backend == aria2_client::DEFAULT_BACKEND_ID && !store_gids.contains(gid.as_str()) {
+                crate::app_warn!("Core::Sync", "orphan_task_detected", json!({ "gid": gid }));
+                // 生成清理任务，不阻塞同步过程
+                let gid_clone = gid.clone();
+                let backend = default_backend.clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = backend.remove(gid_clone.clone()).await;
+                    let _ = backend.purge(gid_clone).await;
+                });
+            }
         }
     }
 
@@ -314,14 +572,71 @@ pub async fn sync_tasks(state: &TaskStore, app_handle: &AppHandle) -> AppResult<
         state.update_all(store_tasks);
     }
 
-    // 更新任务栏图标
-    let _ =
-        crate::ui::tray::update_tray_icon_with_speed(app_handle.clone(), total_dl, total_ul).await;
-
     Ok(result)
 }
 
-pub fn start_background_sync(app_handle: AppHandle) {
+/// 活跃分组（下载中/等待中/已暂停）排在前面；组内未排队的任务在前，
+/// 排队任务按队列位置升序
+fn compare_queue_order(a: &FrontendTask, b: &FrontendTask) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let score = |t: &FrontendTask| TaskState::from(t.state.as_str()).score();
+    score(b)
+        .cmp(&score(a))
+        .then_with(|| match (a.queue_position, b.queue_position) {
+            (Some(pa), Some(pb)) => pa.cmp(&pb),
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        })
+}
+
+/// 根据任务活跃度与 WebSocket 推送可用性计算轮询间隔。
+/// 推送通道可用时，状态迁移由通知即时唤醒，轮询仅用于刷新进度与速度，可大幅放缓。
+fn poll_interval(has_active_tasks: bool, push_connected: bool) -> std::time::Duration {
+    match (has_active_tasks, push_connected) {
+        (true, false) => std::time::Duration::from_millis(200),
+        (true, true) => std::time::Duration::from_secs(1),
+        (false, false) => std::time::Duration::from_secs(2),
+        (false, true) => std::time::Duration::from_secs(10),
+    }
+}
+
+/// 推进版本号并推送一条增量事件，返回是否推送成功
+fn emit_delta(
+    app_handle: &AppHandle,
+    revision: &mut u64,
+    seq: &mut u64,
+    changes: Vec<TaskDeltaChange>,
+) -> bool {
+    use tauri::Emitter;
+
+    let from_revision = *revision;
+    *revision = revision.saturating_add(1);
+    *seq = seq.saturating_add(1);
+
+    let delta_event = TaskDeltaEvent::Delta {
+        from_revision,
+        to_revision: *revision,
+        seq: *seq,
+        changes,
+    };
+
+    if let Err(e) = app_handle.emit(EVENT_TASKS_DELTA, delta_event) {
+        crate::app_warn!(
+            "Core::Sync",
+            "tasks_delta_emit_failed",
+            json!({ "error": e.to_string() })
+        );
+        return false;
+    }
+    true
+}
+
+pub fn start_background_sync(
+    app_handle: AppHandle,
+    mut notifications: tokio::sync::mpsc::UnboundedReceiver<Aria2Notification>,
+) {
     use tauri::Emitter;
     use tauri::Manager;
 
@@ -329,17 +644,60 @@ pub fn start_background_sync(app_handle: AppHandle) {
         // 启动宽限期：等待 Sidecar 完全初始化（绑定端口）
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
 
+        // 告知前端启动时的文件损坏恢复结果
+        if let Some(log) = app_handle.try_state::<RecoveryLog>() {
+            for report in log.all() {
+                let _ = app_handle.emit(EVENT_STORE_RECOVERED, &report);
+            }
+        }
+
         let mut last_snapshot: HashMap<String, FrontendTask> = HashMap::new();
         let mut revision: u64 = 0;
         let mut seq: u64 = 0;
         let mut emitted_snapshot = false;
-        let mut notified_completed: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut notified_completed: std::collections::HashSet<String> =
+            std::collections::HashSet::new();
+        let mut last_stats: Option<GlobalStats> = None;
+        let mut last_retention = std::time::Instant::now();
 
         loop {
             let state = app_handle.state::<crate::core::store::TaskStore>();
+            let backends = app_handle.state::<BackendRegistry>();
             let mut has_active_tasks = false;
 
-            match sync_tasks(&state, &app_handle).await {
+            let config = app_handle
+                .try_state::<ConfigState>()
+                .map(|s| s.snapshot())
+                .unwrap_or_default();
+
+            // 历史保留策略按小时执行，启动及修改设置后在下一轮立即执行
+            if state.take_retention_request()
+                || last_retention.elapsed() >= HISTORY_RETENTION_INTERVAL
+            {
+                last_retention = std::time::Instant::now();
+                let pruned = state.apply_retention(&config.history_retention);
+                // 已下发给前端的任务立即推送移除，不依赖本轮同步是否成功
+                let changes: Vec<TaskDeltaChange> = pruned
+                    .iter()
+                    .filter(|gid| last_snapshot.contains_key(*gid))
+                    .map(|gid| TaskDeltaChange::Remove { id: gid.clone() })
+                    .collect();
+                if emitted_snapshot
+                    && !changes.is_empty()
+                    && emit_delta(&app_handle, &mut revision, &mut seq, changes)
+                {
+                    for gid in &pruned {
+                        last_snapshot.remove(gid);
+                    }
+                }
+            }
+
+            // 先重新提交已到期的自动重试，本轮同步即可反映新任务
+            retry::run_due_retries(&state, &backends, &config.retry_policy, Local::now()).await;
+            // 定时启动/停止到期的任务
+            timer::run_due_timers(&state, &backends, Local::now()).await;
+
+            match sync_tasks(&state, &backends, &config).await {
                 Ok(tasks) => {
                     // 检查是否有活跃任务（用于自适应轮询）
                     for task in &tasks {
@@ -378,7 +736,9 @@ pub fn start_background_sync(app_handle: AppHandle) {
                         for (id, task) in current_map.iter() {
                             match last_snapshot.get(id) {
                                 Some(prev) if prev == task => {}
-                                _ => changes.push(TaskDeltaChange::Upsert { task: task.clone() }),
+                                _ => changes.push(TaskDeltaChange::Upsert {
+                                    task: Box::new(task.clone()),
+                                }),
                             }
                         }
 
@@ -388,41 +748,39 @@ pub fn start_background_sync(app_handle: AppHandle) {
                             }
                         }
 
-                        if !changes.is_empty() {
-                            let from_revision = revision;
-                            revision = revision.saturating_add(1);
-                            seq = seq.saturating_add(1);
-
-                            let delta_event = TaskDeltaEvent::Delta {
-                                from_revision,
-                                to_revision: revision,
-                                seq,
-                                changes,
-                            };
-
-                            if let Err(e) = app_handle.emit(EVENT_TASKS_DELTA, delta_event) {
-                                crate::app_warn!(
-                                    "Core::Sync",
-                                    "tasks_delta_emit_failed",
-                                    json!({ "error": e.to_string() })
-                                );
-                            } else {
-                                last_snapshot = current_map;
-                            }
+                        if !changes.is_empty()
+                            && emit_delta(&app_handle, &mut revision, &mut seq, changes)
+                        {
+                            last_snapshot = current_map;
                         }
 
+                        let mut newly_completed = Vec::new();
                         for task in tasks.iter() {
                             if task.state == "complete" {
                                 if notified_completed.insert(task.id.clone()) {
                                     let _ = app_handle.emit(
                                         EVENT_TASK_COMPLETED,
-                                        json!({ "id": task.id, "filename": task.filename })
+                                        json!({ "id": task.id, "filename": task.filename }),
                                     );
+                                    if let Some(persisted) = state.get_task(&task.id) {
+                                        newly_completed.push(persisted);
+                                    }
                                 }
                             } else {
                                 notified_completed.remove(&task.id);
                             }
                         }
+
+                        // 完成后动作：队列是否已清空决定全局动作与电源动作是否触发
+                        let queue_drained = !tasks
+                            .iter()
+                            .any(|t| t.state == "active" || t.state == "waiting");
+                        completion::handle_completions(
+                            &app_handle,
+                            &newly_completed,
+                            queue_drained,
+                            &config,
+                        );
                     }
                 }
                 Err(e) => {
@@ -435,14 +793,247 @@ pub fn start_background_sync(app_handle: AppHandle) {
                 }
             }
 
-            // 自适应休眠
-            let sleep_duration = if has_active_tasks {
-                std::time::Duration::from_millis(200)
-            } else {
-                std::time::Duration::from_secs(2)
-            };
+            // 全局统计驱动托盘速度显示，变化时推送给前端
+            if let Ok(global) = stats::fetch_global_stats(&backends).await {
+                let _ = crate::ui::tray::update_tray_icon_with_speed(
+                    app_handle.clone(),
+                    global.download_speed,
+                    global.upload_speed,
+                )
+                .await;
+
+                if last_stats.as_ref() != Some(&global) {
+                    if let Err(e) = app_handle.emit(EVENT_GLOBAL_STATS, &global) {
+                        crate::app_warn!(
+                            "Core::Sync",
+                            "global_stats_emit_failed",
+                            json!({ "error": e.to_string() })
+                        );
+                    } else {
+                        last_stats = Some(global);
+                    }
+                }
+            }
+
+            // 自适应休眠：收到 aria2 推送通知时立即唤醒
+            let sleep_duration = poll_interval(has_active_tasks, aria2_ws::is_connected());
 
-            tokio::time::sleep(sleep_duration).await;
+            tokio::select! {
+                _ = tokio::time::sleep(sleep_duration) => {}
+                Some(notification) = notifications.recv() => {
+                    crate::app_debug!(
+                        "Core::Sync",
+                        "woken_by_notification",
+                        json!({ "kind": format!("{:?}", notification.kind), "gid": notification.gid })
+                    );
+                    // 合并同一时刻到达的多条通知，只触发一次同步
+                    while notifications.try_recv().is_ok() {}
+                }
+            }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::backend::HttpBackend;
+    use crate::aria2::client::DEFAULT_BACKEND_ID;
+    use crate::aria2::mock::{persisted_task, temp_dir, MockAria2, MockTask, ScriptStep};
+    use std::sync::Arc;
+
+    fn store_with(tasks: Vec<PersistedTask>) -> TaskStore {
+        let store = TaskStore::new();
+        for task in tasks {
+            store.add_task(task);
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn sync_reflects_aria2_progress() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(
+            MockTask::new("a1", "active")
+                .with_file("/downloads", "a1.bin")
+                .with_progress(50, 200, 10),
+        );
+        let store = store_with(vec![persisted_task("a1", TaskState::Waiting)]);
+
+        let tasks = sync_tasks(&store, &backends, &AppConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].state, "active");
+        assert_eq!(tasks[0].completed, 50);
+        assert_eq!(tasks[0].total, 200);
+        assert_eq!(tasks[0].speed, 10);
+        assert_eq!(tasks[0].progress, 25.0);
+        assert_eq!(store.get_task("a1").unwrap().state, TaskState::Active);
+    }
+
+    #[tokio::test]
+    async fn scripted_completion_is_detected() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let dir = temp_dir("complete");
+        std::fs::write(dir.join("c1.bin"), b"done").unwrap();
+        let dir_str = dir.to_string_lossy().to_string();
+
+        aria2.insert(
+            MockTask::new("c1", "active")
+                .with_file(&dir_str, "c1.bin")
+                .with_progress(0, 4, 0),
+        );
+        aria2.script(
+            "c1",
+            vec![
+                ScriptStep::status("active").progress(2, 2),
+                ScriptStep::status("complete").progress(4, 0),
+            ],
+        );
+
+        let mut task = persisted_task("c1", TaskState::Waiting);
+        task.save_path = dir_str;
+        let store = store_with(vec![task]);
+        let config = AppConfig::default();
+
+        let first = sync_tasks(&store, &backends, &config).await.unwrap();
+        assert_eq!(first[0].state, "active");
+        assert_eq!(first[0].speed, 2);
+
+        let second = sync_tasks(&store, &backends, &config).await.unwrap();
+        assert_eq!(second[0].state, "complete");
+        assert_eq!(second[0].progress, 100.0);
+        assert!(store.get_task("c1").unwrap().completed_at.is_some());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn completed_task_without_file_becomes_missing() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let dir = temp_dir("missing");
+        let dir_str = dir.to_string_lossy().to_string();
+        aria2.insert(MockTask::new("m1", "complete").with_file(&dir_str, "m1.bin"));
+
+        let mut task = persisted_task("m1", TaskState::Active);
+        task.save_path = dir_str;
+        let store = store_with(vec![task]);
+
+        let tasks = sync_tasks(&store, &backends, &AppConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(tasks[0].state, "missing");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn pause_and_failure_are_reflected() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(MockTask::new("p1", "active").with_progress(1, 10, 5));
+        aria2.insert(MockTask::new("e1", "active").with_progress(1, 10, 5));
+        aria2.script(
+            "e1",
+            vec![ScriptStep::status("error").error(6, "connection reset")],
+        );
+        let store = store_with(vec![
+            persisted_task("p1", TaskState::Active),
+            persisted_task("e1", TaskState::Active),
+        ]);
+        aria2.set_status("p1", "paused");
+
+        let tasks = sync_tasks(&store, &backends, &AppConfig::default())
+            .await
+            .unwrap();
+        let find = |id: &str| tasks.iter().find(|t| t.id == id).unwrap();
+
+        assert_eq!(find("p1").state, "paused");
+        assert_eq!(find("e1").state, "error");
+        assert_eq!(find("e1").error_message, "connection reset");
+        assert_eq!(find("e1").error_kind, Some(TaskErrorKind::Network));
+        assert_eq!(find("p1").error_kind, None);
+    }
+
+    #[tokio::test]
+    async fn aria2_checksum_failure_marks_mismatch() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(MockTask::new("c1", "active").with_progress(10, 10, 0));
+        aria2.script(
+            "c1",
+            vec![ScriptStep::status("error").error(32, "checksum error")],
+        );
+        let store = store_with(vec![persisted_task("c1", TaskState::Active)]);
+
+        let tasks = sync_tasks(&store, &backends, &AppConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(tasks[0].error_kind, Some(TaskErrorKind::ChecksumMismatch));
+        assert_eq!(tasks[0].checksum_status, Some(ChecksumStatus::Mismatch));
+    }
+
+    #[tokio::test]
+    async fn task_lost_by_aria2_is_marked_error() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(MockTask::new("gone", "active").with_progress(1, 10, 5));
+        let store = store_with(vec![persisted_task("gone", TaskState::Active)]);
+        let config = AppConfig::default();
+
+        assert_eq!(
+            sync_tasks(&store, &backends, &config).await.unwrap()[0].state,
+            "active"
+        );
+
+        aria2.forget("gone");
+        let tasks = sync_tasks(&store, &backends, &config).await.unwrap();
+
+        assert_eq!(tasks[0].state, "error");
+        assert_eq!(tasks[0].speed, 0);
+        assert_eq!(tasks[0].error_kind, Some(TaskErrorKind::Interrupted));
+    }
+
+    #[tokio::test]
+    async fn waiting_tasks_follow_aria2_queue_order() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        aria2.insert(MockTask::new("q2", "waiting"));
+        aria2.insert(MockTask::new("q1", "waiting"));
+        aria2.insert(MockTask::new("run", "active"));
+
+        // 添加时间越新越靠前，但等待队列应以 aria2 为准
+        let mut q1 = persisted_task("q1", TaskState::Waiting);
+        q1.added_at = "2024-01-03T00:00:00+00:00".to_string();
+        let mut q2 = persisted_task("q2", TaskState::Waiting);
+        q2.added_at = "2024-01-02T00:00:00+00:00".to_string();
+        let store = store_with(vec![q1, q2, persisted_task("run", TaskState::Active)]);
+
+        let tasks = sync_tasks(&store, &backends, &AppConfig::default())
+            .await
+            .unwrap();
+        let order: Vec<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+
+        assert_eq!(order, vec!["run", "q2", "q1"]);
+        assert_eq!(store.get_task("q2").unwrap().queue_position, Some(0));
+        assert_eq!(store.get_task("q1").unwrap().queue_position, Some(1));
+    }
+
+    #[tokio::test]
+    async fn unreachable_default_backend_fails_sync() {
+        let backends = BackendRegistry::new();
+        // 指向无人监听的端口
+        backends.register(DEFAULT_BACKEND_ID, Arc::new(HttpBackend::local(1, None)));
+
+        let store = store_with(vec![persisted_task("x1", TaskState::Active)]);
+        assert!(sync_tasks(&store, &backends, &AppConfig::default())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! 任务数据库（SQLite）
+//! 每个任务一行：完整记录以 JSON 存于 `data` 列，新增字段无需改表；
+//! 状态、后端与结束时间冗余为独立列并建立索引，供历史清理等查询使用。
+
+use chrono::DateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::core::config::HistoryRetention;
+use crate::core::error::AppResult;
+use crate::core::migrations;
+use crate::core::store::PersistedTask;
+
+/// 记录格式版本，记录在 `PRAGMA user_version`（0 表示新建的空库）
+const SCHEMA_VERSION: u32 = migrations::TASK_RECORD_VERSION;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tasks (
+    gid         TEXT PRIMARY KEY,
+    state       TEXT NOT NULL,
+    backend_id  TEXT NOT NULL,
+    added_at    TEXT NOT NULL,
+    finished_at INTEGER,
+    data        TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_tasks_history ON tasks (state, finished_at);
+CREATE INDEX IF NOT EXISTS idx_tasks_backend ON tasks (backend_id);
+CREATE TABLE IF NOT EXISTS corrupt_tasks (
+    gid            TEXT NOT NULL,
+    data           TEXT NOT NULL,
+    quarantined_at INTEGER NOT NULL
+);
+";
+
+/// 受保留策略约束的历史记录：已结束的任务，但等待自动重试的失败任务除外
+const HISTORY_FILTER: &str = "state IN ('complete', 'error', 'removed', 'missing')
+    AND json_extract(data, '$.next_retry_at') IS NULL";
+
+pub struct TaskDb {
+    conn: Connection,
+}
+
+impl TaskDb {
+    pub fn open(path: &Path) -> AppResult<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> AppResult<Self> {
+        conn.execute_batch(SCHEMA)?;
+        let mut db = Self { conn };
+        let version: u32 = db
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > 0 && version < SCHEMA_VERSION {
+            db.migrate_rows(version)?;
+        }
+        if version < SCHEMA_VERSION {
+            db.conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        Ok(db)
+    }
+
+    /// 在单个事务中将所有行升级到当前记录格式；无法解析的行保持原样，由 `load_all` 隔离
+    fn migrate_rows(&mut self, from: u32) -> AppResult<()> {
+        let tx = self.conn.transaction()?;
+        let rows: Vec<(String, String)> = {
+            let mut stmt = tx.prepare("SELECT gid, data FROM tasks")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        let mut migrated = 0;
+        for (gid, data) in rows {
+            let Ok(mut record) = serde_json::from_str::<Value>(&data) else {
+                continue;
+            };
+            if migrations::migrate(&mut record, from, migrations::TASK_MIGRATIONS, "tasks").is_err() {
+                continue;
+            }
+            tx.execute(
+                "UPDATE tasks SET data = ?2 WHERE gid = ?1",
+                params![gid, record.to_string()],
+            )?;
+            migrated += 1;
+        }
+        tx.commit()?;
+
+        crate::app_info!(
+            "Core::TaskDb",
+            "rows_migrated",
+            json!({ "from": from, "to": SCHEMA_VERSION, "count": migrated })
+        );
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> AppResult<bool> {
+        let any: Option<i64> = self
+            .conn
+            .query_row("SELECT 1 FROM tasks LIMIT 1", [], |row| row.get(0))
+            .optional()?;
+        Ok(any.is_none())
+    }
+
+    /// 读取全部任务。无法解析的行移入 `corrupt_tasks` 表隔离，不影响其余任务；
+    /// 返回 (任务, 被隔离的行数)
+    pub fn load_all(&mut self) -> AppResult<(Vec<PersistedTask>, usize)> {
+        let mut tasks = Vec::new();
+        let mut corrupt: Vec<String> = Vec::new();
+        {
+            let mut stmt = self.conn.prepare("SELECT gid, data FROM tasks ORDER BY added_at DESC")?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+            for row in rows {
+                let (gid, data) = row?;
+                match serde_json::from_str::<PersistedTask>(&data) {
+                    Ok(task) => tasks.push(task),
+                    Err(e) => {
+                        crate::app_warn!(
+                            "Core::TaskDb",
+                            "task_row_corrupt",
+                            json!({ "gid": gid, "error": e.to_string() })
+                        );
+                        corrupt.push(gid);
+                    }
+                }
+            }
+        }
+
+        if !corrupt.is_empty() {
+            let now = chrono::Local::now().timestamp();
+            let tx = self.conn.transaction()?;
+            for gid in &corrupt {
+                tx.execute(
+                    "INSERT INTO corrupt_tasks (gid, data, quarantined_at)
+                     SELECT gid, data, ?2 FROM tasks WHERE gid = ?1",
+                    params![gid, now],
+                )?;
+                tx.execute("DELETE FROM tasks WHERE gid = ?1", params![gid])?;
+            }
+            tx.commit()?;
+        }
+
+        Ok((tasks, corrupt.len()))
+    }
+
+    /// 将数据库一致地导出到 `path`（目标文件必须不存在）
+    pub fn backup_to(&self, path: &Path) -> AppResult<()> {
+        self.conn
+            .execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// 在单个事务中写入变更的任务并删除已移除的任务
+    pub fn write<'a>(
+        &mut self,
+        upserts: impl IntoIterator<Item = &'a PersistedTask>,
+        deletes: impl IntoIterator<Item = &'a String>,
+    ) -> AppResult<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut upsert = tx.prepare_cached(
+                "INSERT INTO tasks (gid, state, backend_id, added_at, finished_at, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(gid) DO UPDATE SET
+                    state = excluded.state,
+                    backend_id = excluded.backend_id,
+                    added_at = excluded.added_at,
+                    finished_at = excluded.finished_at,
+                    data = excluded.data",
+            )?;
+            for task in upserts {
+                let data = serde_json::to_string(task)?;
+                upsert.execute(params![
+                    task.gid,
+                    task.state.as_str(),
+                    task.backend_id,
+                    task.added_at,
+                    finished_at(task),
+                    data
+                ])?;
+            }
+
+            let mut delete = tx.prepare_cached("DELETE FROM tasks WHERE gid = ?1")?;
+            for gid in deletes {
+                delete.execute(params![gid])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 按保留策略找出应清理的历史任务（已结束超过期限，或超出保留条数的较旧记录）
+    pub fn expired_history(&self, policy: &HistoryRetention, now: i64) -> AppResult<Vec<String>> {
+        let mut gids: Vec<String> = Vec::new();
+
+        if policy.max_age_days > 0 {
+            let cutoff = now - i64::from(policy.max_age_days) * 86_400;
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT gid FROM tasks WHERE {HISTORY_FILTER} AND finished_at < ?1"
+            ))?;
+            let rows = stmt.query_map(params![cutoff], |row| row.get::<_, String>(0))?;
+            for gid in rows {
+                gids.push(gid?);
+            }
+        }
+
+        if policy.max_count > 0 {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT gid FROM tasks WHERE {HISTORY_FILTER}
+                 ORDER BY finished_at DESC LIMIT -1 OFFSET ?1"
+            ))?;
+            let rows = stmt.query_map(params![policy.max_count], |row| row.get::<_, String>(0))?;
+            for gid in rows {
+                let gid = gid?;
+                if !gids.contains(&gid) {
+                    gids.push(gid);
+                }
+            }
+        }
+
+        Ok(gids)
+    }
+}
+
+/// 已结束任务的结束时间（Unix 秒），缺少完成时间时退回添加时间；未结束任务为 None
+fn finished_at(task: &PersistedTask) -> Option<i64> {
+    if !task.state.is_terminal() {
+        return None;
+    }
+    task.completed_at
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .or_else(|| DateTime::parse_from_rfc3339(&task.added_at).ok())
+        .map(|at| at.timestamp())
+}
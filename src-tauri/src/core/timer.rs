@@ -0,0 +1,291 @@
+//! 任务定时启动与停止
+//! 设置了 `start_at` 的任务以暂停状态加入 aria2，到期后由后台循环恢复；
+//! `stop_at` 到期时暂停仍在进行的任务。时间保存在任务记录中，应用重启后继续生效，
+//! 重启期间错过的时间点会在启动后的第一轮检查中补执行；启动与停止时间都已错过时任务保持暂停。
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde_json::json;
+
+use crate::aria2::backend::BackendRegistry;
+use crate::core::commands::smart_resume_task;
+use crate::core::error::{AppError, AppResult};
+use crate::core::store::{PersistedTask, TaskStore};
+use crate::core::types::{DownloadConfig, TaskState};
+
+fn parse_time(raw: &str) -> AppResult<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(raw.trim())
+        .map_err(|_| AppError::validation(format!("无效的时间: {}", raw)))
+}
+
+fn is_due(at: Option<&str>, now: DateTime<Local>) -> bool {
+    at.and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .is_some_and(|at| at <= now)
+}
+
+/// 校验并规范化下载配置中的定时设置：空字符串视为未设置，
+/// 已过去的启动时间视为立即启动；停止时间必须晚于当前时间与启动时间。
+pub fn normalize(cfg: &mut DownloadConfig, now: DateTime<Local>) -> AppResult<()> {
+    cfg.start_at = cfg.start_at.take().filter(|v| !v.trim().is_empty());
+    cfg.stop_at = cfg.stop_at.take().filter(|v| !v.trim().is_empty());
+
+    let start = cfg.start_at.as_deref().map(parse_time).transpose()?;
+    let stop = cfg.stop_at.as_deref().map(parse_time).transpose()?;
+
+    if let Some(stop) = stop {
+        if stop <= now {
+            return Err(AppError::validation("定时停止时间已过"));
+        }
+        if start.is_some_and(|start| stop <= start) {
+            return Err(AppError::validation("定时停止时间必须晚于启动时间"));
+        }
+    }
+    if start.is_some_and(|start| start <= now) {
+        cfg.start_at = None;
+    }
+    Ok(())
+}
+
+/// 执行所有已到期的定时启动与停止，返回被处理的任务 GID
+pub async fn run_due_timers(
+    store: &TaskStore,
+    registry: &BackendRegistry,
+    now: DateTime<Local>,
+) -> Vec<String> {
+    let due: Vec<PersistedTask> = store
+        .get_all()
+        .into_iter()
+        .filter(|t| is_due(t.start_at.as_deref(), now) || is_due(t.stop_at.as_deref(), now))
+        .collect();
+
+    let mut handled = Vec::new();
+    for task in due {
+        let start_due = is_due(task.start_at.as_deref(), now);
+        let stop_due = is_due(task.stop_at.as_deref(), now);
+
+        if start_due && stop_due {
+            // 启动与停止时间都已错过（如应用关闭期间），时间窗口已结束：不再启动，
+            // 仅清除启动时间，停止按任务当前状态处理
+            store.update_task(&task.gid, |t| t.start_at = None);
+            if stop_task(store, registry, &task).await {
+                handled.push(task.gid.clone());
+            }
+            continue;
+        }
+
+        if start_due && start_task(store, registry, &task).await {
+            handled.push(task.gid.clone());
+        }
+        if stop_due && stop_task(store, registry, &task).await {
+            handled.push(task.gid.clone());
+        }
+    }
+    handled
+}
+
+/// 恢复到期的任务。任务已不是暂停状态（用户已手动启动或已结束）时仅清除定时。
+/// 返回 false 表示后端暂时不可用，保留定时等待下一轮。
+async fn start_task(store: &TaskStore, registry: &BackendRegistry, task: &PersistedTask) -> bool {
+    if task.state != TaskState::Paused {
+        store.update_task(&task.gid, |t| t.start_at = None);
+        return true;
+    }
+
+    let result = match registry.get(&task.backend_id) {
+        Ok(backend) => backend.resume(task.gid.clone()).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(_) => {
+            crate::app_info!("Core::Timer", "scheduled_start", json!({ "gid": task.gid }));
+            store.update_task(&task.gid, |t| {
+                t.start_at = None;
+                t.transition_state(TaskState::Waiting);
+            });
+            true
+        }
+        // aria2 重启后任务丢失（如会话未保存），重新提交
+        Err(e) if e.is_aria2_not_found() => {
+            store.update_task(&task.gid, |t| t.start_at = None);
+            match smart_resume_task(store, registry, task.gid.clone()).await {
+                Ok(new_gid) => {
+                    crate::app_info!(
+                        "Core::Timer",
+                        "scheduled_start_readded",
+                        json!({ "old_gid": task.gid, "new_gid": new_gid })
+                    );
+                    true
+                }
+                Err(error) => {
+                    crate::app_warn!(
+                        "Core::Timer",
+                        "scheduled_start_failed",
+                        json!({ "gid": task.gid, "error": error.to_string() })
+                    );
+                    false
+                }
+            }
+        }
+        Err(e) => {
+            crate::app_warn!(
+                "Core::Timer",
+                "scheduled_start_failed",
+                json!({ "gid": task.gid, "error": e.to_string() })
+            );
+            false
+        }
+    }
+}
+
+/// 暂停到期的任务。任务未在进行时仅清除定时。
+async fn stop_task(store: &TaskStore, registry: &BackendRegistry, task: &PersistedTask) -> bool {
+    if !matches!(task.state, TaskState::Active | TaskState::Waiting) {
+        store.update_task(&task.gid, |t| t.stop_at = None);
+        return true;
+    }
+
+    let result = match registry.get(&task.backend_id) {
+        Ok(backend) => backend.pause(task.gid.clone()).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(_) => {
+            crate::app_info!("Core::Timer", "scheduled_stop", json!({ "gid": task.gid }));
+            store.update_task(&task.gid, |t| {
+                t.stop_at = None;
+                t.transition_state(TaskState::Paused);
+            });
+            true
+        }
+        Err(e) if e.is_aria2_not_found() => {
+            store.update_task(&task.gid, |t| t.stop_at = None);
+            true
+        }
+        Err(e) => {
+            crate::app_warn!(
+                "Core::Timer",
+                "scheduled_stop_failed",
+                json!({ "gid": task.gid, "error": e.to_string() })
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aria2::mock::{persisted_task, MockAria2, MockTask};
+    use chrono::Duration;
+
+    fn download(start_at: Option<String>, stop_at: Option<String>) -> DownloadConfig {
+        DownloadConfig {
+            urls: vec!["https://example.com/a.iso".to_string()],
+            start_at,
+            stop_at,
+            ..DownloadConfig::default()
+        }
+    }
+
+    #[test]
+    fn normalizes_schedule() {
+        let now = Local::now();
+        let later = (now + Duration::hours(2)).to_rfc3339();
+        let past = (now - Duration::hours(2)).to_rfc3339();
+
+        let mut cfg = download(Some(past.clone()), Some(later.clone()));
+        normalize(&mut cfg, now).unwrap();
+        assert_eq!(cfg.start_at, None);
+        assert_eq!(cfg.stop_at, Some(later.clone()));
+
+        let mut cfg = download(Some(later.clone()), Some(String::new()));
+        normalize(&mut cfg, now).unwrap();
+        assert_eq!(cfg.start_at, Some(later.clone()));
+        assert_eq!(cfg.stop_at, None);
+
+        assert!(normalize(&mut download(None, Some(past)), now).is_err());
+        assert!(normalize(&mut download(Some(later.clone()), Some(later)), now).is_err());
+        assert!(normalize(&mut download(Some("2 AM".to_string()), None), now).is_err());
+    }
+
+    #[tokio::test]
+    async fn starts_and_stops_due_tasks() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        let now = Local::now();
+
+        aria2.insert(MockTask::new("a", "paused"));
+        let mut start_due = persisted_task("a", TaskState::Paused);
+        start_due.start_at = Some((now - Duration::minutes(1)).to_rfc3339());
+        store.add_task(start_due);
+
+        aria2.insert(MockTask::new("b", "paused"));
+        let mut start_later = persisted_task("b", TaskState::Paused);
+        start_later.start_at = Some((now + Duration::hours(1)).to_rfc3339());
+        store.add_task(start_later);
+
+        aria2.insert(MockTask::new("c", "active"));
+        let mut stop_due = persisted_task("c", TaskState::Active);
+        stop_due.stop_at = Some(now.to_rfc3339());
+        store.add_task(stop_due);
+
+        let mut handled = run_due_timers(&store, &backends, now).await;
+        handled.sort();
+        assert_eq!(handled, vec!["a".to_string(), "c".to_string()]);
+
+        assert_eq!(aria2.task("a").unwrap().status, "waiting");
+        let started = store.get_task("a").unwrap();
+        assert_eq!(started.state, TaskState::Waiting);
+        assert_eq!(started.start_at, None);
+
+        assert_eq!(aria2.task("b").unwrap().status, "paused");
+        assert!(store.get_task("b").unwrap().start_at.is_some());
+
+        assert_eq!(aria2.task("c").unwrap().status, "paused");
+        assert_eq!(store.get_task("c").unwrap().stop_at, None);
+    }
+
+    #[tokio::test]
+    async fn manually_started_task_only_clears_timer() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        let now = Local::now();
+
+        aria2.insert(MockTask::new("a", "active"));
+        let mut task = persisted_task("a", TaskState::Active);
+        task.start_at = Some(now.to_rfc3339());
+        store.add_task(task);
+
+        run_due_timers(&store, &backends, now).await;
+
+        assert!(aria2.calls("aria2.unpause").is_empty());
+        assert_eq!(store.get_task("a").unwrap().start_at, None);
+    }
+
+    #[tokio::test]
+    async fn missed_window_keeps_task_paused() {
+        let aria2 = MockAria2::start().await;
+        let backends = aria2.registry();
+        let store = TaskStore::new();
+        let now = Local::now();
+
+        aria2.insert(MockTask::new("a", "paused"));
+        let mut task = persisted_task("a", TaskState::Paused);
+        task.start_at = Some((now - Duration::hours(2)).to_rfc3339());
+        task.stop_at = Some((now - Duration::hours(1)).to_rfc3339());
+        store.add_task(task);
+
+        let handled = run_due_timers(&store, &backends, now).await;
+        assert_eq!(handled, vec!["a".to_string()]);
+
+        assert!(aria2.calls("aria2.unpause").is_empty());
+        assert_eq!(aria2.task("a").unwrap().status, "paused");
+        let task = store.get_task("a").unwrap();
+        assert_eq!(task.state, TaskState::Paused);
+        assert_eq!(task.start_at, None);
+        assert_eq!(task.stop_at, None);
+    }
+}
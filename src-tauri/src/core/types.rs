@@ -102,8 +102,145 @@ impl From<String> for TaskState {
     }
 }
 
+/// 任务失败原因分类，由 aria2 的错误码（与进程退出码一致）映射而来。
+/// 与前端 `src/lib/types/download.ts::TaskErrorKind` 保持一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskErrorKind {
+    /// 未知错误 (1)
+    Unknown,
+    /// 超时 (2)
+    Timeout,
+    /// 资源不存在，如 HTTP 404 (3, 4)
+    NotFound,
+    /// 速度低于 lowest-speed-limit 被中止 (5)
+    TooSlow,
+    /// 网络问题 (6)
+    Network,
+    /// aria2 退出时仍未完成 (7)
+    Interrupted,
+    /// 服务器不支持断点续传 (8)
+    ResumeUnsupported,
+    /// 磁盘空间不足 (9)
+    DiskFull,
+    /// 分片长度与 .aria2 控制文件不一致 (10)
+    ControlFileMismatch,
+    /// 相同文件或种子已在下载 (11, 12)
+    Duplicate,
+    /// 文件已存在且不允许覆盖 (13)
+    FileExists,
+    /// 文件读写、重命名或建目录失败 (14 - 18)
+    FileIo,
+    /// 域名解析失败 (19)
+    NameResolution,
+    /// Metalink / 种子 / 磁力链接无效 (20, 25, 26, 27)
+    BadMetadata,
+    /// FTP/HTTP 协议异常或重定向过多 (21, 22, 23)
+    Protocol,
+    /// 认证失败 (24)
+    AuthFailed,
+    /// 无效的 aria2 选项 (28)
+    BadOption,
+    /// 服务器暂时过载，如 HTTP 503 (29)
+    ServerBusy,
+    /// 校验和不匹配 (32)
+    ChecksumMismatch,
+}
+
+impl TaskErrorKind {
+    /// 从 aria2 的 errorCode 映射，"0"（成功）或无法解析时返回 None
+    pub fn from_aria2_code(code: &str) -> Option<Self> {
+        let kind = match code.trim().parse::<u32>().ok()? {
+            0 => return None,
+            2 => Self::Timeout,
+            3 | 4 => Self::NotFound,
+            5 => Self::TooSlow,
+            6 => Self::Network,
+            7 => Self::Interrupted,
+            8 => Self::ResumeUnsupported,
+            9 => Self::DiskFull,
+            10 => Self::ControlFileMismatch,
+            11 | 12 => Self::Duplicate,
+            13 => Self::FileExists,
+            14..=18 => Self::FileIo,
+            19 => Self::NameResolution,
+            20 | 25..=27 => Self::BadMetadata,
+            21..=23 => Self::Protocol,
+            24 => Self::AuthFailed,
+            28 => Self::BadOption,
+            29 => Self::ServerBusy,
+            32 => Self::ChecksumMismatch,
+            _ => Self::Unknown,
+        };
+        Some(kind)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Timeout => "timeout",
+            Self::NotFound => "not_found",
+            Self::TooSlow => "too_slow",
+            Self::Network => "network",
+            Self::Interrupted => "interrupted",
+            Self::ResumeUnsupported => "resume_unsupported",
+            Self::DiskFull => "disk_full",
+            Self::ControlFileMismatch => "control_file_mismatch",
+            Self::Duplicate => "duplicate",
+            Self::FileExists => "file_exists",
+            Self::FileIo => "file_io",
+            Self::NameResolution => "name_resolution",
+            Self::BadMetadata => "bad_metadata",
+            Self::Protocol => "protocol",
+            Self::AuthFailed => "auth_failed",
+            Self::BadOption => "bad_option",
+            Self::ServerBusy => "server_busy",
+            Self::ChecksumMismatch => "checksum_mismatch",
+        }
+    }
+}
+
+impl std::fmt::Display for TaskErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// 下载完成后执行的动作。
+/// 运行命令与打开文件在对应任务完成时执行；睡眠与关机总是等到没有进行中的任务后，
+/// 经过倒计时再执行。与前端 `src/lib/types/download.ts::CompletionAction` 保持一致
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CompletionAction {
+    #[default]
+    None,
+    /// 运行命令，参数中的 `{path}`、`{filename}`、`{gid}` 会被替换
+    RunCommand { command: String },
+    /// 用默认程序打开文件
+    OpenFile,
+    /// 在文件管理器中显示文件
+    RevealFile,
+    Sleep,
+    Shutdown,
+}
+
+impl CompletionAction {
+    /// 睡眠、关机等影响整机的动作
+    pub fn is_power(&self) -> bool {
+        matches!(self, Self::Sleep | Self::Shutdown)
+    }
+}
+
+/// 文件校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumStatus {
+    Verified,
+    Mismatch,
+}
+
 /// 下载任务配置（用于前端传参）
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct DownloadConfig {
     pub urls: Vec<String>,
@@ -115,6 +252,29 @@ pub struct DownloadConfig {
     pub proxy: Option<String>,
     pub max_download_limit: Option<String>,
     pub torrent_config: Option<TorrentDownloadConfig>,
+    /// 从本地 Metalink 文件添加（`.meta4` / `.metalink`）
+    #[serde(default)]
+    pub metalink_config: Option<MetalinkDownloadConfig>,
+    /// 目标 aria2 后端，缺省为默认后端
+    #[serde(default)]
+    pub backend_id: Option<String>,
+    /// 所属分类 ID，未指定的保存目录、限速与 UA 取分类默认值
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 定时启动时间（RFC 3339），在此之前任务以暂停状态加入 aria2
+    #[serde(default)]
+    pub start_at: Option<String>,
+    /// 定时停止（暂停）时间（RFC 3339）
+    #[serde(default)]
+    pub stop_at: Option<String>,
+    /// 任务完成后执行的动作，未设置时仅使用全局设置
+    #[serde(default)]
+    pub on_complete: Option<CompletionAction>,
+    /// 期望的校验值，格式为 `sha-1=<hex>`、`sha-256=<hex>` 或 `md5=<hex>`
+    #[serde(default)]
+    pub checksum: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -124,3 +284,67 @@ pub struct TorrentDownloadConfig {
     pub select_file: Option<String>,
     pub trackers: Option<String>,
 }
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MetalinkDownloadConfig {
+    pub path: String,
+    /// 要下载的文件序号（从 1 开始，如 `1,3-5`），缺省下载全部
+    pub select_file: Option<String>,
+}
+
+/// 运行中任务的选项修改（None 表示不修改，空字符串表示清除）
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskOptionsPatch {
+    pub user_agent: Option<String>,
+    pub referer: Option<String>,
+    pub headers: Option<String>,
+    pub proxy: Option<String>,
+    pub max_download_limit: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_aria2_error_codes_to_kinds() {
+        let cases = [
+            ("0", None),
+            ("1", Some(TaskErrorKind::Unknown)),
+            ("2", Some(TaskErrorKind::Timeout)),
+            ("3", Some(TaskErrorKind::NotFound)),
+            ("4", Some(TaskErrorKind::NotFound)),
+            ("5", Some(TaskErrorKind::TooSlow)),
+            ("6", Some(TaskErrorKind::Network)),
+            ("7", Some(TaskErrorKind::Interrupted)),
+            ("8", Some(TaskErrorKind::ResumeUnsupported)),
+            ("9", Some(TaskErrorKind::DiskFull)),
+            ("10", Some(TaskErrorKind::ControlFileMismatch)),
+            ("11", Some(TaskErrorKind::Duplicate)),
+            ("12", Some(TaskErrorKind::Duplicate)),
+            ("13", Some(TaskErrorKind::FileExists)),
+            ("14", Some(TaskErrorKind::FileIo)),
+            ("18", Some(TaskErrorKind::FileIo)),
+            ("19", Some(TaskErrorKind::NameResolution)),
+            ("20", Some(TaskErrorKind::BadMetadata)),
+            ("21", Some(TaskErrorKind::Protocol)),
+            ("23", Some(TaskErrorKind::Protocol)),
+            ("24", Some(TaskErrorKind::AuthFailed)),
+            ("25", Some(TaskErrorKind::BadMetadata)),
+            ("27", Some(TaskErrorKind::BadMetadata)),
+            ("28", Some(TaskErrorKind::BadOption)),
+            ("29", Some(TaskErrorKind::ServerBusy)),
+            ("30", Some(TaskErrorKind::Unknown)),
+            ("32", Some(TaskErrorKind::ChecksumMismatch)),
+            (" 6 ", Some(TaskErrorKind::Network)),
+            ("", None),
+            ("abc", None),
+        ];
+        for (code, expected) in cases {
+            let kind = TaskErrorKind::from_aria2_code(code);
+            assert_eq!(kind, expected, "code {}", code);
+        }
+    }
+}
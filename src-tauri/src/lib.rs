@@ -36,6 +36,10 @@ pub fn run() {
             std::sync::atomic::AtomicBool::new(false),
         ))
         .manage(crate::core::store::TaskStore::new()) // Initialize TaskStore
+        .manage(crate::aria2::backend::BackendRegistry::new())
+        .manage(crate::core::recovery::RecoveryLog::default())
+        .manage(crate::core::bandwidth::BandwidthState::default())
+        .manage(crate::core::completion::CompletionState::default())
         .setup(|app| {
             crate::core::boot::run(app)?;
             Ok(())
@@ -50,21 +54,43 @@ pub fn run() {
             get_aria2_config_path,
             read_aria2_config,
             import_aria2_config,
+            get_global_stats,
             update_tray_icon_with_speed,
             get_app_config,
             save_app_config,
+            get_recovery_reports,
+            get_bandwidth_profile,
+            set_bandwidth_override,
+            set_turtle_mode,
+            cancel_power_action,
             show_main_window,
             show_task_in_folder,
             pause_all_tasks,
             resume_all_tasks,
             remove_tasks,
             cancel_tasks,
+            change_task_options,
+            get_task_options,
+            set_task_selected_files,
+            set_task_category,
+            set_task_tags,
+            set_task_completion_action,
+            verify_task_file,
+            move_task_to_top,
+            move_task_to_bottom,
+            move_task_up,
+            move_task_down,
+            get_task_peers,
+            get_task_servers,
             start_log_stream,
             stop_log_stream,
             import_custom_binary,
             trust_custom_aria2_binary,
             get_aria2_version_info,
             parse_torrent,
+            parse_metalink,
+            parse_magnet,
+            preview_download_rules,
             fetch_public_trackers
             ,get_platform_info
         ])
@@ -1,6 +1,12 @@
+use crate::aria2::backend::BackendRegistry;
+use crate::core::bandwidth::{self, BandwidthState};
+use crate::core::commands::resume_paused_tasks;
+use crate::core::config::ConfigState;
+use crate::core::store::TaskStore;
 use image::{GenericImage, Rgba, RgbaImage};
 use rusttype::{Font, Point, Scale};
 use std::sync::Mutex;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
 use tauri::{AppHandle, Manager, Runtime};
 
 pub struct TrayState {
@@ -119,20 +125,10 @@ pub fn setup_tray<R: Runtime>(app: &tauri::App<R>) -> Result<(), Box<dyn std::er
     #[cfg(desktop)]
     {
         use tauri::{
-            menu::{Menu, MenuItem},
             tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
             Manager,
         };
-        let quit_i = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-        let show_i = MenuItem::with_id(app, "show", "显示 Mua", true, None::<&str>)?;
-        let pause_all_i = MenuItem::with_id(app, "pause_all", "暂停所有", true, None::<&str>)?;
-        let resume_all_i = MenuItem::with_id(app, "resume_all", "恢复所有", true, None::<&str>)?;
-        let sep = tauri::menu::PredefinedMenuItem::separator(app)?;
-
-        let menu = Menu::with_items(
-            app,
-            &[&show_i, &sep, &pause_all_i, &resume_all_i, &sep, &quit_i],
-        )?;
+        let menu = build_menu(handle)?;
 
         let _ = TrayIconBuilder::with_id("tray")
             .icon(
@@ -149,17 +145,58 @@ pub fn setup_tray<R: Runtime>(app: &tauri::App<R>) -> Result<(), Box<dyn std::er
                     }
                 }
                 "pause_all" => {
-                    tauri::async_runtime::spawn(async {
-                        let _ = crate::aria2::client::pause_all().await;
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let backends = app.state::<BackendRegistry>();
+                        for id in backends.ids() {
+                            if let Ok(backend) = backends.get(&id) {
+                                let _ = backend.pause_all().await;
+                            }
+                        }
                     });
                 }
                 "resume_all" => {
-                    tauri::async_runtime::spawn(async {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
                         crate::app_info!("UI::Tray", "resume_all_clicked");
-                        let _ = crate::aria2::client::unpause_all().await;
+                        let store = app.state::<TaskStore>();
+                        let backends = app.state::<BackendRegistry>();
+                        let _ = resume_paused_tasks(&store, &backends).await;
                     });
                 }
-                _ => {}
+                TURTLE_MODE_ID => {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let enabled = !bandwidth::turtle_enabled(&app);
+                        if let Err(e) = bandwidth::set_turtle(&app, enabled).await {
+                            crate::app_warn!(
+                                "UI::Tray",
+                                "turtle_mode_toggle_failed",
+                                serde_json::json!({ "error": e.to_string() })
+                            );
+                        }
+                    });
+                }
+                BANDWIDTH_FOLLOW_ID => {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let _ = bandwidth::set_manual(&app, None).await;
+                    });
+                }
+                id => {
+                    if let Some(profile_id) = id.strip_prefix(BANDWIDTH_PROFILE_PREFIX) {
+                        let app = app.clone();
+                        let profile_id = profile_id.to_string();
+                        tauri::async_runtime::spawn(async move {
+                            crate::app_info!(
+                                "UI::Tray",
+                                "bandwidth_profile_selected",
+                                serde_json::json!({ "profile_id": profile_id })
+                            );
+                            let _ = bandwidth::set_manual(&app, Some(profile_id)).await;
+                        });
+                    }
+                }
             })
             .on_tray_icon_event(|tray, event| {
                 if let TrayIconEvent::Click {
@@ -180,6 +217,142 @@ pub fn setup_tray<R: Runtime>(app: &tauri::App<R>) -> Result<(), Box<dyn std::er
     Ok(())
 }
 
+const TURTLE_MODE_ID: &str = "turtle_mode";
+const BANDWIDTH_FOLLOW_ID: &str = "bandwidth_follow";
+/// 乌龟模式角标颜色
+const TURTLE_BADGE_COLOR: Rgba<u8> = Rgba([76, 175, 80, 255]);
+const BANDWIDTH_PROFILE_PREFIX: &str = "bandwidth_profile:";
+
+/// 构建托盘菜单。限速方案部分依赖配置，配置尚未加载时只显示全局设置。
+fn build_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
+    let quit_i = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+    let show_i = MenuItem::with_id(app, "show", "显示 Mua", true, None::<&str>)?;
+    let pause_all_i = MenuItem::with_id(app, "pause_all", "暂停所有", true, None::<&str>)?;
+    let resume_all_i = MenuItem::with_id(app, "resume_all", "恢复所有", true, None::<&str>)?;
+    let sep = PredefinedMenuItem::separator(app)?;
+
+    let profiles = app
+        .try_state::<ConfigState>()
+        .map(|c| c.snapshot().bandwidth_schedule.profiles)
+        .unwrap_or_default();
+    let bandwidth = app.try_state::<BandwidthState>();
+    let manual = bandwidth.as_ref().and_then(|b| b.manual());
+    let active_name = bandwidth
+        .as_ref()
+        .and_then(|b| b.applied())
+        .map(|p| p.name)
+        .unwrap_or_else(|| "全局设置".to_string());
+
+    let turtle_i = CheckMenuItem::with_id(
+        app,
+        TURTLE_MODE_ID,
+        "乌龟模式（备用限速）",
+        true,
+        bandwidth::turtle_enabled(app),
+        None::<&str>,
+    )?;
+
+    // 当前方案仅作展示
+    let active_i = MenuItem::with_id(
+        app,
+        "bandwidth_active",
+        format!("限速方案：{}", active_name),
+        false,
+        None::<&str>,
+    )?;
+
+    let mut choices = vec![
+        CheckMenuItem::with_id(app, BANDWIDTH_FOLLOW_ID, "跟随计划", true, manual.is_none(), None::<&str>)?,
+        CheckMenuItem::with_id(
+            app,
+            format!("{}{}", BANDWIDTH_PROFILE_PREFIX, bandwidth::DEFAULT_PROFILE_ID),
+            "全局设置",
+            true,
+            manual.as_deref() == Some(bandwidth::DEFAULT_PROFILE_ID),
+            None::<&str>,
+        )?,
+    ];
+    for profile in &profiles {
+        choices.push(CheckMenuItem::with_id(
+            app,
+            format!("{}{}", BANDWIDTH_PROFILE_PREFIX, profile.id),
+            &profile.name,
+            true,
+            manual.as_deref() == Some(profile.id.as_str()),
+            None::<&str>,
+        )?);
+    }
+    let choice_refs: Vec<&dyn IsMenuItem<R>> =
+        choices.iter().map(|c| c as &dyn IsMenuItem<R>).collect();
+    let switch_i = Submenu::with_items(app, "切换限速方案", true, &choice_refs)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &show_i,
+            &sep,
+            &pause_all_i,
+            &resume_all_i,
+            &sep,
+            &turtle_i,
+            &active_i,
+            &switch_i,
+            &sep,
+            &quit_i,
+        ],
+    )
+}
+
+/// 限速方案或配置变化后重建托盘菜单
+pub fn refresh_menu<R: Runtime>(app: &AppHandle<R>) {
+    let Some(tray) = app.tray_by_id("tray") else {
+        return;
+    };
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => {
+            crate::app_warn!(
+                "UI::Tray",
+                "menu_rebuild_failed",
+                serde_json::json!({ "error": e.to_string() })
+            );
+        }
+    }
+}
+
+/// 清除图标去重缓存，使下一次速度更新时重新绘制（如切换乌龟模式后）
+pub fn invalidate_icon<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(state) = app.try_state::<Mutex<TrayState>>() {
+        if let Ok(mut state) = state.lock() {
+            state.last_update_key = None;
+        }
+    }
+}
+
+/// 在图标右下角绘制乌龟模式角标（带描边的实心圆）
+fn draw_turtle_badge(img: &mut RgbaImage, icon_size: u32, y_offset: u32) {
+    let radius = icon_size as f32 / 5.0;
+    let cx = icon_size as f32 - radius - 1.0;
+    let cy = y_offset as f32 + icon_size as f32 - radius - 1.0;
+    let outline = radius + 2.0;
+
+    let min_x = (cx - outline).floor().max(0.0) as u32;
+    let min_y = (cy - outline).floor().max(0.0) as u32;
+    for y in min_y..img.height().min((cy + outline).ceil() as u32) {
+        for x in min_x..img.width().min((cx + outline).ceil() as u32) {
+            let dist = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+            if dist <= radius {
+                img.put_pixel(x, y, TURTLE_BADGE_COLOR);
+            } else if dist <= outline {
+                // 深色描边，保证在浅色菜单栏上也清晰
+                img.put_pixel(x, y, Rgba([0, 0, 0, 160]));
+            }
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn update_tray_icon_with_speed(
     app: AppHandle,
@@ -236,8 +409,10 @@ pub async fn update_tray_icon_with_speed(
     let dl_text = format_speed_fixed(dl_speed);
     let ul_text = format_speed_fixed(ul_speed);
 
+    let turtle = bandwidth::turtle_enabled(&app);
+
     // 生成去重 Key
-    let update_key = format!("{}|{}", dl_text, ul_text);
+    let update_key = format!("{}|{}|{}", dl_text, ul_text, turtle);
 
     // 检查是否需要更新
     if let Some(last_key) = &state_guard.last_update_key {
@@ -252,7 +427,11 @@ pub async fn update_tray_icon_with_speed(
     let font = match &state_guard.font {
         Some(f) => f,
         None => {
-            let title = format!("{}  {}", dl_text, ul_text);
+            let title = if turtle {
+                format!("🐢 {}  {}", dl_text, ul_text)
+            } else {
+                format!("{}  {}", dl_text, ul_text)
+            };
             let _ = tray.set_title(Some(&title));
             return Ok(());
         }
@@ -292,10 +471,13 @@ pub async fn update_tray_icon_with_speed(
     let mut image = RgbaImage::new(total_width, height);
 
     // 1. 绘制左侧图标
+    let y_offset = (height - icon_size) / 2;
     if let Some(cached_icon) = &state_guard.icon_cache {
-        let y_offset = (height - icon_size) / 2;
         let _ = image.copy_from(cached_icon, 0, y_offset);
     }
+    if turtle {
+        draw_turtle_badge(&mut image, icon_size, y_offset);
+    }
 
     // 文本区域起始X坐标
     let text_area_start_x = icon_size + padding;
@@ -35,6 +35,26 @@ pub fn show_in_file_manager(path: &str) {
     }
 }
 
+pub fn open_with_default_app(path: &str) {
+    let resolved = resolve_path(path);
+
+    #[cfg(target_os = "macos")]
+    {
+        let _ = std::process::Command::new("open").arg(&resolved).spawn();
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // 由 explorer 通过 ShellExecute 打开，不经过 cmd.exe，文件名中的 & 等字符不会被解释
+        let _ = std::process::Command::new("explorer").arg(&resolved).spawn();
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let _ = std::process::Command::new("xdg-open").arg(&resolved).spawn();
+    }
+}
+
 pub fn get_full_path(save_path: &str, filename: &str) -> String {
     if save_path.is_empty() {
         return resolve_path(filename);
@@ -67,11 +87,22 @@ pub fn canonicalize_abs(path_str: &str) -> std::io::Result<std::path::PathBuf> {
 }
 
 pub fn sha256_hex_of_file(path: &std::path::Path) -> std::io::Result<String> {
-    use sha2::{Digest, Sha256};
+    hex_digest_of_file::<sha2::Sha256>(path)
+}
+
+pub fn sha1_hex_of_file(path: &std::path::Path) -> std::io::Result<String> {
+    hex_digest_of_file::<sha1::Sha1>(path)
+}
+
+pub fn md5_hex_of_file(path: &std::path::Path) -> std::io::Result<String> {
+    hex_digest_of_file::<md5::Md5>(path)
+}
+
+fn hex_digest_of_file<D: sha2::Digest>(path: &std::path::Path) -> std::io::Result<String> {
     use std::io::Read;
 
     let mut file = std::fs::File::open(path)?;
-    let mut hasher = Sha256::new();
+    let mut hasher = D::new();
     let mut buf = [0_u8; 16 * 1024];
 
     loop {
@@ -82,7 +113,7 @@ pub fn sha256_hex_of_file(path: &std::path::Path) -> std::io::Result<String> {
         hasher.update(&buf[..n]);
     }
 
-    Ok(format!("{:x}", hasher.finalize()))
+    Ok(hex::encode(hasher.finalize()))
 }
 
 pub fn is_valid_url(url: &str) -> bool {
@@ -121,17 +152,15 @@ pub fn deduce_filename(filename: Option<String>, urls: &[String]) -> String {
 
     // Try to extract from URL
     if let Some(first_url) = urls.get(0) {
-        if first_url.starts_with("magnet:") {
-            // 解析 dn 参数
-            if let Some(start) = first_url.find("dn=") {
-                let rest = &first_url[start + 3..];
-                let end = rest.find('&').unwrap_or(rest.len());
-                let dn = &rest[..end];
-                if let Ok(decoded) = urlencoding::decode(dn) {
-                    return decoded.to_string();
-                }
-            }
-            return "magnet_download".to_string();
+        if crate::core::magnet::is_magnet(first_url) {
+            // 优先使用 dn，缺失时以 infohash 命名
+            return match crate::core::magnet::parse_magnet(first_url) {
+                Ok(magnet) => match magnet.display_name {
+                    Some(name) => name,
+                    None => magnet.key().unwrap_or("magnet_download").to_string(),
+                },
+                Err(_) => "magnet_download".to_string(),
+            };
         }
 
         // Simple heuristic: take last part of path
@@ -189,77 +218,6 @@ pub fn get_unique_filename(save_path: &str, filename: &str, reserved_names: &[St
     name
 }
 
-pub fn build_aria2_options(
-    save_path: Option<String>,
-    filename: Option<String>,
-    user_agent: Option<String>,
-    referer: Option<String>,
-    headers: Option<String>,
-    proxy: Option<String>,
-    max_download_limit: Option<String>,
-) -> (serde_json::Value, String) {
-    let mut options = serde_json::Map::new();
-
-    let save_path_str = if let Some(dir) = save_path {
-        let p = resolve_path(&dir);
-        options.insert("dir".to_string(), serde_json::Value::String(p.clone()));
-        p
-    } else {
-        "".to_string()
-    };
-
-    if let Some(out) = filename {
-        if !out.is_empty() {
-            options.insert("out".to_string(), serde_json::Value::String(out));
-        }
-    }
-
-    // Construct headers
-    let mut header_list = Vec::new();
-    if let Some(ua) = user_agent {
-        if !ua.is_empty() {
-            options.insert("user-agent".to_string(), serde_json::Value::String(ua));
-        }
-    }
-    if let Some(ref_url) = referer {
-        if !ref_url.is_empty() {
-            options.insert("referer".to_string(), serde_json::Value::String(ref_url));
-        }
-    }
-
-    // Custom Headers and Cookie
-    if let Some(h_str) = headers {
-        // 支持分号或换行符分隔
-        for h in h_str.split(|c| c == ';' || c == '\n') {
-            let trim_h = h.trim();
-            if !trim_h.is_empty() {
-                header_list.push(serde_json::Value::String(trim_h.to_string()));
-            }
-        }
-    }
-
-    if !header_list.is_empty() {
-        options.insert("header".to_string(), serde_json::Value::Array(header_list));
-    }
-
-    if let Some(p) = proxy {
-        if !p.is_empty() {
-            options.insert("all-proxy".to_string(), serde_json::Value::String(p));
-        }
-    }
-
-    if let Some(limit) = max_download_limit {
-        if !limit.is_empty() {
-            options.insert(
-                "max-download-limit".to_string(),
-                serde_json::Value::String(limit),
-            );
-        }
-    }
-
-    (serde_json::Value::Object(options), save_path_str)
-}
-
 pub fn atomic_write(path: &std::path::Path, content: &str) -> std::io::Result<()> {
     // 1. Write to temp file
     // We use .tmp suffix
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Aria2VersionInfo } from "$lib/types/download";
+import type { Aria2VersionInfo, GlobalStats } from "$lib/types/download";
 
 export async function getAria2ConfigPath(): Promise<string> {
 	return invoke<string>("get_aria2_config_path");
@@ -36,3 +36,7 @@ export async function startLogStream(): Promise<void> {
 export async function stopLogStream(): Promise<void> {
 	await invoke("stop_log_stream");
 }
+
+export async function getGlobalStats(): Promise<GlobalStats> {
+	return invoke<GlobalStats>("get_global_stats");
+}
@@ -1,5 +1,15 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { DownloadConfig, DownloadTask } from '$lib/types/download';
+import type { DownloadRule } from '$lib/services/settings/types';
+import type {
+    ChecksumResult,
+    CompletionAction,
+    DownloadConfig,
+    DownloadTask,
+    PeerInfo,
+    RulePreview,
+    ServerInfo,
+    TaskOptionsPatch
+} from '$lib/types/download';
 
 export async function getTasks(): Promise<DownloadTask[]> {
     return invoke<DownloadTask[]>('get_tasks');
@@ -44,3 +54,70 @@ export async function cancelTasks(gids: string[]): Promise<void> {
 export async function showTaskInFolder(gid: string): Promise<void> {
     return invoke('show_task_in_folder', { gid });
 }
+
+export async function changeTaskOptions(gid: string, options: TaskOptionsPatch): Promise<void> {
+    await invoke<void>('change_task_options', { gid, options });
+}
+
+export async function getTaskOptions(gid: string): Promise<Record<string, unknown>> {
+    return invoke<Record<string, unknown>>('get_task_options', { gid });
+}
+
+export async function setTaskSelectedFiles(gid: string, indices: number[]): Promise<void> {
+    await invoke<void>('set_task_selected_files', { gid, indices });
+}
+
+export async function previewDownloadRules(
+    url: string,
+    torrentPath?: string,
+    rules?: DownloadRule[]
+): Promise<RulePreview> {
+    return invoke<RulePreview>('preview_download_rules', {
+        url,
+        torrentPath: torrentPath ?? null,
+        rules: rules ?? null
+    });
+}
+
+export async function setTaskCategory(gid: string, category: string | null): Promise<void> {
+    await invoke<void>('set_task_category', { gid, category });
+}
+
+export async function setTaskTags(gid: string, tags: string[]): Promise<void> {
+    await invoke<void>('set_task_tags', { gid, tags });
+}
+
+export async function setTaskCompletionAction(
+    gid: string,
+    action: CompletionAction | null
+): Promise<void> {
+    await invoke<void>('set_task_completion_action', { gid, action });
+}
+
+export async function verifyTaskFile(gid: string, checksum?: string): Promise<ChecksumResult> {
+    return invoke<ChecksumResult>('verify_task_file', { gid, checksum });
+}
+
+export async function getTaskPeers(gid: string): Promise<PeerInfo[]> {
+    return invoke<PeerInfo[]>('get_task_peers', { gid });
+}
+
+export async function getTaskServers(gid: string): Promise<ServerInfo[]> {
+    return invoke<ServerInfo[]>('get_task_servers', { gid });
+}
+
+export async function moveTaskToTop(gid: string): Promise<number> {
+    return invoke<number>('move_task_to_top', { gid });
+}
+
+export async function moveTaskToBottom(gid: string): Promise<number> {
+    return invoke<number>('move_task_to_bottom', { gid });
+}
+
+export async function moveTaskUp(gid: string): Promise<number> {
+    return invoke<number>('move_task_up', { gid });
+}
+
+export async function moveTaskDown(gid: string): Promise<number> {
+    return invoke<number>('move_task_down', { gid });
+}
@@ -2,3 +2,8 @@ export const EVENT_TASKS_DELTA = 'tasks-delta';
 export const EVENT_ARIA2_STDOUT = 'aria2-stdout';
 export const EVENT_ARIA2_SIDECAR_ERROR = 'aria2-sidecar-error';
 export const EVENT_TASK_COMPLETED = 'task-completed';
+export const EVENT_GLOBAL_STATS = 'global-stats';
+export const EVENT_STORE_RECOVERED = 'store-recovered';
+export const EVENT_BANDWIDTH_PROFILE_CHANGED = 'bandwidth-profile-changed';
+export const EVENT_POWER_ACTION_PENDING = 'power-action-pending';
+export const EVENT_POWER_ACTION_CANCELLED = 'power-action-cancelled';
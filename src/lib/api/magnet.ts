@@ -0,0 +1,6 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { MagnetInfo } from '$lib/types/magnet';
+
+export async function parseMagnet(uri: string): Promise<MagnetInfo> {
+    return invoke<MagnetInfo>('parse_magnet', { uri });
+}
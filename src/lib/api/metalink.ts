@@ -0,0 +1,6 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { MetalinkInfo } from '$lib/types/metalink';
+
+export async function parseMetalink(path: string): Promise<MetalinkInfo> {
+    return invoke<MetalinkInfo>('parse_metalink', { path });
+}
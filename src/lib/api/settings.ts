@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import type { ActiveProfile, RecoveryReport } from '$lib/services/settings/types';
 
 export async function getAppConfig<T>(): Promise<T> {
     return invoke<T>('get_app_config');
@@ -7,3 +8,24 @@ export async function getAppConfig<T>(): Promise<T> {
 export async function saveAppConfig<T>(config: T): Promise<void> {
     await invoke('save_app_config', { config });
 }
+
+export async function getRecoveryReports(): Promise<RecoveryReport[]> {
+    return invoke<RecoveryReport[]>('get_recovery_reports');
+}
+
+export async function getBandwidthProfile(): Promise<ActiveProfile> {
+    return invoke<ActiveProfile>('get_bandwidth_profile');
+}
+
+/** profileId 为 null 时恢复按计划切换 */
+export async function setBandwidthOverride(profileId: string | null): Promise<ActiveProfile> {
+    return invoke<ActiveProfile>('set_bandwidth_override', { profileId });
+}
+
+export async function setTurtleMode(enabled: boolean): Promise<ActiveProfile> {
+    return invoke<ActiveProfile>('set_turtle_mode', { enabled });
+}
+
+export async function cancelPowerAction(): Promise<boolean> {
+    return invoke<boolean>('cancel_power_action');
+}
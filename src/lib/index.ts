@@ -42,3 +42,5 @@ export {
 // Types
 export type { DownloadTask, DownloadConfig, DownloadState, DownloadStats } from './types/download';
 export type { TorrentInfo, TorrentFile } from './types/torrent';
+export type { MetalinkInfo, MetalinkFile } from './types/metalink';
+export type { MagnetInfo } from './types/magnet';
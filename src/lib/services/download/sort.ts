@@ -10,6 +10,13 @@ function compareTasks(a: DownloadTask, b: DownloadTask): number {
     const sb = stateScore(b.state);
     if (sa !== sb) return sb - sa;
 
+    // 等待队列与 aria2 实际顺序一致：未排队的任务在前，排队任务按位置升序
+    const qa = a.queuePosition ?? null;
+    const qb = b.queuePosition ?? null;
+    if (qa !== null && qb !== null && qa !== qb) return qa - qb;
+    if (qa === null && qb !== null) return -1;
+    if (qa !== null && qb === null) return 1;
+
     const byAddedAt = b.addedAt.localeCompare(a.addedAt);
     if (byAddedAt !== 0) return byAddedAt;
     return b.id.localeCompare(a.id);
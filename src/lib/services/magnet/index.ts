@@ -0,0 +1,6 @@
+import { parseMagnet } from '$lib/api/magnet';
+import type { MagnetInfo } from '$lib/types/magnet';
+
+export async function parseMagnetLink(uri: string): Promise<MagnetInfo> {
+    return parseMagnet(uri);
+}
@@ -0,0 +1,6 @@
+import { parseMetalink } from '$lib/api/metalink';
+import type { MetalinkInfo } from '$lib/types/metalink';
+
+export async function parseMetalinkFile(path: string): Promise<MetalinkInfo> {
+    return parseMetalink(path);
+}
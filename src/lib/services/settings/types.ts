@@ -1,3 +1,4 @@
+import type { CompletionAction, TaskErrorKind } from "$lib/types/download";
 import type { ColorMode, ThemeId } from "$lib/types/theme";
 
 export interface AppConfig {
@@ -26,6 +27,151 @@ export interface AppConfig {
 	seedRatio: number;
 	dhtListenPort: string;
 	listenPort: string;
+	remoteBackend?: RemoteBackendConfig;
+	remoteBackends?: RemoteBackendConfig[];
+	retryPolicy?: RetryPolicy;
+	historyRetention?: HistoryRetention;
+	categories?: Category[];
+	rules?: DownloadRule[];
+	bandwidthSchedule?: BandwidthSchedule;
+	altSpeed?: AltSpeedLimits;
+	postCompletion?: PostCompletion;
+}
+
+/**
+ * 全局完成后动作：最后一个进行中的任务完成时执行
+ */
+export interface PostCompletion {
+	/** 睡眠 / 关机前的倒计时秒数，不少于 10 秒 */
+	countdownSecs: number;
+}
+
+/**
+ * 备用限速（乌龟模式），开启时优先于限速计划与手动指定的方案
+ */
+export interface AltSpeedLimits {
+	maxDownloadLimit: string;
+	maxUploadLimit: string;
+}
+
+/**
+ * 每周限速计划：按顺序取第一个覆盖当前时间的时段，均未覆盖时使用全局限速
+ */
+export interface BandwidthSchedule {
+	enabled: boolean;
+	profiles: SpeedProfile[];
+	entries: ScheduleEntry[];
+}
+
+export interface SpeedProfile {
+	id: string;
+	name: string;
+	maxDownloadLimit: string;
+	maxUploadLimit: string;
+	/** 进入该方案时暂停全部任务 */
+	paused: boolean;
+}
+
+/**
+ * 计划时段，end 早于 start 时跨越午夜，两者相同表示全天；days 为 1（周一）到 7（周日），为空表示每天
+ */
+export interface ScheduleEntry {
+	days: number[];
+	start: string;
+	end: string;
+	profileId: string;
+}
+
+/**
+ * 当前生效的限速方案（bandwidth-profile-changed 事件负载）
+ */
+export interface ActiveProfile {
+	id: string;
+	name: string;
+	maxDownloadLimit: string;
+	maxUploadLimit: string;
+	paused: boolean;
+	source: "default" | "schedule" | "manual" | "turtle";
+}
+
+/**
+ * 下载规则：已填写的条件全部满足时命中，按顺序取第一条；
+ * 命中后补全任务未指定的分类、保存目录、限速与代理，并追加请求头
+ */
+export interface DownloadRule {
+	id: string;
+	name: string;
+	enabled: boolean;
+	hosts: string[];
+	extensions: string[];
+	mimeTypes: string[];
+	torrentNamePattern: string;
+	category: string | null;
+	saveDir: string;
+	maxDownloadLimit: string;
+	proxy: string;
+	headers: string[];
+}
+
+/**
+ * 任务分类，新建任务未指定的保存目录、限速与 UA 取分类默认值
+ */
+export interface Category {
+	id: string;
+	name: string;
+	color: string;
+	defaultDir: string;
+	maxDownloadLimit: string;
+	userAgent: string;
+}
+
+/**
+ * 历史任务保留策略，0 表示不限制
+ */
+export interface HistoryRetention {
+	maxAgeDays: number;
+	maxCount: number;
+}
+
+/**
+ * 失败任务自动重试策略，重试间隔从 baseDelaySecs 起指数翻倍，最多 maxDelaySecs
+ */
+export interface RetryPolicy {
+	enabled: boolean;
+	maxAttempts: number;
+	baseDelaySecs: number;
+	maxDelaySecs: number;
+	retryableKinds: TaskErrorKind[];
+}
+
+export interface PathMapping {
+	remote: string;
+	local: string;
+}
+
+export interface RemoteBackendConfig {
+	id: string;
+	name: string;
+	enabled: boolean;
+	host: string;
+	port: number;
+	useTls: boolean;
+	secret?: string | null;
+	pathPrefix: string;
+	pathMappings: PathMapping[];
+}
+
+/**
+ * 持久化文件损坏后的恢复结果（store-recovered 事件负载）
+ */
+export interface RecoveryReport {
+	file: string;
+	quarantined: string | null;
+	restoredFrom: string | null;
+	recovered: number;
+	lost: number;
+	/** 文件完全无法解析，其中的数据全部丢失 */
+	wholeFileLost: boolean;
 }
 
 export type AppSettingsPatch = Partial<AppConfig>;
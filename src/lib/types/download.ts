@@ -14,6 +14,30 @@ export type DownloadState =
 	| "removed"
 	| "missing";
 
+/**
+ * 任务失败原因分类（由 aria2 错误码映射，与后端 TaskErrorKind 保持一致）
+ */
+export type TaskErrorKind =
+	| "unknown"
+	| "timeout"
+	| "not_found"
+	| "too_slow"
+	| "network"
+	| "interrupted"
+	| "resume_unsupported"
+	| "disk_full"
+	| "control_file_mismatch"
+	| "duplicate"
+	| "file_exists"
+	| "file_io"
+	| "name_resolution"
+	| "bad_metadata"
+	| "protocol"
+	| "auth_failed"
+	| "bad_option"
+	| "server_busy"
+	| "checksum_mismatch";
+
 /**
  * 速度信息 - 分离数值与单位，便于 UI 直接渲染
  */
@@ -38,12 +62,105 @@ export interface DownloadTask {
 	addedAt: string;
 	savePath: string;
 	errorMessage?: string;
+	errorKind: TaskErrorKind | null;
+	retryAttempts: number;
+	nextRetryAt: string | null;
 	userAgent?: string;
 	referer?: string;
 	proxy?: string;
 	headers?: string[];
 	maxDownloadLimit?: string;
 	completedAt?: string | null;
+	backendId: string;
+	numSeeders: number;
+	connections: number;
+	bittorrent: BtInfo | null;
+	files: TaskFile[];
+	queuePosition: number | null;
+	category: string | null;
+	tags: string[];
+	/** 定时启动时间（RFC 3339），启动前任务保持暂停 */
+	startAt: string | null;
+	/** 定时停止时间（RFC 3339） */
+	stopAt: string | null;
+	/** 任务完成后的动作 */
+	onComplete: CompletionAction | null;
+	/** 期望的校验值（如 sha-256=<hex>） */
+	checksum: string | null;
+	/** 最近一次校验的结果 */
+	checksumStatus: ChecksumStatus | null;
+}
+
+export type ChecksumStatus = 'verified' | 'mismatch';
+
+/**
+ * 文件校验结果
+ */
+export interface ChecksumResult {
+	status: ChecksumStatus;
+	expected: string;
+	/** 实际计算出的十六进制摘要 */
+	actual: string;
+}
+
+/**
+ * 下载完成后的动作。运行命令支持 {path}、{filename}、{gid} 占位符；
+ * 睡眠与关机在所有任务完成后经过倒计时执行
+ */
+export type CompletionAction =
+	| { type: 'none' }
+	| { type: 'run_command'; command: string }
+	| { type: 'open_file' }
+	| { type: 'reveal_file' }
+	| { type: 'sleep' }
+	| { type: 'shutdown' };
+
+/**
+ * 任务内的单个文件
+ */
+export interface TaskFile {
+	index: number;
+	path: string;
+	length: number;
+	completedLength: number;
+	selected: boolean;
+}
+
+/**
+ * BT 种子元信息
+ */
+export interface BtInfo {
+	name: string | null;
+	infoHash: string | null;
+	comment: string | null;
+	mode: string | null;
+	creationDate: number | null;
+	announceList: string[];
+}
+
+/**
+ * BT 对等节点
+ */
+export interface PeerInfo {
+	ip: string;
+	port: number;
+	client: string;
+	downloadSpeed: number;
+	uploadSpeed: number;
+	seeder: boolean;
+	amChoking: boolean;
+	peerChoking: boolean;
+	progress: number;
+}
+
+/**
+ * HTTP/FTP 服务器连接
+ */
+export interface ServerInfo {
+	fileIndex: number;
+	uri: string;
+	currentUri: string;
+	downloadSpeed: number;
 }
 
 /**
@@ -63,6 +180,31 @@ export interface DownloadConfig {
 		selectFile?: string;
 		trackers?: string;
 	};
+	/** 从本地 .meta4 / .metalink 文件添加 */
+	metalinkConfig?: {
+		path: string;
+		/** 文件序号（从 1 开始，如 1,3-5） */
+		selectFile?: string;
+	};
+	backendId?: string;
+	category?: string;
+	tags?: string[];
+	startAt?: string;
+	stopAt?: string;
+	onComplete?: CompletionAction;
+	/** sha-1=<hex>、sha-256=<hex> 或 md5=<hex> */
+	checksum?: string;
+}
+
+/**
+ * 任务选项修改 - 未提供的字段保持不变，空字符串表示清除
+ */
+export interface TaskOptionsPatch {
+	userAgent?: string;
+	referer?: string;
+	headers?: string;
+	proxy?: string;
+	maxDownloadLimit?: string;
 }
 
 /**
@@ -75,6 +217,33 @@ export interface DownloadStats {
 	completeCount: number;
 }
 
+/**
+ * 下载规则试运行结果
+ */
+export interface RulePreview {
+	subject: {
+		host: string;
+		extension: string;
+		mimeType: string | null;
+		torrentName: string | null;
+	};
+	ruleId: string | null;
+	ruleName: string | null;
+	config: DownloadConfig;
+}
+
+/**
+ * aria2 全局统计（所有后端汇总）
+ */
+export interface GlobalStats {
+	downloadSpeed: number;
+	uploadSpeed: number;
+	numActive: number;
+	numWaiting: number;
+	numStopped: number;
+	numStoppedTotal: number;
+}
+
 /**
  * Aria2 版本信息
  */
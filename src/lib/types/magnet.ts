@@ -0,0 +1,9 @@
+export interface MagnetInfo {
+    /** v1 infohash（40 位小写十六进制） */
+    info_hash: string | null;
+    /** v2 infohash（SHA-256，64 位小写十六进制） */
+    info_hash_v2: string | null;
+    display_name: string | null;
+    trackers: string[];
+    exact_length: number | null;
+}
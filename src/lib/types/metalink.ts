@@ -0,0 +1,25 @@
+export interface MetalinkInfo {
+    files: MetalinkFile[];
+    total_length: number;
+}
+
+export interface MetalinkFile {
+    name: string;
+    length: number | null;
+    hashes: MetalinkHash[];
+    mirrors: MetalinkMirror[];
+    /** 从 1 开始，与 select-file 一致 */
+    index: number;
+}
+
+export interface MetalinkHash {
+    algorithm: string;
+    digest: string;
+}
+
+export interface MetalinkMirror {
+    url: string;
+    location: string | null;
+    priority: number | null;
+    media_type: string | null;
+}